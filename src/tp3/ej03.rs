@@ -30,6 +30,33 @@ impl Fecha {
         Fecha::new(date.day(), date.month(), date.year())
     }
 
+    pub fn get_dia(&self) -> u32 {
+        self.dia
+    }
+
+    pub fn get_mes(&self) -> u32 {
+        self.mes
+    }
+
+    pub fn get_anio(&self) -> i32 {
+        self.anio
+    }
+
+    // Cantidad de dias desde self hasta fecha (negativo si fecha es anterior)
+    pub fn dias_hasta(&self, fecha: &Fecha) -> i64 {
+        fecha.dias_desde_epoca() - self.dias_desde_epoca()
+    }
+
+    // Dias transcurridos desde el 1/3 del año 0 en el calendario gregoriano. Contar los años
+    //desde marzo deja el 29 de febrero al final, lo que evita recorrer los meses
+    fn dias_desde_epoca(&self) -> i64 {
+        let anio = self.anio as i64 - i64::from(self.mes <= 2);
+        let mes = (self.mes as i64 + 9) % 12;
+        let dia_del_anio = (153 * mes + 2) / 5 + self.dia as i64 - 1;
+
+        anio * 365 + anio.div_euclid(4) - anio.div_euclid(100) + anio.div_euclid(400) + dia_del_anio
+    }
+
     fn get_dias_mes(mes: u32) -> u32 {
         let dias_mes = HashMap::from([
             (1, 31),
@@ -221,6 +248,30 @@ fn test_operaciones_fecha() {
     assert!(f1.es_mayor(&f2));
 }
 
+#[test]
+fn test_dias_hasta() {
+    let f1 = Fecha::new(28, 2, 2024);
+    let f2 = Fecha::new(1, 3, 2024);
+
+    assert_eq!(f1.dias_hasta(&f2), 2);
+    assert_eq!(f2.dias_hasta(&f1), -2);
+    assert_eq!(f1.dias_hasta(&f1), 0);
+    assert_eq!(
+        Fecha::new(1, 1, 2024).dias_hasta(&Fecha::new(28, 1, 2048)),
+        8793
+    );
+
+    assert_eq!(
+        Fecha::new(31, 12, 1899).dias_hasta(&Fecha::new(1, 3, 1900)),
+        60
+    );
+    assert_eq!(Fecha::new(1, 1, -1).dias_hasta(&Fecha::new(1, 1, 1)), 731);
+
+    assert_eq!(f2.get_dia(), 1);
+    assert_eq!(f2.get_mes(), 3);
+    assert_eq!(f2.get_anio(), 2024);
+}
+
 #[test]
 fn test_from_chrono() {
     let date = Utc.with_ymd_and_hms(2024, 5, 20, 20, 0, 0);
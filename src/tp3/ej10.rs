@@ -1,5 +1,7 @@
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{hash_map, HashMap},
+    fmt::Display,
     ops::Deref,
     thread::panicking,
 };
@@ -10,19 +12,40 @@ use super::ej03::Fecha;
 struct Biblioteca {
     nombre: String,
     direccion: String,
-    copias: HashMap<u32, u32>,
+    copias: HashMap<Isbn, u32>,
     prestamos: Vec<Prestamo>,
 }
 
 #[derive(Debug)]
 struct Libro {
-    isbn: u32,
+    isbn: Isbn,
     titulo: String,
     autor: String,
     paginas: u32,
     genero: Generos,
 }
 
+// Se guarda normalizado como ISBN-13 sin guiones, que es ademas su formato serializado
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "IsbnArchivo", into = "String")]
+pub struct Isbn {
+    digitos: String,
+}
+
+// Los archivos previos guardaban el isbn como numero
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IsbnArchivo {
+    Texto(String),
+    Numero(u64),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ErrorIsbn {
+    Formato,
+    DigitoVerificador,
+}
+
 #[derive(Debug, Clone)]
 enum Generos {
     Novela,
@@ -58,7 +81,7 @@ impl Biblioteca {
     }
 
     fn agregar_copia(&mut self, libro: Libro) {
-        self.copias.insert(libro.isbn, 0);
+        self.copias.insert(libro.isbn.clone(), 0);
     }
 
     fn obtener_cantidad_copias(&self, libro: &Libro) -> u32 {
@@ -182,7 +205,7 @@ impl Biblioteca {
 impl Clone for Libro {
     fn clone(&self) -> Self {
         Libro::new(
-            self.isbn,
+            self.isbn.clone(),
             self.titulo.clone(),
             self.autor.clone(),
             self.paginas,
@@ -192,7 +215,7 @@ impl Clone for Libro {
 }
 
 impl Libro {
-    fn new(isbn: u32, titulo: String, autor: String, paginas: u32, genero: Generos) -> Libro {
+    fn new(isbn: Isbn, titulo: String, autor: String, paginas: u32, genero: Generos) -> Libro {
         Libro {
             isbn,
             titulo,
@@ -211,6 +234,136 @@ impl Libro {
     }
 }
 
+impl Isbn {
+    // Acepta ISBN-10 e ISBN-13, con o sin guiones
+    pub fn new(texto: &str) -> Result<Isbn, ErrorIsbn> {
+        let isbn: String = texto
+            .chars()
+            .filter(|c| *c != '-' && *c != ' ')
+            .collect::<String>()
+            .to_uppercase();
+
+        // El largo se mide en bytes, por lo que solo se admiten caracteres ASCII
+        if !isbn.is_ascii() {
+            return Err(ErrorIsbn::Formato);
+        }
+
+        match isbn.len() {
+            10 => {
+                let (base, verificador) = isbn.split_at(9);
+
+                if !base.chars().all(|c| c.is_ascii_digit())
+                    || !verificador.chars().all(|c| c.is_ascii_digit() || c == 'X')
+                {
+                    return Err(ErrorIsbn::Formato);
+                }
+
+                if Self::digito_isbn10(base) != verificador {
+                    return Err(ErrorIsbn::DigitoVerificador);
+                }
+
+                let base = format!("978{}", base);
+                let verificador = Self::digito_isbn13(&base);
+
+                Ok(Isbn {
+                    digitos: format!("{}{}", base, verificador),
+                })
+            }
+            13 => {
+                if !isbn.chars().all(|c| c.is_ascii_digit())
+                    || !(isbn.starts_with("978") || isbn.starts_with("979"))
+                {
+                    return Err(ErrorIsbn::Formato);
+                }
+
+                let (base, verificador) = isbn.split_at(12);
+
+                if Self::digito_isbn13(base) != verificador {
+                    return Err(ErrorIsbn::DigitoVerificador);
+                }
+
+                Ok(Isbn { digitos: isbn })
+            }
+            _ => Err(ErrorIsbn::Formato),
+        }
+    }
+
+    // Los isbn numericos no conservaban los ceros a la izquierda, que se completan hasta formar
+    //un ISBN-10. El digito verificador debe ser parte del numero, por lo que un ISBN-10 terminado
+    //en X no puede recuperarse
+    pub fn from_numero(numero: u64) -> Result<Isbn, ErrorIsbn> {
+        Isbn::new(&format!("{:010}", numero))
+    }
+
+    fn digito_isbn10(base: &str) -> String {
+        let suma: u32 = base
+            .chars()
+            .zip((2..11).rev())
+            .map(|(c, peso)| c.to_digit(10).unwrap() * peso)
+            .sum();
+
+        match (11 - suma % 11) % 11 {
+            10 => "X".to_string(),
+            d => d.to_string(),
+        }
+    }
+
+    fn digito_isbn13(base: &str) -> String {
+        let suma: u32 = base
+            .chars()
+            .enumerate()
+            .map(|(i, c)| c.to_digit(10).unwrap() * if i % 2 == 0 { 1 } else { 3 })
+            .sum();
+
+        ((10 - suma % 10) % 10).to_string()
+    }
+
+    pub fn get_isbn13(&self) -> &str {
+        &self.digitos
+    }
+
+    // Solo los ISBN-13 con prefijo 978 tienen equivalente ISBN-10
+    pub fn get_isbn10(&self) -> Option<String> {
+        let base = self.digitos.strip_prefix("978")?.get(..9)?;
+
+        Some(format!("{}{}", base, Self::digito_isbn10(base)))
+    }
+}
+
+impl Display for Isbn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.digitos)
+    }
+}
+
+impl From<Isbn> for String {
+    fn from(isbn: Isbn) -> Self {
+        isbn.digitos
+    }
+}
+
+impl TryFrom<IsbnArchivo> for Isbn {
+    type Error = ErrorIsbn;
+
+    fn try_from(isbn: IsbnArchivo) -> Result<Self, Self::Error> {
+        match isbn {
+            IsbnArchivo::Texto(texto) => Isbn::new(&texto),
+            IsbnArchivo::Numero(numero) => Isbn::from_numero(numero),
+        }
+    }
+}
+
+impl Display for ErrorIsbn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorIsbn::Formato => write!(f, "El isbn no tiene un formato valido"),
+            ErrorIsbn::DigitoVerificador => {
+                write!(f, "El digito verificador del isbn no es valido")
+            }
+        }
+    }
+}
+
 impl Prestamo {
     fn new(libro: Libro, cliente: Cliente, fecha_vencimiento: Fecha) -> Prestamo {
         if !fecha_vencimiento.es_fecha_valida() {
@@ -271,7 +424,7 @@ impl Cliente {
 fn test_biblioteca_vacia() {
     let mut biblioteca = Biblioteca::new("Biblioteca UNLP".to_string(), "Direccion".to_string());
     let l1 = Libro::new(
-        123456,
+        Isbn::new("978-0-452-28423-4").unwrap(),
         "1984".to_string(),
         "Orwell".to_string(),
         320,
@@ -298,21 +451,21 @@ fn test_biblioteca_vacia() {
 fn test_biblioteca1() {
     let mut biblioteca = Biblioteca::new("Biblioteca UNLP".to_string(), "Direccion".to_string());
     let l1 = Libro::new(
-        123456,
+        Isbn::new("978-0-452-28423-4").unwrap(),
         "1984".to_string(),
         "Orwell".to_string(),
         320,
         Generos::Novela,
     );
     let l2 = Libro::new(
-        78910,
+        Isbn::new("1-4215-2772-3").unwrap(),
         "Battle Royale".to_string(),
         "Koushun Takami".to_string(),
         550,
         Generos::Otros,
     );
     let l3 = Libro::new(
-        111213,
+        Isbn::new("978-0-547-92822-7").unwrap(),
         "El Hobbit".to_string(),
         "Tolkien".to_string(),
         255,
@@ -415,7 +568,7 @@ fn test_biblioteca1() {
 #[test]
 fn test_estructuras_secundarias() {
     let libro1 = Libro {
-        isbn: 12345,
+        isbn: Isbn::new("0-8044-2957-X").unwrap(),
         titulo: "example".to_string(),
         autor: "autor".to_string(),
         paginas: 77,
@@ -431,3 +584,68 @@ fn test_estructuras_secundarias() {
 
     assert_eq!(prestamo.get_cliente().nombre, "Name");
 }
+
+#[test]
+fn test_isbn() {
+    // ISBN-13 e ISBN-10 del mismo libro
+
+    let isbn13 = Isbn::new("978-0-452-28423-4").unwrap();
+    let isbn10 = Isbn::new("0 452 28423 6").unwrap();
+
+    assert_eq!(isbn13, isbn10);
+    assert_eq!(isbn13.get_isbn13(), "9780452284234");
+    assert_eq!(isbn13.get_isbn10(), Some("0452284236".to_string()));
+    assert_eq!(isbn13.to_string(), "9780452284234");
+
+    // Digito verificador X
+
+    let isbn = Isbn::new("0-8044-2957-x").unwrap();
+    assert_eq!(isbn.get_isbn10(), Some("080442957X".to_string()));
+
+    // Los ISBN-13 con prefijo 979 no tienen equivalente ISBN-10
+
+    let isbn = Isbn::new("979-10-90636-07-1").unwrap();
+    assert!(isbn.get_isbn10().is_none());
+
+    // Errores
+
+    assert_eq!(
+        Isbn::new("978-0-452-28423-5"),
+        Err(ErrorIsbn::DigitoVerificador)
+    );
+    assert_eq!(
+        Isbn::new("0-452-28423-7"),
+        Err(ErrorIsbn::DigitoVerificador)
+    );
+    assert_eq!(Isbn::new("977-0-452-28423-4"), Err(ErrorIsbn::Formato));
+    assert_eq!(Isbn::new("0-45X-28423-8"), Err(ErrorIsbn::Formato));
+    assert_eq!(Isbn::new("12345"), Err(ErrorIsbn::Formato));
+    assert_eq!(Isbn::new("12345678é"), Err(ErrorIsbn::Formato));
+    assert_eq!(Isbn::new("978045228423é"), Err(ErrorIsbn::Formato));
+    println!("{} - {}", ErrorIsbn::Formato, ErrorIsbn::DigitoVerificador);
+
+    // Isbn numericos de archivos previos
+
+    assert_eq!(Isbn::from_numero(452284236).unwrap(), isbn13);
+    assert_eq!(Isbn::from_numero(9780452284234).unwrap(), isbn13);
+
+    // No se inventa un digito verificador para los numeros que no son un isbn completo
+
+    assert_eq!(
+        Isbn::from_numero(45228423),
+        Err(ErrorIsbn::DigitoVerificador)
+    );
+    assert_eq!(
+        Isbn::from_numero(9780452284235),
+        Err(ErrorIsbn::DigitoVerificador)
+    );
+    assert_eq!(Isbn::from_numero(97804522842), Err(ErrorIsbn::Formato));
+
+    // Serializacion
+
+    let json = serde_json::to_string(&isbn13).unwrap();
+    assert_eq!(json, "\"9780452284234\"");
+    assert_eq!(serde_json::from_str::<Isbn>(&json).unwrap(), isbn13);
+    assert_eq!(serde_json::from_str::<Isbn>("452284236").unwrap(), isbn13);
+    assert!(serde_json::from_str::<Isbn>("\"0-452-28423-7\"").is_err());
+}
@@ -1,14 +1,33 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Error;
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::io;
 use std::{fmt::Display, fs::File, fs::OpenOptions, io::prelude::*, path::Path};
 
+mod busqueda;
+mod formatos;
+mod historial;
+mod inteligentes;
+mod mezcla;
+mod reproduccion;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct Cancion {
     titulo: String,
     artista: String,
     genero: Generos,
+    // Duracion en segundos, 0 si se desconoce
+    #[serde(default)]
+    duracion: u32,
+    #[serde(default)]
+    album: Option<String>,
+    #[serde(default)]
+    numero_pista: Option<u32>,
+    #[serde(default)]
+    anio: Option<u32>,
+    #[serde(default)]
+    path_archivo: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -30,10 +49,137 @@ impl Generos {
     }
 }
 
+// Condicion sobre una cancion. Las reglas se combinan para definir playlists inteligentes,
+//por ejemplo: genero Rock o Pop y artista distinto de X
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+enum Regla {
+    GeneroEn(Vec<Generos>),
+    Artista(String),
+    No(Box<Regla>),
+    Todas(Vec<Regla>),
+    Alguna(Vec<Regla>),
+}
+
+// Sus canciones se calculan a partir de la playlist cada vez que se consultan, por lo que solo
+//se guarda la regla
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct PlaylistInteligente {
+    nombre: String,
+    regla: Regla,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CampoOrden {
+    Titulo,
+    Artista,
+    Genero,
+    Duracion,
+    Album,
+    NumeroPista,
+    Anio,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ModoMezcla {
+    Aleatorio,
+    SinRepetirArtista,
+}
+
+// Calidad de una coincidencia de busqueda, de mejor a peor. La aproximada guarda la
+//distancia de edicion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Coincidencia {
+    Exacta,
+    Prefijo,
+    Aproximada(usize),
+}
+
+// Formatos de otros reproductores que se pueden importar y exportar
+#[derive(Debug, Clone, PartialEq)]
+enum FormatoPlaylist {
+    M3u,
+    Pls,
+    Xspf,
+}
+
+// Entrada importada como Otros porque su genero falta o no corresponde a ningun Generos
+#[derive(Debug, Clone, PartialEq)]
+struct EntradaSinGenero {
+    posicion: usize,
+    titulo: String,
+    genero: Option<String>,
+}
+
+#[derive(Debug)]
+struct ReporteImportacion {
+    importadas: usize,
+    sin_genero: Vec<EntradaSinGenero>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+enum ModoRepeticion {
+    Ninguna,
+    Una,
+    Todas,
+}
+
+// Estado de reproduccion armado a partir de una playlist. Se guarda completo en su archivo
+//para poder retomar la reproduccion desde la misma posicion. Las reproducciones se guardan
+//junto a la playlist, para que se sigan contando en las proximas colas
+#[derive(Debug, Serialize, Deserialize)]
+struct ColaReproduccion {
+    canciones: Vec<Cancion>,
+    actual: Option<usize>,
+    // Segundos reproducidos de la cancion actual
+    posicion: u32,
+    repeticion: ModoRepeticion,
+    // La cancion mas reciente primero
+    historial: VecDeque<Cancion>,
+    #[serde(skip)]
+    reproducciones: Vec<(Cancion, u32)>,
+    path_reproducciones: String,
+    #[serde(skip)]
+    path_file: String,
+}
+
+// Cambio aplicado a una playlist, con los datos necesarios para deshacerlo
+#[derive(Debug, Serialize, Deserialize, Clone)]
+enum Edicion {
+    Agregar(Cancion),
+    Eliminar {
+        cancion: Cancion,
+        index: usize,
+    },
+    Mover {
+        desde: usize,
+        hasta: usize,
+    },
+    ModificarTitulo {
+        anterior: String,
+        nuevo: String,
+    },
+    // Cambios que afectan a toda la lista, como vaciarla u ordenarla
+    Reemplazar {
+        anteriores: Vec<Cancion>,
+        nuevas: Vec<Cancion>,
+    },
+}
+
+// Cada paso es un grupo de ediciones que se deshacen y rehacen juntas
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistorialEdiciones {
+    deshacer: Vec<Vec<Edicion>>,
+    rehacer: Vec<Vec<Edicion>>,
+    #[serde(skip)]
+    grupo: Option<Vec<Edicion>>,
+}
+
 struct Playlist {
     nombre: String,
     canciones: VecDeque<Cancion>,
     path_file: String,
+    inteligentes: Vec<PlaylistInteligente>,
+    historial: HistorialEdiciones,
 }
 
 impl Cancion {
@@ -42,7 +188,33 @@ impl Cancion {
             titulo,
             artista,
             genero,
+            duracion: 0,
+            album: None,
+            numero_pista: None,
+            anio: None,
+            path_archivo: None,
+        }
+    }
+
+    fn set_duracion(&mut self, minutos: u32, segundos: u32) {
+        if segundos >= 60 {
+            panic!("Duracion no valida");
         }
+
+        self.duracion = minutos * 60 + segundos;
+    }
+
+    fn set_album(&mut self, album: String, numero_pista: Option<u32>) {
+        self.album = Some(album);
+        self.numero_pista = numero_pista;
+    }
+
+    fn set_anio(&mut self, anio: u32) {
+        self.anio = Some(anio);
+    }
+
+    fn set_path_archivo(&mut self, path_archivo: String) {
+        self.path_archivo = Some(path_archivo);
     }
 
     fn to_string(&self) -> String {
@@ -60,9 +232,33 @@ impl Playlist {
             nombre,
             canciones,
             path_file,
+            inteligentes: Vec::new(),
+            historial: HistorialEdiciones::default(),
         }
     }
 
+    // Carga las canciones, el historial de ediciones y las playlists inteligentes guardados
+    fn recuperar(nombre: String, path_file: String) -> Result<Playlist, ErrorPlaylist> {
+        let Ok(mut f) = File::open(&path_file) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(canciones) = serde_json::from_str::<VecDeque<Cancion>>(&buf) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+
+        let mut playlist = Playlist::new(nombre, canciones, path_file);
+        playlist.recuperar_historial()?;
+        playlist.recuperar_inteligentes()?;
+
+        Ok(playlist)
+    }
+
     fn build_archivo(&self, canciones: &VecDeque<Cancion>) -> Result<(), ErrorPlaylist> {
         let file = File::create(&self.path_file);
 
@@ -83,9 +279,9 @@ impl Playlist {
     }
 
     fn agregar_cancion(&mut self, cancion: Cancion) -> Result<(), ErrorPlaylist> {
-        self.canciones.push_front(cancion);
+        self.canciones.push_front(cancion.clone());
 
-        self.build_archivo(&self.canciones)
+        self.registrar(Edicion::Agregar(cancion))
     }
 
     fn buscar_cancion(&self, cancion: &Cancion) -> Option<usize> {
@@ -99,9 +295,9 @@ impl Playlist {
 
     fn eliminar_cancion(&mut self, cancion: &Cancion) -> Result<(), ErrorPlaylist> {
         if let Some(index) = self.buscar_cancion(cancion) {
-            self.canciones.remove(index);
+            let cancion = self.canciones.remove(index).unwrap();
 
-            return self.build_archivo(&self.canciones);
+            return self.registrar(Edicion::Eliminar { cancion, index });
         }
 
         Ok(())
@@ -111,13 +307,13 @@ impl Playlist {
         if let Some(index) = self.buscar_cancion(cancion) {
             let song = self.canciones.remove(index);
 
-            if new_index >= self.canciones.len() {
-                self.canciones.push_back(song.unwrap())
-            } else {
-                self.canciones.insert(new_index, song.unwrap());
-            }
+            let hasta = new_index.min(self.canciones.len());
+            self.canciones.insert(hasta, song.unwrap());
 
-            return self.build_archivo(&self.canciones);
+            return self.registrar(Edicion::Mover {
+                desde: index,
+                hasta,
+            });
         }
 
         Ok(())
@@ -160,12 +356,96 @@ impl Playlist {
         songs
     }
 
-    fn modificar_titulo(&mut self, new_title: String) {
-        self.nombre = new_title;
+    // Duracion total en segundos
+    fn duracion_total(&self) -> u32 {
+        self.canciones.iter().map(|c| c.duracion).sum()
     }
 
-    fn clear(&mut self) {
-        self.canciones.clear();
+    // Conserva las primeras canciones que entran en la cantidad de minutos dada y devuelve
+    //las que se quitaron
+    fn recortar_a_minutos(&mut self, minutos: u32) -> Result<Vec<Cancion>, ErrorPlaylist> {
+        let limite = minutos * 60;
+        let mut acumulado = 0;
+
+        let Some(index) = self.canciones.iter().position(|c| {
+            acumulado += c.duracion;
+            acumulado > limite
+        }) else {
+            return Ok(Vec::new());
+        };
+
+        let anteriores: Vec<Cancion> = self.canciones.iter().cloned().collect();
+        let quitadas = self.canciones.split_off(index).into_iter().collect();
+        self.registrar(Edicion::Reemplazar {
+            anteriores,
+            nuevas: self.canciones.iter().cloned().collect(),
+        })?;
+
+        Ok(quitadas)
+    }
+
+    // El orden es estable y las canciones sin el dato quedan al final
+    fn ordenar_por(&mut self, campo: &CampoOrden, ascendente: bool) -> Result<(), ErrorPlaylist> {
+        let anteriores: Vec<Cancion> = self.canciones.iter().cloned().collect();
+
+        self.canciones.make_contiguous().sort_by(|a, b| {
+            let orden = match campo {
+                CampoOrden::Titulo => a.titulo.cmp(&b.titulo),
+                CampoOrden::Artista => a.artista.cmp(&b.artista),
+                CampoOrden::Genero => a.genero.to_string().cmp(&b.genero.to_string()),
+                CampoOrden::Duracion => a.duracion.cmp(&b.duracion),
+                CampoOrden::Album => comparar_opcionales(&a.album, &b.album, ascendente),
+                CampoOrden::NumeroPista => {
+                    comparar_opcionales(&a.numero_pista, &b.numero_pista, ascendente)
+                }
+                CampoOrden::Anio => comparar_opcionales(&a.anio, &b.anio, ascendente),
+            };
+
+            if ascendente {
+                orden
+            } else {
+                orden.reverse()
+            }
+        });
+
+        self.registrar(Edicion::Reemplazar {
+            anteriores,
+            nuevas: self.canciones.iter().cloned().collect(),
+        })
+    }
+
+    fn modificar_titulo(&mut self, new_title: String) -> Result<(), ErrorPlaylist> {
+        let anterior = std::mem::replace(&mut self.nombre, new_title.clone());
+
+        self.registrar(Edicion::ModificarTitulo {
+            anterior,
+            nuevo: new_title,
+        })
+    }
+
+    fn clear(&mut self) -> Result<(), ErrorPlaylist> {
+        let anteriores = self.canciones.drain(..).collect();
+
+        self.registrar(Edicion::Reemplazar {
+            anteriores,
+            nuevas: Vec::new(),
+        })
+    }
+}
+
+// Ubica los valores faltantes al final sin importar el sentido del orden
+fn comparar_opcionales<T: Ord>(a: &Option<T>, b: &Option<T>, ascendente: bool) -> Ordering {
+    let faltante = if ascendente {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    };
+
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => faltante.reverse(),
+        (None, Some(_)) => faltante,
+        (None, None) => Ordering::Equal,
     }
 }
 
@@ -174,6 +454,10 @@ enum ErrorPlaylist {
     AbrirArchivo,
     FormatearCanciones,
     EscribirArchivo,
+    PlaylistDuplicada,
+    PlaylistInexistente,
+    FormatoInvalido,
+    HistorialInvalido,
 }
 
 impl Display for ErrorPlaylist {
@@ -184,6 +468,24 @@ impl Display for ErrorPlaylist {
                 write!(f, "Error al intentar cambiar formato de cancion a string")
             }
             ErrorPlaylist::EscribirArchivo => write!(f, "Error al intentar escribir el archivo"),
+            ErrorPlaylist::PlaylistDuplicada => {
+                write!(f, "Ya existe una playlist inteligente con ese nombre")
+            }
+            ErrorPlaylist::PlaylistInexistente => {
+                write!(f, "No existe una playlist inteligente con ese nombre")
+            }
+            ErrorPlaylist::FormatoInvalido => {
+                write!(
+                    f,
+                    "El contenido no corresponde al formato de playlist indicado"
+                )
+            }
+            ErrorPlaylist::HistorialInvalido => {
+                write!(
+                    f,
+                    "El historial no corresponde a las canciones de la playlist"
+                )
+            }
         }
     }
 }
@@ -243,7 +545,7 @@ mod test {
         assert_eq!(p.get_canciones_por_artista(&c2.artista).len(), 2); // Vec con canciones del mismo artista
         assert_eq!(p.get_canciones_por_genero(&c4.genero).len(), 2); // Vec con canciones del mismo género
 
-        p.modificar_titulo("Modified title".to_string());
+        assert!(p.modificar_titulo("Modified title".to_string()).is_err()); // Path de archivo invalido
         assert_eq!(p.nombre, "Modified title");
 
         assert!(p.mover_cancion(&c2, 0).is_err()); // Path de archivo invalido
@@ -257,7 +559,7 @@ mod test {
         assert_eq!(p.canciones.len(), 4);
         assert_eq!(p.canciones.get(3).unwrap().titulo, "Cancion1");
 
-        p.clear();
+        assert!(p.clear().is_err());
         assert_eq!(p.canciones.len(), 0);
     }
 
@@ -377,4 +679,120 @@ mod test {
             }
         };
     }
+
+    fn crear_cancion(titulo: &str, artista: &str, minutos: u32, segundos: u32) -> Cancion {
+        let mut c = Cancion::new(titulo.to_string(), artista.to_string(), Generos::Rock);
+        c.set_duracion(minutos, segundos);
+        c
+    }
+
+    #[test]
+    fn test_duracion_y_recorte() {
+        let canciones = VecDeque::from(vec![
+            crear_cancion("Cancion1", "Artista1", 4, 30),
+            crear_cancion("Cancion2", "Artista2", 3, 15),
+            crear_cancion("Cancion3", "Artista1", 5, 0),
+            crear_cancion("Cancion4", "Artista3", 1, 0),
+        ]);
+        let mut p = Playlist::new(
+            "Playlist".to_string(),
+            canciones,
+            "test_files/canciones_duracion.json".to_string(),
+        );
+
+        assert_eq!(p.duracion_total(), 825);
+
+        // Entran las dos primeras, aunque la cuarta tambien entraria sola
+
+        let quitadas = p.recortar_a_minutos(10).unwrap();
+        assert_eq!(p.canciones.len(), 2);
+        assert_eq!(p.duracion_total(), 465);
+        assert_eq!(quitadas.len(), 2);
+        assert_eq!(quitadas[0].titulo, "Cancion3");
+
+        assert!(p.recortar_a_minutos(60).unwrap().is_empty());
+        assert_eq!(p.canciones.len(), 2);
+
+        assert_eq!(p.recortar_a_minutos(0).unwrap().len(), 2);
+        assert_eq!(p.duracion_total(), 0);
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_duracion_no_valida() {
+        crear_cancion("Cancion", "Artista", 3, 75);
+    }
+
+    #[test]
+    fn test_ordenar_por() {
+        let mut c1 = crear_cancion("B", "Artista2", 3, 0);
+        c1.set_album("Album1".to_string(), Some(2));
+        c1.set_anio(1999);
+        let mut c2 = crear_cancion("A", "Artista1", 4, 0);
+        c2.set_album("Album1".to_string(), Some(1));
+        c2.set_path_archivo("/musica/a.mp3".to_string());
+        let mut c3 = Cancion::new("C".to_string(), "Artista1".to_string(), Generos::Jazz);
+        c3.set_anio(2005);
+
+        let mut p = Playlist::new(
+            "Playlist".to_string(),
+            VecDeque::from(vec![c1, c2, c3]),
+            "test_files/canciones_orden.json".to_string(),
+        );
+
+        let titulos =
+            |p: &Playlist| -> String { p.canciones.iter().map(|c| c.titulo.as_str()).collect() };
+
+        assert!(p.ordenar_por(&CampoOrden::Titulo, true).is_ok());
+        assert_eq!(titulos(&p), "ABC");
+        assert!(p.ordenar_por(&CampoOrden::Duracion, false).is_ok());
+        assert_eq!(titulos(&p), "ABC");
+        assert!(p.ordenar_por(&CampoOrden::Genero, true).is_ok());
+        assert_eq!(titulos(&p), "CAB");
+
+        // Estable: ante el mismo artista se conserva el orden previo
+
+        assert!(p.ordenar_por(&CampoOrden::Artista, true).is_ok());
+        assert_eq!(titulos(&p), "CAB");
+
+        // Los datos faltantes quedan al final en ambos sentidos
+
+        assert!(p.ordenar_por(&CampoOrden::NumeroPista, true).is_ok());
+        assert_eq!(titulos(&p), "ABC");
+        assert!(p.ordenar_por(&CampoOrden::Anio, false).is_ok());
+        assert_eq!(titulos(&p), "CBA");
+        assert!(p.ordenar_por(&CampoOrden::Album, false).is_ok());
+        assert_eq!(titulos(&p), "BAC");
+
+        // Se guarda el nuevo orden con los metadatos
+
+        let c = abrir_archivo(&p.path_file).unwrap();
+        assert_eq!(c.front().unwrap().anio, Some(1999));
+        assert_eq!(
+            c.get(1).unwrap().path_archivo.as_deref(),
+            Some("/musica/a.mp3")
+        );
+
+        let mut invalida =
+            Playlist::new("Playlist".to_string(), p.canciones.clone(), "/".to_string());
+        assert!(invalida.ordenar_por(&CampoOrden::Titulo, true).is_err());
+        assert_eq!(titulos(&invalida), "ABC");
+    }
+
+    #[test]
+    fn test_archivo_sin_metadatos() {
+        // Formato anterior, sin duracion, album, pista, anio ni path
+
+        let json = r#"[{"titulo":"Cancion1","artista":"Artista1","genero":"Pop"}]"#;
+        let c: VecDeque<Cancion> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(c[0].duracion, 0);
+        assert!(c[0].album.is_none() && c[0].numero_pista.is_none());
+        assert!(c[0].anio.is_none() && c[0].path_archivo.is_none());
+        assert!(c[0].eq(&Cancion::new(
+            "Cancion1".to_string(),
+            "Artista1".to_string(),
+            Generos::Pop
+        )));
+    }
 }
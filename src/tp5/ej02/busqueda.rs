@@ -0,0 +1,274 @@
+use super::{Cancion, Coincidencia, Playlist};
+
+// Diferencia maxima de duracion, en segundos, entre dos versiones de la misma cancion
+const TOLERANCIA_DURACION: u32 = 5;
+
+// Pasa a minusculas, quita acentos y deja las palabras separadas por un solo espacio
+fn normalizar(texto: &str) -> String {
+    let plano: String = texto
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' | 'â' | 'ã' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' | 'õ' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ñ' => 'n',
+            'ç' => 'c',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+
+    plano.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+// Distancia de Levenshtein: cantidad minima de caracteres a insertar, borrar o reemplazar
+fn distancia_edicion(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut fila: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = fila[0];
+        fila[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let reemplazo = diagonal + usize::from(ca != *cb);
+            diagonal = fila[j + 1];
+            fila[j + 1] = reemplazo.min(fila[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    fila[b.len()]
+}
+
+// Compara una consulta ya normalizada contra un texto, completo o por palabras
+fn comparar(consulta: &str, texto: &str) -> Option<Coincidencia> {
+    let texto = normalizar(texto);
+
+    if texto == consulta {
+        return Some(Coincidencia::Exacta);
+    }
+    if texto.starts_with(consulta) || texto.split(' ').any(|p| p.starts_with(consulta)) {
+        return Some(Coincidencia::Prefijo);
+    }
+
+    let tolerancia = (consulta.chars().count() / 3).max(1);
+    let distancia = texto
+        .split(' ')
+        .map(|p| distancia_edicion(consulta, p))
+        .chain([distancia_edicion(consulta, &texto)])
+        .min()?;
+
+    (distancia <= tolerancia).then_some(Coincidencia::Aproximada(distancia))
+}
+
+fn digitos(texto: &str) -> String {
+    texto.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+impl Cancion {
+    // Mismo artista y titulo salvo diferencias menores de escritura. Los numeros del titulo
+    //deben coincidir, para no confundir partes o pistas distintas de una misma obra. La
+    //tolerancia depende del titulo mas largo, para que la comparacion sea simetrica
+    fn es_casi_igual(&self, otra: &Cancion) -> bool {
+        if normalizar(&self.artista) != normalizar(&otra.artista) {
+            return false;
+        }
+
+        if self.duracion > 0
+            && otra.duracion > 0
+            && self.duracion.abs_diff(otra.duracion) > TOLERANCIA_DURACION
+        {
+            return false;
+        }
+
+        let titulo = normalizar(&self.titulo);
+        let otro_titulo = normalizar(&otra.titulo);
+
+        let largo = titulo.chars().count().max(otro_titulo.chars().count());
+
+        digitos(&titulo) == digitos(&otro_titulo)
+            && distancia_edicion(&titulo, &otro_titulo) <= largo / 8
+    }
+}
+
+impl Playlist {
+    // Busca por titulo, artista o ambos, sin distinguir mayusculas ni acentos. Devuelve las
+    //canciones ordenadas de mejor a peor coincidencia, respetando el orden de la playlist
+    fn buscar(&self, consulta: &str) -> Vec<(&Cancion, Coincidencia)> {
+        let consulta = normalizar(consulta);
+        if consulta.is_empty() {
+            return Vec::new();
+        }
+
+        let mut resultados: Vec<(&Cancion, Coincidencia)> = self
+            .canciones
+            .iter()
+            .filter_map(|c| {
+                let completo = format!("{} {}", c.artista, c.titulo);
+
+                [&c.titulo, &c.artista, &completo]
+                    .iter()
+                    .filter_map(|texto| comparar(&consulta, texto))
+                    .min()
+                    .map(|coincidencia| (c, coincidencia))
+            })
+            .collect();
+        resultados.sort_by_key(|(_, coincidencia)| *coincidencia);
+
+        resultados
+    }
+
+    // Pares de posiciones de canciones casi iguales dentro de la playlist
+    fn buscar_duplicados(&self) -> Vec<(usize, usize)> {
+        let mut duplicados = Vec::new();
+
+        for (i, a) in self.canciones.iter().enumerate() {
+            for (j, b) in self.canciones.iter().enumerate().skip(i + 1) {
+                if a.es_casi_igual(b) {
+                    duplicados.push((i, j));
+                }
+            }
+        }
+
+        duplicados
+    }
+
+    // Pares de posiciones (en esta playlist, en la otra) de canciones casi iguales
+    fn duplicados_con(&self, otra: &Playlist) -> Vec<(usize, usize)> {
+        let mut duplicados = Vec::new();
+
+        for (i, a) in self.canciones.iter().enumerate() {
+            for (j, b) in otra.canciones.iter().enumerate() {
+                if a.es_casi_igual(b) {
+                    duplicados.push((i, j));
+                }
+            }
+        }
+
+        duplicados
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Generos;
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn crear_playlist(canciones: &[(&str, &str)]) -> Playlist {
+        let canciones = canciones
+            .iter()
+            .map(|(titulo, artista)| {
+                Cancion::new(titulo.to_string(), artista.to_string(), Generos::Rock)
+            })
+            .collect::<VecDeque<Cancion>>();
+
+        Playlist::new("Playlist".to_string(), canciones, Default::default())
+    }
+
+    fn titulos<'a>(resultados: &[(&'a Cancion, Coincidencia)]) -> Vec<&'a str> {
+        resultados.iter().map(|(c, _)| c.titulo.as_str()).collect()
+    }
+
+    #[test]
+    fn test_normalizar_y_distancia() {
+        assert_eq!(normalizar("  Canción  DE  Otoño!! "), "cancion de otono");
+        assert_eq!(normalizar("AC/DC"), "ac dc");
+
+        assert_eq!(distancia_edicion("", "abc"), 3);
+        assert_eq!(distancia_edicion("kitten", "sitting"), 3);
+        assert_eq!(distancia_edicion("rapsody", "rhapsody"), 1);
+        assert_eq!(distancia_edicion("igual", "igual"), 0);
+    }
+
+    #[test]
+    fn test_buscar() {
+        let p = crear_playlist(&[
+            ("Bohemian Rhapsody", "Queen"),
+            ("La Bohème", "Charles Aznavour"),
+            ("Canción Animal", "Soda Stereo"),
+            ("De Música Ligera", "Soda Stereo"),
+            ("Another One Bites the Dust", "Queen"),
+            ("Rapsodia", "Otro"),
+            ("Rapsody", "Otro"),
+        ]);
+
+        // Sin distinguir mayusculas ni acentos
+
+        let r = p.buscar("cancion animal");
+        assert_eq!(titulos(&r), vec!["Canción Animal"]);
+        assert_eq!(r[0].1, Coincidencia::Exacta);
+
+        let r = p.buscar("QUEEN");
+        assert_eq!(
+            titulos(&r),
+            vec!["Bohemian Rhapsody", "Another One Bites the Dust"]
+        );
+        assert!(r.iter().all(|(_, c)| *c == Coincidencia::Exacta));
+
+        let r = p.buscar("boh");
+        assert_eq!(titulos(&r), vec!["Bohemian Rhapsody", "La Bohème"]);
+        assert!(r.iter().all(|(_, c)| *c == Coincidencia::Prefijo));
+
+        // Las exactas primero, luego los prefijos y al final las aproximadas
+
+        let r = p.buscar("rapsody");
+        assert_eq!(
+            titulos(&r),
+            vec!["Rapsody", "Bohemian Rhapsody", "Rapsodia"]
+        );
+        assert_eq!(
+            r.iter().map(|(_, c)| *c).collect::<Vec<Coincidencia>>(),
+            vec![
+                Coincidencia::Exacta,
+                Coincidencia::Aproximada(1),
+                Coincidencia::Aproximada(2)
+            ]
+        );
+
+        let r = p.buscar("soda stereo musica ligera");
+        assert_eq!(r[0].0.titulo, "De Música Ligera");
+
+        assert!(p.buscar("Metallica").is_empty());
+        assert!(p.buscar("  ¿? ").is_empty());
+    }
+
+    #[test]
+    fn test_duplicados() {
+        let mut p = crear_playlist(&[
+            ("Bohemian Rhapsody", "Queen"),
+            ("Bohemian Rapsody", "QUEEN"),
+            ("Track 1", "Artista"),
+            ("Track 2", "Artista"),
+            ("Bohemian Rhapsody", "Panic! at the Disco"),
+            ("bohemian rhapsody!", "Queen"),
+        ]);
+
+        assert_eq!(p.buscar_duplicados(), vec![(0, 1), (0, 5), (1, 5)]);
+
+        // Con duraciones muy distintas son versiones diferentes
+
+        p.canciones[0].set_duracion(5, 55);
+        p.canciones[5].set_duracion(6, 30);
+        assert_eq!(p.buscar_duplicados(), vec![(0, 1), (1, 5)]);
+
+        let otra = crear_playlist(&[("Track 1", "ARTISTA"), ("Bohemian Rhapsody", "Queen")]);
+        assert_eq!(
+            p.duplicados_con(&otra),
+            vec![(0, 1), (1, 1), (2, 0), (5, 1)]
+        );
+        assert_eq!(
+            otra.duplicados_con(&p),
+            vec![(0, 2), (1, 0), (1, 1), (1, 5)]
+        );
+
+        // El resultado no depende del orden de la comparacion
+
+        let p = crear_playlist(&[("Paradis", "Coldplay"), ("Paradise", "Coldplay")]);
+        assert!(p.canciones[0].es_casi_igual(&p.canciones[1]));
+        assert!(p.canciones[1].es_casi_igual(&p.canciones[0]));
+    }
+}
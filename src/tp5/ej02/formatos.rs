@@ -0,0 +1,560 @@
+use std::collections::BTreeMap;
+use std::{fs::File, io::prelude::*, path::Path};
+
+use super::{
+    Cancion, Edicion, EntradaSinGenero, ErrorPlaylist, FormatoPlaylist, Generos, Playlist,
+    ReporteImportacion,
+};
+
+// Datos de una entrada tal como aparecen en el archivo importado
+#[derive(Default)]
+struct Entrada {
+    titulo: String,
+    artista: String,
+    genero: Option<String>,
+    duracion: u32,
+    ubicacion: Option<String>,
+}
+
+impl Generos {
+    fn desde_nombre(nombre: &str) -> Option<Generos> {
+        match nombre.trim().to_lowercase().as_str() {
+            "rock" => Some(Generos::Rock),
+            "pop" => Some(Generos::Pop),
+            "rap" => Some(Generos::Rap),
+            "jazz" => Some(Generos::Jazz),
+            "otros" => Some(Generos::Otros),
+            _ => None,
+        }
+    }
+}
+
+// Nombre visible en M3U y PLS, que no tienen campos separados para titulo y artista
+fn nombre_visible(artista: &str, titulo: &str) -> String {
+    if artista.is_empty() {
+        titulo.to_string()
+    } else {
+        format!("{} - {}", artista, titulo)
+    }
+}
+
+impl Cancion {
+    fn nombre_visible(&self) -> String {
+        nombre_visible(&self.artista, &self.titulo)
+    }
+
+    // M3U y PLS requieren una ubicacion para cada entrada. Sin archivo, se usa el nombre visible
+    fn ubicacion(&self) -> String {
+        match &self.path_archivo {
+            Some(path) => path.clone(),
+            None => self.nombre_visible(),
+        }
+    }
+
+    // M3U y PLS usan -1 para una duracion desconocida
+    fn duracion_visible(&self) -> i64 {
+        if self.duracion == 0 {
+            -1
+        } else {
+            self.duracion as i64
+        }
+    }
+}
+
+impl Entrada {
+    // Duracion en segundos, donde -1 indica que se desconoce
+    fn set_duracion(&mut self, duracion: &str) -> Result<(), ErrorPlaylist> {
+        let Ok(duracion) = duracion.trim().parse::<i64>() else {
+            return Err(ErrorPlaylist::FormatoInvalido);
+        };
+        self.duracion = duracion.max(0) as u32;
+
+        Ok(())
+    }
+
+    fn set_nombre_visible(&mut self, nombre: &str) {
+        match nombre.split_once(" - ") {
+            Some((artista, titulo)) => {
+                self.artista = artista.trim().to_string();
+                self.titulo = titulo.trim().to_string();
+            }
+            None => self.titulo = nombre.trim().to_string(),
+        }
+    }
+
+    // Sin titulo, se usa el nombre del archivo sin extension. Una ubicacion igual al nombre
+    //visible corresponde a una cancion exportada sin archivo
+    fn completar_desde_ubicacion(&mut self, ubicacion: &str) {
+        if self.titulo.is_empty() {
+            let nombre = Path::new(ubicacion)
+                .file_stem()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(ubicacion.to_string());
+            self.set_nombre_visible(&nombre);
+        }
+
+        if ubicacion != nombre_visible(&self.artista, &self.titulo) {
+            self.ubicacion = Some(ubicacion.to_string());
+        }
+    }
+}
+
+fn escapar_xml(texto: &str) -> String {
+    texto
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn desescapar_xml(texto: &str) -> String {
+    texto
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn contenido_etiqueta(bloque: &str, apertura: &str, cierre: &str) -> Option<String> {
+    let inicio = bloque.find(apertura)? + apertura.len();
+    let fin = bloque[inicio..].find(cierre)? + inicio;
+
+    Some(desescapar_xml(bloque[inicio..fin].trim()))
+}
+
+fn leer_m3u(contenido: &str) -> Result<Vec<Entrada>, ErrorPlaylist> {
+    let mut lineas = contenido.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    if lineas.next() != Some("#EXTM3U") {
+        return Err(ErrorPlaylist::FormatoInvalido);
+    }
+
+    let mut entradas = Vec::new();
+    let mut actual = Entrada::default();
+
+    // Las directivas describen a la entrada cuya ubicacion aparece a continuacion
+    for linea in lineas {
+        if let Some(info) = linea.strip_prefix("#EXTINF:") {
+            let Some((duracion, nombre)) = info.split_once(',') else {
+                return Err(ErrorPlaylist::FormatoInvalido);
+            };
+            actual.set_duracion(duracion)?;
+            actual.set_nombre_visible(nombre);
+        } else if let Some(genero) = linea.strip_prefix("#EXTGENRE:") {
+            actual.genero = Some(genero.trim().to_string());
+        } else if !linea.starts_with('#') {
+            actual.completar_desde_ubicacion(linea);
+            entradas.push(std::mem::take(&mut actual));
+        }
+    }
+
+    Ok(entradas)
+}
+
+fn leer_pls(contenido: &str) -> Result<Vec<Entrada>, ErrorPlaylist> {
+    let mut lineas = contenido.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    if !lineas
+        .next()
+        .is_some_and(|l| l.eq_ignore_ascii_case("[playlist]"))
+    {
+        return Err(ErrorPlaylist::FormatoInvalido);
+    }
+
+    // Cada clave termina con el numero de entrada, por ejemplo File1 o Title1
+    let mut entradas: BTreeMap<usize, (Option<String>, Entrada)> = BTreeMap::new();
+
+    for linea in lineas {
+        let Some((clave, valor)) = linea.split_once('=') else {
+            return Err(ErrorPlaylist::FormatoInvalido);
+        };
+        let Some(pos) = clave.find(|c: char| c.is_ascii_digit()) else {
+            continue; // NumberOfEntries y Version
+        };
+        let Ok(numero) = clave[pos..].parse::<usize>() else {
+            return Err(ErrorPlaylist::FormatoInvalido);
+        };
+
+        let (ubicacion, entrada) = entradas.entry(numero).or_default();
+        match clave[..pos].to_lowercase().as_str() {
+            "file" => *ubicacion = Some(valor.trim().to_string()),
+            "title" => entrada.set_nombre_visible(valor),
+            "genre" => entrada.genero = Some(valor.trim().to_string()),
+            "length" => entrada.set_duracion(valor)?,
+            _ => (),
+        }
+    }
+
+    entradas
+        .into_values()
+        .map(|(ubicacion, mut entrada)| {
+            let Some(ubicacion) = ubicacion else {
+                return Err(ErrorPlaylist::FormatoInvalido);
+            };
+            entrada.completar_desde_ubicacion(&ubicacion);
+            Ok(entrada)
+        })
+        .collect()
+}
+
+fn leer_xspf(contenido: &str) -> Result<Vec<Entrada>, ErrorPlaylist> {
+    if !contenido.contains("<playlist") {
+        return Err(ErrorPlaylist::FormatoInvalido);
+    }
+
+    let mut entradas = Vec::new();
+    let mut resto = contenido;
+
+    while let Some(inicio) = resto.find("<track>") {
+        let Some(fin) = resto[inicio..].find("</track>") else {
+            return Err(ErrorPlaylist::FormatoInvalido);
+        };
+        let bloque = &resto[inicio..inicio + fin];
+
+        let mut entrada = Entrada {
+            titulo: contenido_etiqueta(bloque, "<title>", "</title>").unwrap_or_default(),
+            artista: contenido_etiqueta(bloque, "<creator>", "</creator>").unwrap_or_default(),
+            genero: contenido_etiqueta(bloque, "<meta rel=\"genre\">", "</meta>"),
+            duracion: 0,
+            ubicacion: None,
+        };
+        if let Some(milisegundos) = contenido_etiqueta(bloque, "<duration>", "</duration>") {
+            let Ok(milisegundos) = milisegundos.parse::<u32>() else {
+                return Err(ErrorPlaylist::FormatoInvalido);
+            };
+            entrada.duracion = milisegundos / 1000;
+        }
+        if let Some(ubicacion) = contenido_etiqueta(bloque, "<location>", "</location>") {
+            entrada.completar_desde_ubicacion(&ubicacion);
+        }
+        entradas.push(entrada);
+
+        resto = &resto[inicio + fin..];
+    }
+
+    Ok(entradas)
+}
+
+impl Playlist {
+    // La ubicacion de cada entrada es el archivo de la cancion. El genero se escribe en
+    //#EXTGENRE y GenreN, que otros reproductores ignoran si no los soportan
+    fn exportar(&self, formato: &FormatoPlaylist) -> String {
+        let mut salida = String::new();
+
+        match formato {
+            FormatoPlaylist::M3u => {
+                salida.push_str("#EXTM3U\n");
+                salida.push_str(&format!("#PLAYLIST:{}\n", self.nombre));
+
+                for c in &self.canciones {
+                    salida.push_str(&format!(
+                        "#EXTINF:{},{}\n",
+                        c.duracion_visible(),
+                        c.nombre_visible()
+                    ));
+                    salida.push_str(&format!("#EXTGENRE:{}\n", c.genero.to_string()));
+                    salida.push_str(&format!("{}\n", c.ubicacion()));
+                }
+            }
+            FormatoPlaylist::Pls => {
+                salida.push_str("[playlist]\n");
+
+                for (i, c) in self.canciones.iter().enumerate() {
+                    let n = i + 1;
+                    salida.push_str(&format!("File{}={}\n", n, c.ubicacion()));
+                    salida.push_str(&format!("Title{}={}\n", n, c.nombre_visible()));
+                    salida.push_str(&format!("Length{}={}\n", n, c.duracion_visible()));
+                    salida.push_str(&format!("Genre{}={}\n", n, c.genero.to_string()));
+                }
+
+                salida.push_str(&format!("NumberOfEntries={}\n", self.canciones.len()));
+                salida.push_str("Version=2\n");
+            }
+            FormatoPlaylist::Xspf => {
+                salida.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                salida.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+                salida.push_str(&format!("  <title>{}</title>\n", escapar_xml(&self.nombre)));
+                salida.push_str("  <trackList>\n");
+
+                for c in &self.canciones {
+                    salida.push_str("    <track>\n");
+                    if let Some(path) = &c.path_archivo {
+                        salida.push_str(&format!(
+                            "      <location>{}</location>\n",
+                            escapar_xml(path)
+                        ));
+                    }
+                    salida.push_str(&format!(
+                        "      <title>{}</title>\n",
+                        escapar_xml(&c.titulo)
+                    ));
+                    salida.push_str(&format!(
+                        "      <creator>{}</creator>\n",
+                        escapar_xml(&c.artista)
+                    ));
+                    salida.push_str(&format!(
+                        "      <meta rel=\"genre\">{}</meta>\n",
+                        c.genero.to_string()
+                    ));
+                    if c.duracion > 0 {
+                        salida.push_str(&format!(
+                            "      <duration>{}</duration>\n",
+                            c.duracion * 1000
+                        ));
+                    }
+                    salida.push_str("    </track>\n");
+                }
+
+                salida.push_str("  </trackList>\n");
+                salida.push_str("</playlist>\n");
+            }
+        }
+
+        salida
+    }
+
+    fn exportar_archivo(&self, path: &str, formato: &FormatoPlaylist) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::create(path) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(_) = f.write_all(self.exportar(formato).as_bytes()) else {
+            return Err(ErrorPlaylist::EscribirArchivo);
+        };
+
+        Ok(())
+    }
+
+    // Agrega las entradas al final de la playlist en el orden del archivo. Las que no tienen
+    //un genero conocido se agregan como Otros y se informan en el reporte
+    fn importar(
+        &mut self,
+        contenido: &str,
+        formato: &FormatoPlaylist,
+    ) -> Result<ReporteImportacion, ErrorPlaylist> {
+        let entradas = match formato {
+            FormatoPlaylist::M3u => leer_m3u(contenido)?,
+            FormatoPlaylist::Pls => leer_pls(contenido)?,
+            FormatoPlaylist::Xspf => leer_xspf(contenido)?,
+        };
+
+        let anteriores: Vec<Cancion> = self.canciones.iter().cloned().collect();
+        let mut reporte = ReporteImportacion {
+            importadas: entradas.len(),
+            sin_genero: Vec::new(),
+        };
+
+        for (i, entrada) in entradas.into_iter().enumerate() {
+            let genero = match entrada.genero.as_deref().and_then(Generos::desde_nombre) {
+                Some(g) => g,
+                None => {
+                    reporte.sin_genero.push(EntradaSinGenero {
+                        posicion: i + 1,
+                        titulo: entrada.titulo.clone(),
+                        genero: entrada.genero,
+                    });
+                    Generos::Otros
+                }
+            };
+
+            let mut cancion = Cancion::new(entrada.titulo, entrada.artista, genero);
+            cancion.duracion = entrada.duracion;
+            cancion.path_archivo = entrada.ubicacion;
+            self.canciones.push_back(cancion);
+        }
+
+        self.registrar(Edicion::Reemplazar {
+            anteriores,
+            nuevas: self.canciones.iter().cloned().collect(),
+        })?;
+
+        Ok(reporte)
+    }
+
+    fn importar_archivo(
+        &mut self,
+        path: &str,
+        formato: &FormatoPlaylist,
+    ) -> Result<ReporteImportacion, ErrorPlaylist> {
+        let Ok(mut f) = File::open(path) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let mut contenido = String::new();
+        let Ok(_) = f.read_to_string(&mut contenido) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        self.importar(&contenido, formato)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn crear_playlist(n: u32) -> Playlist {
+        let canciones = VecDeque::from(vec![
+            Cancion::new(
+                "Cancion1".to_string(),
+                "Artista1".to_string(),
+                Generos::Rock,
+            ),
+            Cancion::new(
+                "Cancion & 2".to_string(),
+                "Artista2".to_string(),
+                Generos::Jazz,
+            ),
+            Cancion::new("Cancion3".to_string(), String::new(), Generos::Otros),
+        ]);
+        let mut p = Playlist::new(
+            "Playlist".to_string(),
+            canciones,
+            format!("test_files/playlist_formatos{}.json", n),
+        );
+        p.canciones[0].set_duracion(3, 25);
+        p.canciones[0].set_path_archivo("/musica/Cancion1.mp3".to_string());
+        p.canciones[2].set_duracion(0, 48);
+
+        p
+    }
+
+    fn vacia(n: u32) -> Playlist {
+        Playlist::new(
+            "Importada".to_string(),
+            VecDeque::new(),
+            format!("test_files/importada_formatos{}.json", n),
+        )
+    }
+
+    #[test]
+    fn test_exportar_importar() {
+        let p = crear_playlist(1);
+        let formatos = [
+            (FormatoPlaylist::M3u, "m3u"),
+            (FormatoPlaylist::Pls, "pls"),
+            (FormatoPlaylist::Xspf, "xspf"),
+        ];
+
+        // Exportar e importar conserva titulo, artista, genero y archivo
+
+        for (i, (formato, extension)) in formatos.iter().enumerate() {
+            let path = format!("test_files/playlist_formatos.{}", extension);
+            assert!(p.exportar_archivo(&path, formato).is_ok());
+
+            let mut importada = vacia(i as u32 + 1);
+            let reporte = importada.importar_archivo(&path, formato).unwrap();
+
+            assert_eq!(reporte.importadas, 3);
+            assert!(reporte.sin_genero.is_empty());
+            assert!(importada
+                .canciones
+                .iter()
+                .zip(&p.canciones)
+                .all(|(a, b)| a.eq(b)));
+        }
+
+        let xspf = p.exportar(&FormatoPlaylist::Xspf);
+        assert!(xspf.contains("<title>Cancion &amp; 2</title>"));
+        assert!(xspf.contains("<location>/musica/Cancion1.mp3</location>"));
+        assert_eq!(xspf.matches("<location>").count(), 1);
+
+        let m3u = p.exportar(&FormatoPlaylist::M3u);
+        assert!(m3u.contains("\n/musica/Cancion1.mp3\n"));
+        assert!(m3u.contains("\nArtista2 - Cancion & 2\n"));
+    }
+
+    #[test]
+    fn test_importar_generos_desconocidos() {
+        let m3u = "#EXTM3U\n\
+            #EXTINF:215,Artista1 - Cancion1\n\
+            #EXTGENRE:rock\n\
+            /musica/cancion1.mp3\n\
+            #EXTINF:180,Artista2 - Cancion2\n\
+            #EXTGENRE:Cumbia\n\
+            /musica/cancion2.mp3\n\
+            /musica/Artista3 - Cancion3.ogg\n";
+
+        let mut p = vacia(4);
+        let reporte = p.importar(m3u, &FormatoPlaylist::M3u).unwrap();
+
+        assert_eq!(reporte.importadas, 3);
+        assert_eq!(
+            reporte.sin_genero,
+            vec![
+                EntradaSinGenero {
+                    posicion: 2,
+                    titulo: "Cancion2".to_string(),
+                    genero: Some("Cumbia".to_string()),
+                },
+                EntradaSinGenero {
+                    posicion: 3,
+                    titulo: "Cancion3".to_string(),
+                    genero: None,
+                },
+            ]
+        );
+        assert_eq!(p.canciones[0].genero, Generos::Rock);
+        assert_eq!(p.canciones[0].duracion, 215);
+        assert_eq!(p.canciones[2].duracion, 0);
+        assert_eq!(p.canciones[1].genero, Generos::Otros);
+        assert_eq!(p.canciones[2].artista, "Artista3");
+        assert_eq!(
+            p.canciones[2].path_archivo.as_deref(),
+            Some("/musica/Artista3 - Cancion3.ogg")
+        );
+
+        // PLS sin genero, con entradas desordenadas
+
+        let pls = "[playlist]\n\
+            File2=http://radio.example/stream\n\
+            Title2=Radio\n\
+            File1=/musica/Artista1 - Cancion4.mp3\n\
+            NumberOfEntries=2\n";
+
+        let mut p = vacia(5);
+        let reporte = p.importar(pls, &FormatoPlaylist::Pls).unwrap();
+
+        assert_eq!(reporte.sin_genero.len(), 2);
+        assert_eq!(p.canciones[0].titulo, "Cancion4");
+        assert_eq!(p.canciones[1].titulo, "Radio");
+        assert_eq!(p.canciones[1].artista, "");
+        assert_eq!(
+            p.canciones[1].path_archivo.as_deref(),
+            Some("http://radio.example/stream")
+        );
+    }
+
+    #[test]
+    fn test_importar_formato_invalido() {
+        let mut p = vacia(6);
+
+        let result = p.importar("[playlist]\nFile1=a.mp3\n", &FormatoPlaylist::M3u);
+        assert!(matches!(result, Err(ErrorPlaylist::FormatoInvalido)));
+        println!("{}", result.unwrap_err());
+
+        assert!(p
+            .importar("[playlist]\nTitle1=Sin archivo\n", &FormatoPlaylist::Pls)
+            .is_err());
+        assert!(p.importar("#EXTM3U\n", &FormatoPlaylist::Xspf).is_err());
+        assert!(p
+            .importar(
+                "<playlist><trackList><track><title>A",
+                &FormatoPlaylist::Xspf
+            )
+            .is_err());
+        assert!(p.canciones.is_empty());
+
+        assert!(matches!(
+            p.importar_archivo("test_files/no_existe.m3u", &FormatoPlaylist::M3u),
+            Err(ErrorPlaylist::AbrirArchivo)
+        ));
+        assert!(matches!(
+            crear_playlist(7).exportar_archivo("/", &FormatoPlaylist::M3u),
+            Err(ErrorPlaylist::AbrirArchivo)
+        ));
+    }
+}
@@ -0,0 +1,420 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::{fs::File, io::prelude::*};
+
+use super::{Cancion, Edicion, ErrorPlaylist, HistorialEdiciones, Playlist};
+
+// El nombre de la playlist se guarda con el historial, ya que sus cambios se registran en el.
+//Los historiales guardados antes no tienen nombre
+#[derive(Serialize)]
+struct HistorialGuardado<'a> {
+    nombre: &'a str,
+    #[serde(flatten)]
+    historial: &'a HistorialEdiciones,
+}
+
+#[derive(Deserialize)]
+struct HistorialRecuperado {
+    #[serde(default)]
+    nombre: Option<String>,
+    #[serde(flatten)]
+    historial: HistorialEdiciones,
+}
+
+impl Playlist {
+    // El historial se guarda junto al archivo de canciones
+    fn path_historial(&self) -> PathBuf {
+        Path::new(&self.path_file).with_extension("historial.json")
+    }
+
+    fn guardar_historial(&self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::create(self.path_historial()) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let guardado = HistorialGuardado {
+            nombre: &self.nombre,
+            historial: &self.historial,
+        };
+        let Ok(historial) = serde_json::to_string(&guardado) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+
+        let Ok(_) = f.write_all(historial.as_bytes()) else {
+            return Err(ErrorPlaylist::EscribirArchivo);
+        };
+
+        Ok(())
+    }
+
+    // Sin archivo de historial se empieza con el historial vacio. El nombre guardado reemplaza
+    //al actual, ya que pudo haberse modificado
+    pub(super) fn recuperar_historial(&mut self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::open(self.path_historial()) else {
+            self.historial = HistorialEdiciones::default();
+            return Ok(());
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(recuperado) = serde_json::from_str::<HistorialRecuperado>(&buf) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+        if let Some(nombre) = recuperado.nombre {
+            self.nombre = nombre;
+        }
+        self.historial = recuperado.historial;
+
+        Ok(())
+    }
+
+    fn guardar_todo(&self) -> Result<(), ErrorPlaylist> {
+        self.build_archivo(&self.canciones)?;
+        self.guardar_historial()
+    }
+
+    // Registra una edicion ya aplicada. Cualquier cambio nuevo descarta lo que se podia rehacer
+    pub(super) fn registrar(&mut self, edicion: Edicion) -> Result<(), ErrorPlaylist> {
+        match &mut self.historial.grupo {
+            Some(grupo) => grupo.push(edicion),
+            None => self.historial.deshacer.push(vec![edicion]),
+        }
+        self.historial.rehacer.clear();
+
+        self.guardar_todo()
+    }
+
+    // El historial recuperado puede no corresponder a las canciones guardadas, por lo que se
+    //controlan los indices antes de aplicar cada edicion
+    fn aplicar(&mut self, edicion: &Edicion, inversa: bool) -> Result<(), ErrorPlaylist> {
+        let largo = self.canciones.len();
+
+        match (edicion, inversa) {
+            (Edicion::Agregar(cancion), false) => self.canciones.push_front(cancion.clone()),
+            (Edicion::Agregar(_), true) => {
+                if self.canciones.pop_front().is_none() {
+                    return Err(ErrorPlaylist::HistorialInvalido);
+                }
+            }
+            (Edicion::Eliminar { index, .. }, false) => {
+                if self.canciones.remove(*index).is_none() {
+                    return Err(ErrorPlaylist::HistorialInvalido);
+                }
+            }
+            (Edicion::Eliminar { cancion, index }, true) => {
+                if *index > largo {
+                    return Err(ErrorPlaylist::HistorialInvalido);
+                }
+                self.canciones.insert(*index, cancion.clone())
+            }
+            (Edicion::Mover { desde, hasta }, _) => {
+                let (desde, hasta) = if inversa {
+                    (*hasta, *desde)
+                } else {
+                    (*desde, *hasta)
+                };
+                if desde >= largo || hasta >= largo {
+                    return Err(ErrorPlaylist::HistorialInvalido);
+                }
+                if let Some(cancion) = self.canciones.remove(desde) {
+                    self.canciones.insert(hasta, cancion);
+                }
+            }
+            (Edicion::ModificarTitulo { nuevo, .. }, false) => self.nombre = nuevo.clone(),
+            (Edicion::ModificarTitulo { anterior, .. }, true) => self.nombre = anterior.clone(),
+            (Edicion::Reemplazar { nuevas, .. }, false) => {
+                self.canciones = VecDeque::from(nuevas.clone())
+            }
+            (Edicion::Reemplazar { anteriores, .. }, true) => {
+                self.canciones = VecDeque::from(anteriores.clone())
+            }
+        }
+
+        Ok(())
+    }
+
+    // Aplica todas las ediciones del grupo o ninguna
+    fn aplicar_grupo<'a, I>(&mut self, ediciones: I, inversa: bool) -> Result<(), ErrorPlaylist>
+    where
+        I: Iterator<Item = &'a Edicion>,
+    {
+        let canciones = self.canciones.clone();
+        let nombre = self.nombre.clone();
+
+        for edicion in ediciones {
+            if let Err(e) = self.aplicar(edicion, inversa) {
+                self.canciones = canciones;
+                self.nombre = nombre;
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Devuelve false si no habia nada para deshacer
+    fn deshacer(&mut self) -> Result<bool, ErrorPlaylist> {
+        let Some(grupo) = self.historial.deshacer.pop() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self.aplicar_grupo(grupo.iter().rev(), true) {
+            self.historial.deshacer.push(grupo);
+            return Err(e);
+        }
+        self.historial.rehacer.push(grupo);
+
+        self.guardar_todo()?;
+
+        Ok(true)
+    }
+
+    fn rehacer(&mut self) -> Result<bool, ErrorPlaylist> {
+        let Some(grupo) = self.historial.rehacer.pop() else {
+            return Ok(false);
+        };
+
+        if let Err(e) = self.aplicar_grupo(grupo.iter(), false) {
+            self.historial.rehacer.push(grupo);
+            return Err(e);
+        }
+        self.historial.deshacer.push(grupo);
+
+        self.guardar_todo()?;
+
+        Ok(true)
+    }
+
+    // Las ediciones hechas dentro de operaciones se deshacen en un solo paso, aunque alguna
+    //falle. Si ya hay un grupo abierto, se suman a ese grupo
+    fn agrupar<F>(&mut self, operaciones: F) -> Result<(), ErrorPlaylist>
+    where
+        F: FnOnce(&mut Playlist) -> Result<(), ErrorPlaylist>,
+    {
+        if self.historial.grupo.is_some() {
+            return operaciones(self);
+        }
+
+        self.historial.grupo = Some(Vec::new());
+        let resultado = operaciones(self);
+
+        if let Some(grupo) = self.historial.grupo.take() {
+            if !grupo.is_empty() {
+                self.historial.deshacer.push(grupo);
+            }
+        }
+
+        resultado.and(self.guardar_historial())
+    }
+
+    fn agregar_canciones(&mut self, canciones: Vec<Cancion>) -> Result<(), ErrorPlaylist> {
+        self.agrupar(|p| {
+            for cancion in canciones {
+                p.agregar_cancion(cancion)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{CampoOrden, Generos};
+    use super::*;
+
+    fn cancion(n: u32) -> Cancion {
+        Cancion::new(
+            format!("Cancion{}", n),
+            format!("Artista{}", n),
+            Generos::Pop,
+        )
+    }
+
+    fn crear_playlist(n: u32) -> Playlist {
+        Playlist::new(
+            "Playlist".to_string(),
+            VecDeque::new(),
+            format!("test_files/historial{}.json", n),
+        )
+    }
+
+    fn titulos(p: &Playlist) -> Vec<&str> {
+        p.canciones.iter().map(|c| c.titulo.as_str()).collect()
+    }
+
+    #[test]
+    fn test_deshacer_rehacer() {
+        let mut p = crear_playlist(1);
+        assert!(!p.deshacer().unwrap());
+        assert!(!p.rehacer().unwrap());
+
+        for n in 1..=3 {
+            assert!(p.agregar_cancion(cancion(n)).is_ok());
+        }
+        assert!(p.mover_cancion(&cancion(3), 10).is_ok());
+        assert!(p.eliminar_cancion(&cancion(2)).is_ok());
+        assert!(p.modificar_titulo("Nueva".to_string()).is_ok());
+        assert_eq!(titulos(&p), vec!["Cancion1", "Cancion3"]);
+
+        // Se deshacen en orden inverso, tantos pasos como se quiera
+
+        assert!(p.deshacer().unwrap());
+        assert_eq!(p.nombre, "Playlist");
+        assert!(p.deshacer().unwrap());
+        assert_eq!(titulos(&p), vec!["Cancion2", "Cancion1", "Cancion3"]);
+        assert!(p.deshacer().unwrap());
+        assert_eq!(titulos(&p), vec!["Cancion3", "Cancion2", "Cancion1"]);
+        assert!(p.deshacer().unwrap());
+        assert!(p.deshacer().unwrap());
+        assert!(p.deshacer().unwrap());
+        assert!(p.canciones.is_empty());
+        assert!(!p.deshacer().unwrap());
+
+        for _ in 0..6 {
+            assert!(p.rehacer().unwrap());
+        }
+        assert_eq!(titulos(&p), vec!["Cancion1", "Cancion3"]);
+        assert_eq!(p.nombre, "Nueva");
+
+        // Un cambio nuevo descarta lo que se podia rehacer
+
+        assert!(p.deshacer().unwrap());
+        assert!(p.ordenar_por(&CampoOrden::Titulo, false).is_ok());
+        assert!(!p.rehacer().unwrap());
+        assert_eq!(titulos(&p), vec!["Cancion3", "Cancion1"]);
+        assert!(p.deshacer().unwrap());
+        assert_eq!(titulos(&p), vec!["Cancion1", "Cancion3"]);
+    }
+
+    #[test]
+    fn test_agrupar() {
+        let mut p = crear_playlist(2);
+        assert!(p.agregar_cancion(cancion(1)).is_ok());
+
+        assert!(p
+            .agregar_canciones(vec![cancion(2), cancion(3), cancion(4)])
+            .is_ok());
+        assert!(p
+            .agrupar(|p| {
+                p.eliminar_cancion(&cancion(1))?;
+                p.agregar_canciones(vec![cancion(5)])?;
+                p.modificar_titulo("Agrupada".to_string())
+            })
+            .is_ok());
+        assert_eq!(p.historial.deshacer.len(), 3);
+
+        assert!(p.deshacer().unwrap());
+        assert_eq!(p.canciones.len(), 4);
+        assert_eq!(p.nombre, "Playlist");
+        assert!(p.deshacer().unwrap());
+        assert_eq!(titulos(&p), vec!["Cancion1"]);
+
+        assert!(p.rehacer().unwrap());
+        assert_eq!(p.canciones.len(), 4);
+
+        // Un grupo sin ediciones no agrega un paso
+
+        assert!(p.agrupar(|_| Ok(())).is_ok());
+        assert_eq!(p.historial.deshacer.len(), 2);
+    }
+
+    #[test]
+    fn test_deshacer_clear_despues_de_reiniciar() {
+        let mut p = crear_playlist(3);
+        assert!(p.agregar_canciones(vec![cancion(1), cancion(2)]).is_ok());
+        assert!(p.clear().is_ok());
+
+        let mut reiniciada =
+            Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert!(reiniciada.canciones.is_empty());
+
+        assert!(reiniciada.deshacer().unwrap());
+        assert_eq!(titulos(&reiniciada), vec!["Cancion2", "Cancion1"]);
+
+        // El cambio tambien queda guardado
+
+        let reiniciada = Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(reiniciada.canciones.len(), 2);
+        assert_eq!(reiniciada.historial.rehacer.len(), 1);
+
+        assert!(matches!(
+            Playlist::recuperar(
+                "Playlist".to_string(),
+                "test_files/no_existe.json".to_string()
+            ),
+            Err(ErrorPlaylist::AbrirArchivo)
+        ));
+    }
+
+    #[test]
+    fn test_nombre_despues_de_reiniciar() {
+        let mut p = crear_playlist(4);
+        assert!(p.agregar_cancion(cancion(1)).is_ok());
+        assert!(p.modificar_titulo("Nueva".to_string()).is_ok());
+
+        let mut reiniciada =
+            Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(reiniciada.nombre, "Nueva");
+
+        assert!(reiniciada.deshacer().unwrap());
+        let reiniciada = Playlist::recuperar("Nueva".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(reiniciada.nombre, "Playlist");
+
+        // Un historial guardado sin nombre conserva el indicado al recuperar
+
+        File::create("test_files/historial4.historial.json")
+            .unwrap()
+            .write_all(b"{\"deshacer\": [], \"rehacer\": []}")
+            .unwrap();
+        let reiniciada = Playlist::recuperar("Otra".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(reiniciada.nombre, "Otra");
+        assert!(reiniciada.historial.deshacer.is_empty());
+    }
+
+    #[test]
+    fn test_historial_no_corresponde() {
+        let mut p = crear_playlist(5);
+        assert!(p.agregar_canciones(vec![cancion(1), cancion(2)]).is_ok());
+        assert!(p.eliminar_cancion(&cancion(1)).is_ok());
+
+        // Se reemplaza el archivo de canciones sin tocar el historial
+
+        assert!(p.build_archivo(&VecDeque::new()).is_ok());
+        let mut reiniciada =
+            Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert!(reiniciada.canciones.is_empty());
+
+        assert!(matches!(
+            reiniciada.deshacer(),
+            Err(ErrorPlaylist::HistorialInvalido)
+        ));
+        assert!(reiniciada.canciones.is_empty());
+        assert_eq!(reiniciada.historial.deshacer.len(), 2);
+
+        // El grupo que falla a mitad de camino no deja ediciones aplicadas
+
+        reiniciada.historial.deshacer.pop();
+        assert!(reiniciada.agregar_cancion(cancion(3)).is_ok());
+        reiniciada.historial.deshacer.swap(0, 1);
+        assert!(reiniciada.deshacer().is_err());
+        assert_eq!(titulos(&reiniciada), vec!["Cancion3"]);
+    }
+
+    #[test]
+    fn test_historial_archivo_error() {
+        let mut p = Playlist::new("Playlist".to_string(), VecDeque::new(), "/".to_string());
+
+        // La edicion se aplica y se registra aunque no se pueda guardar
+
+        assert!(p.agregar_cancion(cancion(1)).is_err());
+        assert!(p.deshacer().is_err());
+        assert!(p.canciones.is_empty());
+        assert!(p.agregar_canciones(vec![cancion(2), cancion(3)]).is_err());
+        assert_eq!(p.canciones.len(), 1);
+        assert_eq!(p.historial.deshacer.len(), 1);
+    }
+}
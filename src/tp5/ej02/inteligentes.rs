@@ -0,0 +1,248 @@
+use std::path::{Path, PathBuf};
+use std::{fs::File, io::prelude::*};
+
+use super::{Cancion, ErrorPlaylist, Playlist, PlaylistInteligente, Regla};
+
+impl Regla {
+    fn cumple(&self, cancion: &Cancion) -> bool {
+        match self {
+            Regla::GeneroEn(generos) => generos.iter().any(|g| g.eq(&cancion.genero)),
+            Regla::Artista(artista) => cancion.artista.eq(artista),
+            Regla::No(regla) => !regla.cumple(cancion),
+            Regla::Todas(reglas) => reglas.iter().all(|r| r.cumple(cancion)),
+            Regla::Alguna(reglas) => reglas.iter().any(|r| r.cumple(cancion)),
+        }
+    }
+}
+
+impl Playlist {
+    // Las playlists inteligentes se guardan junto al archivo de canciones
+    fn path_inteligentes(&self) -> PathBuf {
+        Path::new(&self.path_file).with_extension("inteligentes.json")
+    }
+
+    fn guardar_inteligentes(&self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::create(self.path_inteligentes()) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(inteligentes) = serde_json::to_string(&self.inteligentes) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+
+        let Ok(_) = f.write_all(inteligentes.as_bytes()) else {
+            return Err(ErrorPlaylist::EscribirArchivo);
+        };
+
+        Ok(())
+    }
+
+    // Sin archivo de playlists inteligentes no se recupera ninguna
+    pub(super) fn recuperar_inteligentes(&mut self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::open(self.path_inteligentes()) else {
+            self.inteligentes = Vec::new();
+            return Ok(());
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(inteligentes) = serde_json::from_str::<Vec<PlaylistInteligente>>(&buf) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+        self.inteligentes = inteligentes;
+
+        Ok(())
+    }
+
+    fn buscar_inteligente(&self, nombre: &str) -> Option<usize> {
+        self.inteligentes.iter().position(|p| p.nombre == nombre)
+    }
+
+    fn crear_inteligente(&mut self, nombre: String, regla: Regla) -> Result<(), ErrorPlaylist> {
+        if self.buscar_inteligente(&nombre).is_some() {
+            return Err(ErrorPlaylist::PlaylistDuplicada);
+        }
+
+        self.inteligentes
+            .push(PlaylistInteligente { nombre, regla });
+
+        self.guardar_inteligentes()
+    }
+
+    fn modificar_regla(&mut self, nombre: &str, regla: Regla) -> Result<(), ErrorPlaylist> {
+        let Some(index) = self.buscar_inteligente(nombre) else {
+            return Err(ErrorPlaylist::PlaylistInexistente);
+        };
+
+        self.inteligentes[index].regla = regla;
+
+        self.guardar_inteligentes()
+    }
+
+    fn eliminar_inteligente(&mut self, nombre: &str) -> Result<(), ErrorPlaylist> {
+        let Some(index) = self.buscar_inteligente(nombre) else {
+            return Err(ErrorPlaylist::PlaylistInexistente);
+        };
+
+        self.inteligentes.remove(index);
+
+        self.guardar_inteligentes()
+    }
+
+    // Canciones que cumplen la regla, en el orden de la playlist
+    fn get_canciones_inteligente(&self, nombre: &str) -> Option<Vec<&Cancion>> {
+        let index = self.buscar_inteligente(nombre)?;
+        let regla = &self.inteligentes[index].regla;
+
+        Some(self.canciones.iter().filter(|c| regla.cumple(c)).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Generos;
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn crear_playlist() -> Playlist {
+        let canciones = VecDeque::from(vec![
+            Cancion::new(
+                "Cancion1".to_string(),
+                "Artista1".to_string(),
+                Generos::Rock,
+            ),
+            Cancion::new("Cancion2".to_string(), "Artista2".to_string(), Generos::Pop),
+            Cancion::new("Cancion3".to_string(), "Artista1".to_string(), Generos::Pop),
+            Cancion::new(
+                "Cancion4".to_string(),
+                "Artista3".to_string(),
+                Generos::Jazz,
+            ),
+        ]);
+
+        Playlist::new("Playlist".to_string(), canciones, Default::default())
+    }
+
+    fn titulos(canciones: Vec<&Cancion>) -> Vec<&str> {
+        canciones.iter().map(|c| c.titulo.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reglas() {
+        let mut p = crear_playlist();
+        p.path_file = "test_files/playlist_reglas.json".to_string();
+
+        // Genero Rock o Pop y artista distinto de Artista2
+
+        let regla = Regla::Todas(vec![
+            Regla::GeneroEn(vec![Generos::Rock, Generos::Pop]),
+            Regla::No(Box::new(Regla::Artista("Artista2".to_string()))),
+        ]);
+        assert!(p
+            .crear_inteligente("Sin Artista2".to_string(), regla)
+            .is_ok());
+        assert_eq!(
+            titulos(p.get_canciones_inteligente("Sin Artista2").unwrap()),
+            vec!["Cancion1", "Cancion3"]
+        );
+
+        let regla = Regla::Alguna(vec![
+            Regla::GeneroEn(vec![Generos::Jazz]),
+            Regla::Artista("Artista2".to_string()),
+        ]);
+        assert!(p.crear_inteligente("Mezcla".to_string(), regla).is_ok());
+        assert_eq!(
+            titulos(p.get_canciones_inteligente("Mezcla").unwrap()),
+            vec!["Cancion2", "Cancion4"]
+        );
+
+        let result = p.crear_inteligente("Mezcla".to_string(), Regla::Todas(Vec::new()));
+        assert!(matches!(result, Err(ErrorPlaylist::PlaylistDuplicada)));
+        println!("{}", result.unwrap_err());
+
+        // Una regla sin condiciones incluye todas las canciones
+
+        assert!(p
+            .modificar_regla("Mezcla", Regla::Todas(Vec::new()))
+            .is_ok());
+        assert_eq!(p.get_canciones_inteligente("Mezcla").unwrap().len(), 4);
+
+        assert!(p.eliminar_inteligente("Mezcla").is_ok());
+        assert!(p.get_canciones_inteligente("Mezcla").is_none());
+
+        let result = p.eliminar_inteligente("Mezcla");
+        assert!(matches!(result, Err(ErrorPlaylist::PlaylistInexistente)));
+        println!("{}", result.unwrap_err());
+        assert!(p
+            .modificar_regla("Mezcla", Regla::Todas(Vec::new()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_inteligente_actualizada() {
+        let mut p = crear_playlist();
+        let regla = Regla::GeneroEn(vec![Generos::Pop]);
+        assert!(p.crear_inteligente("Pop".to_string(), regla).is_err()); // Path de archivo invalido
+        assert_eq!(p.get_canciones_inteligente("Pop").unwrap().len(), 2);
+
+        // Refleja los cambios de la playlist sin recrearla
+
+        let nueva = Cancion::new("Cancion5".to_string(), "Artista4".to_string(), Generos::Pop);
+        assert!(p.agregar_cancion(nueva.clone()).is_err()); // Path de archivo invalido
+        assert_eq!(
+            titulos(p.get_canciones_inteligente("Pop").unwrap()),
+            vec!["Cancion5", "Cancion2", "Cancion3"]
+        );
+
+        assert!(p.eliminar_cancion(&nueva).is_err());
+        assert!(p.clear().is_err());
+        assert!(p.get_canciones_inteligente("Pop").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_inteligentes_archivo() {
+        let mut p = crear_playlist();
+        p.path_file = "test_files/playlist_inteligentes.json".to_string();
+        assert!(p.build_archivo(&p.canciones).is_ok());
+
+        let regla = Regla::Todas(vec![
+            Regla::GeneroEn(vec![Generos::Pop]),
+            Regla::No(Box::new(Regla::Artista("Artista2".to_string()))),
+        ]);
+        assert!(p.crear_inteligente("Pop".to_string(), regla).is_ok());
+        assert!(p
+            .crear_inteligente("Jazz".to_string(), Regla::Todas(Vec::new()))
+            .is_ok());
+        assert!(p
+            .modificar_regla("Jazz", Regla::GeneroEn(vec![Generos::Jazz]))
+            .is_ok());
+
+        // Se recuperan junto con las canciones
+
+        let recuperada = Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(recuperada.inteligentes, p.inteligentes);
+        assert_eq!(
+            titulos(recuperada.get_canciones_inteligente("Pop").unwrap()),
+            vec!["Cancion3"]
+        );
+
+        assert!(p.eliminar_inteligente("Pop").is_ok());
+        let recuperada = Playlist::recuperar("Playlist".to_string(), p.path_file.clone()).unwrap();
+        assert_eq!(recuperada.inteligentes.len(), 1);
+        assert!(recuperada.get_canciones_inteligente("Pop").is_none());
+
+        // Un archivo con formato no valido
+
+        File::create("test_files/playlist_inteligentes.inteligentes.json")
+            .unwrap()
+            .write_all(b"[{\"nombre\": 1}]")
+            .unwrap();
+        assert!(matches!(
+            Playlist::recuperar("Playlist".to_string(), p.path_file.clone()),
+            Err(ErrorPlaylist::FormatearCanciones)
+        ));
+    }
+}
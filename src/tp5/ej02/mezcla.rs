@@ -0,0 +1,152 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+use super::{Cancion, ModoMezcla, Playlist};
+
+// Indica si las canciones restantes, sin la elegida, pueden ordenarse sin que un artista se
+//repita dos veces seguidas, sabiendo que la primera no puede ser del artista de la elegida
+fn es_factible(restantes: &[&Cancion], elegida: usize) -> bool {
+    let artista = &restantes[elegida].artista;
+    let quedan = restantes.len() - 1;
+    let mut cantidades: HashMap<&String, usize> = HashMap::new();
+
+    for (i, c) in restantes.iter().enumerate() {
+        if i != elegida {
+            *cantidades.entry(&c.artista).or_default() += 1;
+        }
+    }
+
+    cantidades.iter().all(|(a, cantidad)| {
+        if *a == artista {
+            *cantidad <= quedan / 2
+        } else {
+            *cantidad <= quedan.div_ceil(2)
+        }
+    })
+}
+
+// Si no existe ningun orden valido se elige al artista con mas canciones restantes, para
+//que se repita lo menos posible
+fn mas_repetido(restantes: &[&Cancion], anterior: Option<&String>) -> usize {
+    let cantidad = |artista: &String| restantes.iter().filter(|c| c.artista.eq(artista)).count();
+
+    (0..restantes.len())
+        .filter(|&i| Some(&restantes[i].artista) != anterior)
+        .max_by_key(|&i| cantidad(&restantes[i].artista))
+        .unwrap_or(0)
+}
+
+// Recorre las canciones ya mezcladas eligiendo la primera que no repite al artista anterior y
+//no deja al resto sin solucion
+fn separar_artistas(mut restantes: Vec<&Cancion>) -> Vec<&Cancion> {
+    let mut orden: Vec<&Cancion> = Vec::new();
+
+    while !restantes.is_empty() {
+        let anterior = orden.last().map(|c| &c.artista);
+        let index = (0..restantes.len())
+            .find(|&i| Some(&restantes[i].artista) != anterior && es_factible(&restantes, i))
+            .unwrap_or_else(|| mas_repetido(&restantes, anterior));
+
+        orden.push(restantes.remove(index));
+    }
+
+    orden
+}
+
+impl Playlist {
+    // Devuelve un orden de reproduccion sin modificar el orden guardado de la playlist
+    fn mezclar_con<R: Rng>(&self, modo: &ModoMezcla, rng: &mut R) -> Vec<&Cancion> {
+        let mut canciones: Vec<&Cancion> = self.canciones.iter().collect();
+        canciones.shuffle(rng);
+
+        match modo {
+            ModoMezcla::Aleatorio => canciones,
+            ModoMezcla::SinRepetirArtista => separar_artistas(canciones),
+        }
+    }
+
+    fn mezclar(&self, modo: &ModoMezcla) -> Vec<&Cancion> {
+        self.mezclar_con(modo, &mut rand::thread_rng())
+    }
+
+    // Con la misma semilla siempre se obtiene el mismo orden
+    fn mezclar_con_semilla(&self, modo: &ModoMezcla, semilla: u64) -> Vec<&Cancion> {
+        self.mezclar_con(modo, &mut StdRng::seed_from_u64(semilla))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Generos;
+    use super::*;
+    use std::collections::VecDeque;
+
+    fn crear_playlist(artistas: &[&str]) -> Playlist {
+        let canciones = artistas
+            .iter()
+            .enumerate()
+            .map(|(i, a)| Cancion::new(format!("Cancion{}", i + 1), a.to_string(), Generos::Rock))
+            .collect::<VecDeque<Cancion>>();
+
+        Playlist::new("Playlist".to_string(), canciones, Default::default())
+    }
+
+    fn repite_artista(orden: &[&Cancion]) -> bool {
+        orden.windows(2).any(|w| w[0].artista == w[1].artista)
+    }
+
+    #[test]
+    fn test_mezcla_con_semilla() {
+        let p = crear_playlist(&["A", "B", "C", "D", "E", "F"]);
+
+        let orden1 = p.mezclar_con_semilla(&ModoMezcla::Aleatorio, 7);
+        let orden2 = p.mezclar_con_semilla(&ModoMezcla::Aleatorio, 7);
+        assert_eq!(orden1.len(), 6);
+        assert!(orden1.iter().zip(&orden2).all(|(a, b)| a.eq(b)));
+
+        // Contiene todas las canciones y no modifica la playlist
+
+        assert!(p.canciones.iter().all(|c| orden1.iter().any(|o| o.eq(c))));
+        assert_eq!(p.canciones.front().unwrap().titulo, "Cancion1");
+
+        assert_eq!(p.mezclar(&ModoMezcla::SinRepetirArtista).len(), 6);
+    }
+
+    #[test]
+    fn test_mezcla_sin_repetir_artista() {
+        // Un orden valido obliga a intercalar las canciones de A
+
+        let p = crear_playlist(&["A", "A", "A", "A", "B", "C", "B"]);
+
+        for semilla in 0..20 {
+            let orden = p.mezclar_con_semilla(&ModoMezcla::SinRepetirArtista, semilla);
+
+            assert_eq!(orden.len(), 7);
+            assert!(!repite_artista(&orden));
+            assert!(orden.first().is_some_and(|c| c.artista == "A"));
+        }
+    }
+
+    #[test]
+    fn test_mezcla_sin_orden_valido() {
+        let p = crear_playlist(&["A", "A", "A", "B"]);
+
+        // Se repite el artista lo minimo posible
+
+        for semilla in 0..10 {
+            let orden = p.mezclar_con_semilla(&ModoMezcla::SinRepetirArtista, semilla);
+            let repeticiones = orden
+                .windows(2)
+                .filter(|w| w[0].artista == w[1].artista)
+                .count();
+
+            assert_eq!(orden.len(), 4);
+            assert_eq!(repeticiones, 1);
+        }
+
+        let vacia = crear_playlist(&[]);
+        assert!(vacia.mezclar(&ModoMezcla::SinRepetirArtista).is_empty());
+    }
+}
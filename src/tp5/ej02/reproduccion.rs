@@ -0,0 +1,425 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::{fs::File, io::prelude::*};
+
+use super::{Cancion, ColaReproduccion, ErrorPlaylist, ModoRepeticion, Playlist};
+
+const LIMITE_HISTORIAL: usize = 50;
+
+impl ColaReproduccion {
+    // Las reproducciones se guardan junto al archivo de canciones de la playlist
+    fn new(playlist: &Playlist, path_file: String) -> Result<ColaReproduccion, ErrorPlaylist> {
+        let path_reproducciones = Path::new(&playlist.path_file)
+            .with_extension("reproducciones.json")
+            .to_string_lossy()
+            .to_string();
+
+        let mut cola = ColaReproduccion {
+            canciones: playlist.canciones.iter().cloned().collect(),
+            actual: None,
+            posicion: 0,
+            repeticion: ModoRepeticion::Ninguna,
+            historial: VecDeque::new(),
+            reproducciones: Vec::new(),
+            path_reproducciones,
+            path_file,
+        };
+        cola.recuperar_reproducciones()?;
+
+        Ok(cola)
+    }
+
+    fn guardar(&self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::create(&self.path_file) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(cola) = serde_json::to_string(self) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+
+        let Ok(_) = f.write_all(cola.as_bytes()) else {
+            return Err(ErrorPlaylist::EscribirArchivo);
+        };
+
+        self.guardar_reproducciones()
+    }
+
+    fn guardar_reproducciones(&self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::create(&self.path_reproducciones) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(reproducciones) = serde_json::to_string(&self.reproducciones) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+
+        let Ok(_) = f.write_all(reproducciones.as_bytes()) else {
+            return Err(ErrorPlaylist::EscribirArchivo);
+        };
+
+        Ok(())
+    }
+
+    // Sin archivo de reproducciones, la playlist todavia no se escucho
+    fn recuperar_reproducciones(&mut self) -> Result<(), ErrorPlaylist> {
+        let Ok(mut f) = File::open(&self.path_reproducciones) else {
+            self.reproducciones = Vec::new();
+            return Ok(());
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(reproducciones) = serde_json::from_str::<Vec<(Cancion, u32)>>(&buf) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+        self.reproducciones = reproducciones;
+
+        Ok(())
+    }
+
+    fn recuperar(path_file: String) -> Result<ColaReproduccion, ErrorPlaylist> {
+        let Ok(mut f) = File::open(&path_file) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorPlaylist::AbrirArchivo);
+        };
+
+        let Ok(mut cola) = serde_json::from_str::<ColaReproduccion>(&buf) else {
+            return Err(ErrorPlaylist::FormatearCanciones);
+        };
+        cola.path_file = path_file;
+        cola.recuperar_reproducciones()?;
+
+        // Un archivo desactualizado o editado a mano puede apuntar fuera de la cola
+        if cola.actual.is_some_and(|i| i >= cola.canciones.len()) {
+            cola.actual = None;
+            cola.posicion = 0;
+        }
+
+        Ok(cola)
+    }
+
+    fn get_actual(&self) -> Option<&Cancion> {
+        self.canciones.get(self.actual?)
+    }
+
+    // Empieza a reproducir la cancion en index, sumandola al historial y a las reproducciones
+    fn reproducir(&mut self, index: usize) {
+        let cancion = self.canciones[index].clone();
+
+        match self.reproducciones.iter_mut().find(|(c, _)| c.eq(&cancion)) {
+            Some((_, cantidad)) => *cantidad += 1,
+            None => self.reproducciones.push((cancion.clone(), 1)),
+        }
+
+        self.historial.push_front(cancion);
+        self.historial.truncate(LIMITE_HISTORIAL);
+
+        self.actual = Some(index);
+        self.posicion = 0;
+    }
+
+    // Al terminar la ultima cancion, sin repeticion, la cola queda sin cancion actual
+    fn siguiente(&mut self) -> Result<Option<&Cancion>, ErrorPlaylist> {
+        let index = match self.actual {
+            None => 0,
+            Some(i) if i + 1 < self.canciones.len() => i + 1,
+            Some(_) if self.repeticion == ModoRepeticion::Ninguna => self.canciones.len(),
+            Some(_) => 0,
+        };
+
+        if index < self.canciones.len() {
+            self.reproducir(index);
+        } else {
+            self.actual = None;
+            self.posicion = 0;
+        }
+
+        self.guardar()?;
+
+        Ok(self.get_actual())
+    }
+
+    fn anterior(&mut self) -> Result<Option<&Cancion>, ErrorPlaylist> {
+        let Some(i) = self.actual else {
+            return Ok(None);
+        };
+
+        let index = match i {
+            0 if self.repeticion == ModoRepeticion::Todas => self.canciones.len() - 1,
+            0 => 0,
+            i => i - 1,
+        };
+        self.reproducir(index);
+
+        self.guardar()?;
+
+        Ok(self.get_actual())
+    }
+
+    // Avanza la reproduccion. Al completar la cancion actual se repite o se pasa a la siguiente
+    //segun el modo de repeticion. Las canciones de duracion desconocida no terminan solas
+    fn avanzar(&mut self, segundos: u32) -> Result<Option<&Cancion>, ErrorPlaylist> {
+        let Some(i) = self.actual else {
+            return Ok(None);
+        };
+        let Some(duracion) = self.canciones.get(i).map(|c| c.duracion) else {
+            return Ok(None);
+        };
+
+        self.posicion += segundos;
+
+        if duracion == 0 || self.posicion < duracion {
+            self.guardar()?;
+            return Ok(self.get_actual());
+        }
+
+        if self.repeticion == ModoRepeticion::Una {
+            self.reproducir(i);
+            self.guardar()?;
+            return Ok(self.get_actual());
+        }
+
+        self.siguiente()
+    }
+
+    fn set_posicion(&mut self, segundos: u32) -> Result<(), ErrorPlaylist> {
+        let Some(actual) = self.get_actual() else {
+            return Ok(());
+        };
+
+        if actual.duracion > 0 && segundos >= actual.duracion {
+            self.posicion = actual.duracion - 1;
+        } else {
+            self.posicion = segundos;
+        }
+
+        self.guardar()
+    }
+
+    fn set_repeticion(&mut self, repeticion: ModoRepeticion) -> Result<(), ErrorPlaylist> {
+        self.repeticion = repeticion;
+
+        self.guardar()
+    }
+
+    // Inserta la cancion para que sea la proxima en sonar
+    fn reproducir_a_continuacion(&mut self, cancion: Cancion) -> Result<(), ErrorPlaylist> {
+        let index = self.actual.map_or(0, |i| i + 1);
+        self.canciones.insert(index, cancion);
+
+        self.guardar()
+    }
+
+    fn get_historial(&self) -> Vec<&Cancion> {
+        self.historial.iter().collect()
+    }
+
+    // Incluye lo reproducido en colas anteriores de la misma playlist. Ante la misma cantidad
+    //de reproducciones, primero la que se escucho antes por primera vez
+    fn mas_reproducidas(&self, cantidad: usize) -> Vec<(&Cancion, u32)> {
+        let mut ranking: Vec<(&Cancion, u32)> =
+            self.reproducciones.iter().map(|(c, n)| (c, *n)).collect();
+        ranking.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+        ranking.truncate(cantidad);
+
+        ranking
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Generos;
+    use super::*;
+
+    fn crear_cola(n: u32) -> ColaReproduccion {
+        let canciones = (1..=3)
+            .map(|i| {
+                let mut c = Cancion::new(
+                    format!("Cancion{}", i),
+                    "Artista".to_string(),
+                    Generos::Rock,
+                );
+                c.set_duracion(3, 0);
+                c
+            })
+            .collect::<VecDeque<Cancion>>();
+        let playlist = Playlist::new(
+            "Playlist".to_string(),
+            canciones,
+            format!("test_files/playlist_cola{}.json", n),
+        );
+
+        ColaReproduccion::new(&playlist, format!("test_files/cola{}.json", n)).unwrap()
+    }
+
+    fn titulo(cancion: Option<&Cancion>) -> Option<&str> {
+        cancion.map(|c| c.titulo.as_str())
+    }
+
+    #[test]
+    fn test_siguiente_anterior() {
+        let mut cola = crear_cola(1);
+        assert!(cola.get_actual().is_none());
+        assert!(cola.anterior().unwrap().is_none());
+
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion1"));
+        assert_eq!(titulo(cola.anterior().unwrap()), Some("Cancion1"));
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion2"));
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion3"));
+
+        // Sin repeticion, la cola termina despues de la ultima cancion
+
+        assert!(cola.siguiente().unwrap().is_none());
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion1"));
+
+        assert!(cola.set_repeticion(ModoRepeticion::Todas).is_ok());
+        assert_eq!(titulo(cola.anterior().unwrap()), Some("Cancion3"));
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion1"));
+
+        // Pasar de cancion a mano no respeta la repeticion de una sola
+
+        assert!(cola.set_repeticion(ModoRepeticion::Una).is_ok());
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion2"));
+    }
+
+    #[test]
+    fn test_avanzar_y_repetir() {
+        let mut cola = crear_cola(2);
+        assert!(cola.avanzar(30).unwrap().is_none());
+
+        cola.siguiente().unwrap();
+        assert_eq!(titulo(cola.avanzar(100).unwrap()), Some("Cancion1"));
+        assert_eq!(cola.posicion, 100);
+        assert_eq!(titulo(cola.avanzar(80).unwrap()), Some("Cancion2"));
+        assert_eq!(cola.posicion, 0);
+
+        assert!(cola.set_repeticion(ModoRepeticion::Una).is_ok());
+        assert_eq!(titulo(cola.avanzar(180).unwrap()), Some("Cancion2"));
+        assert_eq!(cola.get_historial().len(), 3);
+
+        assert!(cola.set_posicion(500).is_ok());
+        assert_eq!(cola.posicion, 179);
+
+        // Una cancion insertada suena a continuacion de la actual
+
+        let nueva = Cancion::new("Nueva".to_string(), "Artista".to_string(), Generos::Pop);
+        assert!(cola.reproducir_a_continuacion(nueva).is_ok());
+        assert!(cola.set_repeticion(ModoRepeticion::Ninguna).is_ok());
+        assert_eq!(titulo(cola.avanzar(1).unwrap()), Some("Nueva"));
+
+        // Sin duracion conocida solo se cambia de cancion a mano
+
+        assert_eq!(titulo(cola.avanzar(1000).unwrap()), Some("Nueva"));
+        assert_eq!(titulo(cola.siguiente().unwrap()), Some("Cancion3"));
+    }
+
+    #[test]
+    fn test_historial_y_mas_reproducidas() {
+        let mut cola = crear_cola(3);
+        assert!(cola.mas_reproducidas(5).is_empty());
+
+        cola.siguiente().unwrap(); // Cancion1
+        cola.siguiente().unwrap(); // Cancion2
+        cola.anterior().unwrap(); // Cancion1
+        cola.siguiente().unwrap(); // Cancion2
+        cola.siguiente().unwrap(); // Cancion3
+
+        let historial: Vec<&str> = cola
+            .get_historial()
+            .iter()
+            .map(|c| c.titulo.as_str())
+            .collect();
+        assert_eq!(
+            historial,
+            vec!["Cancion3", "Cancion2", "Cancion1", "Cancion2", "Cancion1"]
+        );
+
+        let ranking = cola.mas_reproducidas(2);
+        assert_eq!(ranking.len(), 2);
+        assert_eq!(
+            (ranking[0].0.titulo.as_str(), ranking[0].1),
+            ("Cancion1", 2)
+        );
+        assert_eq!(
+            (ranking[1].0.titulo.as_str(), ranking[1].1),
+            ("Cancion2", 2)
+        );
+
+        // El historial guarda solo las ultimas reproducciones
+
+        cola.set_repeticion(ModoRepeticion::Todas).unwrap();
+        for _ in 0..LIMITE_HISTORIAL {
+            cola.siguiente().unwrap();
+        }
+        assert_eq!(cola.get_historial().len(), LIMITE_HISTORIAL);
+    }
+
+    #[test]
+    fn test_retomar_reproduccion() {
+        let mut cola = crear_cola(4);
+        cola.siguiente().unwrap();
+        cola.siguiente().unwrap();
+        cola.avanzar(95).unwrap();
+
+        let recuperada = ColaReproduccion::recuperar(cola.path_file.clone()).unwrap();
+
+        assert_eq!(titulo(recuperada.get_actual()), Some("Cancion2"));
+        assert_eq!(recuperada.posicion, 95);
+        assert_eq!(recuperada.get_historial().len(), 2);
+        assert_eq!(recuperada.mas_reproducidas(1)[0].1, 1);
+
+        let e = ColaReproduccion::recuperar("test_files/no_existe.json".to_string()).unwrap_err();
+        assert!(matches!(e, ErrorPlaylist::AbrirArchivo));
+
+        // Una cancion actual fuera de la cola se descarta al recuperar
+
+        let mut guardada: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&cola.path_file).unwrap()).unwrap();
+        guardada["actual"] = 10.into();
+        File::create(&cola.path_file)
+            .unwrap()
+            .write_all(guardada.to_string().as_bytes())
+            .unwrap();
+        let mut recuperada = ColaReproduccion::recuperar(cola.path_file.clone()).unwrap();
+        assert!(recuperada.get_actual().is_none());
+        assert_eq!(recuperada.posicion, 0);
+        assert!(recuperada.avanzar(10).unwrap().is_none());
+
+        // Una cola nueva de la misma playlist sigue contando las reproducciones
+
+        let mut nueva = crear_cola(4);
+        assert_eq!(nueva.mas_reproducidas(5).len(), 2);
+        nueva.siguiente().unwrap();
+        assert_eq!(
+            (
+                nueva.mas_reproducidas(1)[0].0.titulo.as_str(),
+                nueva.mas_reproducidas(1)[0].1
+            ),
+            ("Cancion1", 2)
+        );
+        assert_eq!(nueva.get_historial().len(), 1);
+
+        File::create(&nueva.path_reproducciones)
+            .unwrap()
+            .write_all(b"[1]")
+            .unwrap();
+        assert!(matches!(
+            ColaReproduccion::recuperar(nueva.path_file.clone()),
+            Err(ErrorPlaylist::FormatearCanciones)
+        ));
+
+        // El estado en memoria avanza aunque no se pueda guardar
+
+        cola.path_file = "/".to_string();
+        assert!(cola.siguiente().is_err());
+        assert_eq!(titulo(cola.get_actual()), Some("Cancion3"));
+    }
+}
@@ -1,140 +1,581 @@
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::{fmt::Display, fs::File, fs::OpenOptions, io::prelude::*, path::Path};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt::Display, fs::File, io::prelude::*, path::Path};
 
 use crate::tp3::ej03::Fecha;
 
+mod agenda;
+mod especies;
+mod facturacion;
+mod historia;
+mod pacientes;
+
+// Cada cierta cantidad de minutos de espera, la mascota sube un nivel de urgencia
+const MINUTOS_ENVEJECIMIENTO: u32 = 30;
+const DURACION_TURNO: u32 = 30;
+
 struct Veterinaria {
     nombre: String,
     direccion: String,
     id: u32,
-    cola_atencion: VecDeque<Mascota>, // BinaryHeap requiere Ord trait (tema no correspondiente a la practica)
+    cola_atencion: Vec<Turno>, // La prioridad cambia con la espera, por lo que no se usa un BinaryHeap
     registro_atencion: Vec<Atencion>,
+    pacientes: Pacientes,
+    agenda: Agenda,
+    inventario: Vec<Item>,
     file_path: String,
+    path_cola: String,
+    path_agenda: String,
+    path_pacientes: String,
+    path_inventario: String,
 }
 
+// Registro de duenios y mascotas. Las atenciones, turnos y citas referencian a la mascota por
+//su id, por lo que los cambios en los datos de contacto no afectan al historial
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Pacientes {
+    duenios: Vec<Duenio>,
+    mascotas: Vec<Mascota>,
+    #[serde(default = "Especie::catalogo_base")]
+    especies: Vec<Especie>,
+}
+
+// La especie corresponde a una del catalogo (los registros previos la guardaban como tipo).
+//Para los registros previos, la fecha de nacimiento se estima a partir de la edad guardada
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 struct Mascota {
+    id: u32,
     nombre: String,
-    edad: u32,
-    tipo: Animales,
-    duenio: Duenio,
+    #[serde(default)]
+    fecha_nacimiento: Option<Fecha>,
+    #[serde(alias = "tipo")]
+    especie: String,
+    #[serde(default)]
+    raza: Option<String>,
+    id_duenio: u32,
+}
+
+// Especie del catalogo, con su calendario de vacunacion y las dosis admitidas segun el peso.
+//Los medicamentos sin rangos de dosis no se validan
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Especie {
+    nombre: String,
+    razas: Vec<String>,
+    calendario: Vec<VacunaCalendario>,
+    rangos_dosis: Vec<RangoDosis>,
+}
+
+// Vacuna a aplicar desde cierta edad, con refuerzos periodicos opcionales
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct VacunaCalendario {
+    vacuna: String,
+    edad_semanas: u32,
+    refuerzo_dias: Option<u32>,
 }
 
+// Dosis por toma admitida para un medicamento en una franja de peso [peso_desde, peso_hasta)
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
-enum Animales {
-    Perro,
-    Gato,
-    Caballo,
-    Otros,
+struct RangoDosis {
+    medicamento: String,
+    peso_desde: f64,
+    peso_hasta: f64,
+    dosis_min_mg: f64,
+    dosis_max_mg: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 struct Duenio {
+    #[serde(default)]
+    id: u32,
     nombre: String,
     direccion: String,
     telefono: String,
 }
 
+// Libro de turnos. La duracion de los turnos puede configurarse, cada cita conserva la
+//duracion con la que fue reservada
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Agenda {
+    duracion_turno: u32,
+    veterinarios: Vec<Veterinario>,
+    citas: Vec<Cita>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Veterinario {
+    id: u32,
+    nombre: String,
+    horarios: Vec<HorarioLaboral>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct HorarioLaboral {
+    dia: DiaSemana,
+    desde: Hora,
+    hasta: Hora,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum DiaSemana {
+    Lunes,
+    Martes,
+    Miercoles,
+    Jueves,
+    Viernes,
+    Sabado,
+    Domingo,
+}
+
+// Las citas tentativas se generan a partir de la proxima visita de una atencion, y guardan
+//el id de esa atencion
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Cita {
+    id: u32,
+    id_veterinario: u32,
+    id_mascota: u32,
+    fecha: Fecha,
+    inicio: Hora,
+    duracion: u32,
+    estado: EstadoCita,
+    #[serde(default)]
+    id_atencion: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum EstadoCita {
+    Tentativa,
+    Confirmada,
+}
+
+// Hora del dia, con precision de minutos
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Hora {
+    horas: u32,
+    minutos: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum Urgencia {
+    Baja,
+    Media,
+    Alta,
+    Critica,
+}
+
+// El numero indica el orden de llegada y desempata mascotas que llegaron en el mismo minuto.
+//La fecha permite que la espera se siga contando si la cola pasa de un dia a otro
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Turno {
+    id_mascota: u32,
+    urgencia: Urgencia,
+    fecha_llegada: Fecha,
+    llegada: Hora,
+    numero: u32,
+}
+
+// Ademas del diagnostico, cada atencion registra los datos clinicos relevados en la visita.
+//Los campos con valor por defecto permiten leer registros previos
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 struct Atencion {
-    mascota: Mascota,
+    #[serde(default)]
+    id: u32,
+    id_mascota: u32,
     diagnostico: String,
     tratamiento: String,
     prox_visita: Option<Fecha>,
+    #[serde(default)]
+    fecha: Option<Fecha>,
+    #[serde(default)]
+    peso: Option<f64>,
+    #[serde(default)]
+    vacunas: Vec<Vacuna>,
+    #[serde(default)]
+    recetas: Vec<Receta>,
+    #[serde(default)]
+    alergias: Vec<String>,
+    #[serde(default)]
+    factura: Option<Factura>,
+}
+
+// Lista de precios de servicios, medicamentos e insumos. Los servicios no llevan stock
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Item {
+    codigo: String,
+    descripcion: String,
+    tipo: TipoItem,
+    precio: f64,
+    stock: u32,
+    stock_minimo: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum TipoItem {
+    Servicio,
+    Medicamento,
+    Insumo,
+}
+
+// Cada linea conserva el precio vigente al momento de facturar
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Factura {
+    numero: u32,
+    fecha: Fecha,
+    medio_pago: MedioPago,
+    lineas: Vec<LineaFactura>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct LineaFactura {
+    codigo: String,
+    descripcion: String,
+    cantidad: u32,
+    precio_unitario: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum MedioPago {
+    Efectivo,
+    Debito,
+    Credito,
+    Transferencia,
+}
+
+// Sin proxima dosis, la vacuna no requiere refuerzo
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Vacuna {
+    nombre: String,
+    lote: String,
+    fecha_aplicacion: Fecha,
+    proxima_dosis: Option<Fecha>,
+}
+
+// La dosis se expresa en miligramos por toma
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+struct Receta {
+    medicamento: String,
+    dosis_mg: f64,
+    frecuencia_horas: u32,
+    duracion_dias: u32,
 }
 
 impl Veterinaria {
-    fn new(nombre: String, direccion: String, id: u32, file_path: String) -> Veterinaria {
+    fn new(
+        nombre: String,
+        direccion: String,
+        id: u32,
+        file_path: String,
+        path_cola: String,
+        path_agenda: String,
+        path_pacientes: String,
+    ) -> Veterinaria {
+        // El inventario se guarda junto al registro de atenciones: atenciones.json se acompania
+        //de atenciones.inventario.json
+        let path_inventario = Path::new(&file_path)
+            .with_extension("inventario.json")
+            .to_string_lossy()
+            .to_string();
+
         Veterinaria {
             nombre,
             direccion,
             id,
-            cola_atencion: VecDeque::new(),
+            cola_atencion: Vec::new(),
             registro_atencion: Vec::new(),
+            pacientes: Pacientes {
+                duenios: Vec::new(),
+                mascotas: Vec::new(),
+                especies: Especie::catalogo_base(),
+            },
+            agenda: Agenda {
+                duracion_turno: DURACION_TURNO,
+                veterinarios: Vec::new(),
+                citas: Vec::new(),
+            },
+            inventario: Vec::new(),
             file_path,
+            path_cola,
+            path_agenda,
+            path_pacientes,
+            path_inventario,
         }
     }
 
+    fn escribir_archivo<T: Serialize>(path: &str, elemento: &T) -> Result<(), ErrorVeterinaria> {
+        let Ok(mut f) = File::create(path) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        let Ok(elem) = serde_json::to_string_pretty(elemento) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        let Ok(_) = f.write_all(elem.as_bytes()) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        Ok(())
+    }
+
+    // Un archivo inexistente devuelve None, para que cada registro lo considere vacio
+    fn leer_archivo<T: DeserializeOwned>(path: &str) -> Result<Option<T>, ErrorVeterinaria> {
+        let Ok(mut f) = File::open(path) else {
+            return Ok(None);
+        };
+
+        let mut buf = String::new();
+        let Ok(_) = f.read_to_string(&mut buf) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        let Ok(elem) = serde_json::from_str::<T>(&buf) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        Ok(Some(elem))
+    }
+
     fn actualizar_archivo(&self, atenciones: &Vec<Atencion>) -> Result<(), ErrorVeterinaria> {
-        match File::create(&self.file_path) {
-            Ok(mut f) => {
-                let Ok(at) = serde_json::to_string_pretty(&atenciones) else {
-                    return Err(ErrorVeterinaria::Archivo);
-                };
+        Self::escribir_archivo(&self.file_path, atenciones)
+    }
 
-                let Ok(_) = f.write_all(&at.as_bytes()) else {
-                    return Err(ErrorVeterinaria::Archivo);
-                };
+    fn guardar_cola(&self) -> Result<(), ErrorVeterinaria> {
+        Self::escribir_archivo(&self.path_cola, &self.cola_atencion)
+    }
 
-                Ok(())
-            }
-            Err(_) => Err(ErrorVeterinaria::Archivo),
+    fn recuperar_cola(&mut self) -> Result<(), ErrorVeterinaria> {
+        self.cola_atencion = Self::leer_archivo(&self.path_cola)?.unwrap_or_default();
+
+        Ok(())
+    }
+
+    // Si no puede guardarse la cola, la mascota no se agrega
+    fn agregar_mascota(
+        &mut self,
+        id_mascota: u32,
+        urgencia: Urgencia,
+        fecha_llegada: Fecha,
+        llegada: Hora,
+    ) -> Result<(), ErrorVeterinaria> {
+        if self.get_mascota(id_mascota).is_none() {
+            return Err(ErrorVeterinaria::Mascota);
+        }
+
+        if self
+            .cola_atencion
+            .iter()
+            .any(|t| t.id_mascota == id_mascota)
+        {
+            return Err(ErrorVeterinaria::MascotaEnEspera);
         }
+
+        let numero = self
+            .cola_atencion
+            .iter()
+            .map(|t| t.numero)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        self.cola_atencion.push(Turno {
+            id_mascota,
+            urgencia,
+            fecha_llegada,
+            llegada,
+            numero,
+        });
+
+        if let Err(e) = self.guardar_cola() {
+            self.cola_atencion.pop();
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    // Orden en que se atenderian las mascotas en el momento dado: por prioridad (urgencia mas
+    //envejecimiento) y, a igual prioridad, por orden de llegada
+    fn orden_atencion(&self, fecha_actual: &Fecha, hora_actual: &Hora) -> Vec<&Turno> {
+        let mut turnos: Vec<&Turno> = self.cola_atencion.iter().collect();
+
+        turnos.sort_by(|a, b| {
+            b.prioridad(fecha_actual, hora_actual)
+                .cmp(&a.prioridad(fecha_actual, hora_actual))
+                .then(
+                    b.minutos_espera(fecha_actual, hora_actual)
+                        .cmp(&a.minutos_espera(fecha_actual, hora_actual)),
+                )
+                .then(a.numero.cmp(&b.numero))
+        });
+
+        turnos
     }
 
-    fn agregar_mascota(&mut self, mascota: Mascota) {
-        self.cola_atencion.push_back(mascota);
+    // Devuelve el id de la mascota atendida. Si no puede guardarse la cola, la mascota
+    //permanece en ella
+    fn atender_mascota(
+        &mut self,
+        fecha_actual: &Fecha,
+        hora_actual: &Hora,
+    ) -> Result<Option<u32>, ErrorVeterinaria> {
+        let Some(numero) = self
+            .orden_atencion(fecha_actual, hora_actual)
+            .first()
+            .map(|t| t.numero)
+        else {
+            return Ok(None);
+        };
+
+        let index = self
+            .cola_atencion
+            .iter()
+            .position(|t| t.numero == numero)
+            .unwrap();
+        let turno = self.cola_atencion.remove(index);
+
+        if let Err(e) = self.guardar_cola() {
+            self.cola_atencion.insert(index, turno);
+            return Err(e);
+        }
+
+        Ok(Some(turno.id_mascota))
     }
 
-    fn agregar_mascota_prioritaria(&mut self, mascota: Mascota) {
-        self.cola_atencion.push_front(mascota);
+    fn retirar_mascota(&mut self, id_mascota: u32) -> Result<bool, ErrorVeterinaria> {
+        let Some(index) = self
+            .cola_atencion
+            .iter()
+            .position(|t| t.id_mascota == id_mascota)
+        else {
+            return Ok(false);
+        };
+
+        let turno = self.cola_atencion.remove(index);
+
+        if let Err(e) = self.guardar_cola() {
+            self.cola_atencion.insert(index, turno);
+            return Err(e);
+        }
+
+        Ok(true)
     }
 
-    fn atender_mascota(&mut self) -> Option<Mascota> {
-        self.cola_atencion.pop_front()
+    // Suma de la duracion estimada de las atenciones de las mascotas que estan antes en la cola
+    fn espera_estimada(
+        &self,
+        id_mascota: u32,
+        fecha_actual: &Fecha,
+        hora_actual: &Hora,
+    ) -> Option<u32> {
+        let orden = self.orden_atencion(fecha_actual, hora_actual);
+        let posicion = orden.iter().position(|t| t.id_mascota == id_mascota)?;
+
+        Some(
+            orden[..posicion]
+                .iter()
+                .map(|t| t.urgencia.duracion_estimada())
+                .sum(),
+        )
     }
 
-    fn retirar_mascota(&mut self, mascota: &Mascota) -> bool {
-        match self.cola_atencion.iter().position(|m| m.eq(mascota)) {
-            Some(index) => {
-                self.cola_atencion.remove(index);
-                return true;
+    // Recupera duenios, mascotas y atenciones. Las atenciones con el formato previo guardaban
+    //una copia de la mascota, que se registra (o se reutiliza) y se reemplaza por su id
+    fn recuperar_registros(&mut self) -> Result<(), ErrorVeterinaria> {
+        self.recuperar_pacientes()?;
+
+        let Some(valores) = Self::leer_archivo::<Vec<serde_json::Value>>(&self.file_path)? else {
+            return Ok(());
+        };
+
+        let previos = self.pacientes.clone();
+        let mut atenciones: Vec<Atencion> = Vec::new();
+
+        for mut valor in valores {
+            if let Some(mascota) = valor.as_object_mut().and_then(|a| a.remove("mascota")) {
+                let Some(id) = self.migrar_mascota(mascota) else {
+                    self.pacientes = previos;
+                    return Err(ErrorVeterinaria::Archivo);
+                };
+
+                valor["id_mascota"] = id.into();
             }
-            None => (),
+
+            let Ok(atencion) = serde_json::from_value::<Atencion>(valor) else {
+                self.pacientes = previos;
+                return Err(ErrorVeterinaria::Archivo);
+            };
+
+            atenciones.push(atencion);
         }
 
-        false
-    }
+        // Las atenciones previas no tenian id
+        let mut id = atenciones.iter().map(|a| a.id).max().unwrap_or(0);
+        for at in atenciones.iter_mut().filter(|a| a.id == 0) {
+            id += 1;
+            at.id = id;
+        }
 
-    fn registrar_atencion(&mut self, atencion: Atencion) -> Result<(), ErrorVeterinaria> {
-        self.registro_atencion.push(atencion);
+        self.registro_atencion = atenciones;
+
+        self.guardar_pacientes(previos)?;
         self.actualizar_archivo(&self.registro_atencion)
     }
 
-    fn buscar_atencion(
-        &self,
-        nombre_mascota: &String,
-        nombre_duenio: &String,
-        telefono: &String,
-    ) -> Option<&Atencion> {
-        match self
+    // Devuelve el id asignado a la atencion. Si se indica una proxima visita, se reserva un
+    //turno tentativo para ese dia. Si no puede guardarse la agenda o el registro, la atencion
+    //no se registra
+    fn registrar_atencion(&mut self, mut atencion: Atencion) -> Result<u32, ErrorVeterinaria> {
+        let Some(mascota) = self.get_mascota(atencion.id_mascota) else {
+            return Err(ErrorVeterinaria::Mascota);
+        };
+
+        self.validar_recetas(mascota, &atencion)?;
+
+        atencion.id = self
             .registro_atencion
             .iter()
-            .position(|at| at.comparar_atencion(nombre_mascota, nombre_duenio, telefono))
-        {
-            Some(index) => return self.registro_atencion.get(index),
-            None => (),
-        };
+            .map(|at| at.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+        let id = atencion.id;
+
+        if let Some(fecha) = &atencion.prox_visita {
+            self.reservar_tentativa(id, atencion.id_mascota, fecha)?;
+        }
 
-        None
+        let id_mascota = atencion.id_mascota;
+        let prox_visita = atencion.prox_visita.clone();
+        self.registro_atencion.push(atencion);
+
+        if let Err(e) = self.actualizar_archivo(&self.registro_atencion) {
+            self.registro_atencion.pop();
+            let _ = self.reprogramar_tentativa(id, id_mascota, prox_visita.as_ref(), None);
+            return Err(e);
+        }
+
+        Ok(id)
     }
 
-    fn get_pos_atencion(&self, atencion: &Atencion) -> Option<usize> {
-        match self.registro_atencion.iter().position(|at| at.eq(atencion)) {
-            Some(index) => return Some(index),
-            None => (),
-        };
+    fn get_atencion(&self, id: u32) -> Option<&Atencion> {
+        self.registro_atencion.iter().find(|at| at.id == id)
+    }
 
-        None
+    fn atenciones_mascota(&self, id_mascota: u32) -> Vec<&Atencion> {
+        self.registro_atencion
+            .iter()
+            .filter(|at| at.id_mascota == id_mascota)
+            .collect()
+    }
+
+    fn get_pos_atencion(&self, id: u32) -> Option<usize> {
+        self.registro_atencion.iter().position(|at| at.id == id)
     }
 
     fn modificar_diagnostico(
         &mut self,
-        atencion: &Atencion,
+        id_atencion: u32,
         nuevo_diagnostico: String,
     ) -> Result<(), ErrorVeterinaria> {
-        match self.get_pos_atencion(atencion) {
+        match self.get_pos_atencion(id_atencion) {
             Some(index) => {
                 self.registro_atencion
                     .get_mut(index)
@@ -149,28 +590,45 @@ impl Veterinaria {
 
     fn modificar_fecha_visita(
         &mut self,
-        atencion: &Atencion,
+        id_atencion: u32,
         nueva_fecha: Option<Fecha>,
     ) -> Result<(), ErrorVeterinaria> {
-        match self.get_pos_atencion(atencion) {
+        match self.get_pos_atencion(id_atencion) {
             Some(index) => {
-                self.registro_atencion
-                    .get_mut(index)
-                    .unwrap()
-                    .set_prox_fecha(nueva_fecha);
+                let atencion = self.registro_atencion.get_mut(index).unwrap();
+                let anterior = atencion.prox_visita.clone();
+                let id_mascota = atencion.id_mascota;
+
+                atencion.set_prox_fecha(nueva_fecha.clone());
+
+                let resultado_agenda = self.reprogramar_tentativa(
+                    id_atencion,
+                    id_mascota,
+                    anterior.as_ref(),
+                    nueva_fecha.as_ref(),
+                );
 
                 self.actualizar_archivo(&self.registro_atencion)
+                    .and(resultado_agenda)
             }
             None => Err(ErrorVeterinaria::ModificarAtencion),
         }
     }
 
-    fn eliminar_atencion(&mut self, atencion: &Atencion) -> Result<(), ErrorVeterinaria> {
-        match self.get_pos_atencion(atencion) {
+    fn eliminar_atencion(&mut self, id_atencion: u32) -> Result<(), ErrorVeterinaria> {
+        match self.get_pos_atencion(id_atencion) {
             Some(index) => {
-                self.registro_atencion.remove(index);
+                let atencion = self.registro_atencion.remove(index);
+
+                let resultado_agenda = self.reprogramar_tentativa(
+                    id_atencion,
+                    atencion.id_mascota,
+                    atencion.prox_visita.as_ref(),
+                    None,
+                );
 
                 self.actualizar_archivo(&self.registro_atencion)
+                    .and(resultado_agenda)
             }
             None => Err(ErrorVeterinaria::EliminarAtencion),
         }
@@ -178,19 +636,115 @@ impl Veterinaria {
 }
 
 impl Mascota {
-    fn new(nombre: String, edad: u32, tipo: Animales, duenio: Duenio) -> Mascota {
+    // El id se asigna al registrar la mascota
+    fn new(nombre: String, fecha_nacimiento: Fecha, especie: String, id_duenio: u32) -> Mascota {
+        if !fecha_nacimiento.es_fecha_valida() {
+            panic!("Fecha no valida");
+        }
+
         Mascota {
+            id: 0,
             nombre,
-            edad,
-            tipo,
-            duenio,
+            fecha_nacimiento: Some(fecha_nacimiento),
+            especie,
+            raza: None,
+            id_duenio,
+        }
+    }
+
+    fn set_raza(&mut self, raza: String) {
+        self.raza = Some(raza);
+    }
+
+    // Edad en anios cumplidos a la fecha dada
+    fn edad(&self, fecha_actual: &Fecha) -> Option<u32> {
+        let nacimiento = self.fecha_nacimiento.as_ref()?;
+
+        if nacimiento.es_mayor(fecha_actual) {
+            return Some(0);
+        }
+
+        let mut anios = (fecha_actual.get_anio() - nacimiento.get_anio()) as u32;
+        if (fecha_actual.get_mes(), fecha_actual.get_dia())
+            < (nacimiento.get_mes(), nacimiento.get_dia())
+        {
+            anios -= 1;
+        }
+
+        Some(anios)
+    }
+
+    fn edad_semanas(&self, fecha_actual: &Fecha) -> Option<u32> {
+        let dias = self.fecha_nacimiento.as_ref()?.dias_hasta(fecha_actual);
+
+        Some((dias.max(0) / 7) as u32)
+    }
+}
+
+impl Hora {
+    fn new(horas: u32, minutos: u32) -> Hora {
+        if horas > 23 || minutos > 59 {
+            panic!("Hora no valida");
+        }
+
+        Hora { horas, minutos }
+    }
+
+    fn to_minutos(&self) -> u32 {
+        self.horas * 60 + self.minutos
+    }
+
+    fn from_minutos(minutos: u32) -> Hora {
+        Hora::new(minutos / 60, minutos % 60)
+    }
+}
+
+impl Urgencia {
+    fn nivel(&self) -> u32 {
+        match self {
+            Urgencia::Baja => 0,
+            Urgencia::Media => 1,
+            Urgencia::Alta => 2,
+            Urgencia::Critica => 3,
+        }
+    }
+
+    // Duracion estimada de la atencion, en minutos
+    fn duracion_estimada(&self) -> u32 {
+        match self {
+            Urgencia::Baja => 15,
+            Urgencia::Media => 20,
+            Urgencia::Alta => 30,
+            Urgencia::Critica => 45,
         }
     }
 }
 
+impl Turno {
+    // Minutos transcurridos desde la llegada (0 si el momento dado es anterior)
+    fn minutos_espera(&self, fecha_actual: &Fecha, hora_actual: &Hora) -> u32 {
+        let dias = self.fecha_llegada.dias_hasta(fecha_actual);
+        let minutos =
+            dias * 24 * 60 + hora_actual.to_minutos() as i64 - self.llegada.to_minutos() as i64;
+
+        minutos.max(0) as u32
+    }
+
+    // El envejecimiento puede llevar una mascota hasta el nivel Alta, pero nunca la iguala
+    //con una urgencia critica
+    fn prioridad(&self, fecha_actual: &Fecha, hora_actual: &Hora) -> u32 {
+        let nivel = self.urgencia.nivel();
+        let espera = self.minutos_espera(fecha_actual, hora_actual);
+        let maximo = nivel.max(Urgencia::Alta.nivel());
+
+        (nivel + espera / MINUTOS_ENVEJECIMIENTO).min(maximo)
+    }
+}
+
 impl Duenio {
     fn new(nombre: String, direccion: String, telefono: String) -> Duenio {
         Duenio {
+            id: 0,
             nombre,
             direccion,
             telefono,
@@ -199,14 +753,16 @@ impl Duenio {
 }
 
 impl Atencion {
+    // El id se asigna al registrar la atencion
     fn new(
-        mascota: Mascota,
+        id_mascota: u32,
         diagnostico: String,
         tratamiento: String,
         prox_visita: Option<Fecha>,
     ) -> Atencion {
         Atencion {
-            mascota,
+            id: 0,
+            id_mascota,
             diagnostico,
             tratamiento,
             prox_visita: if let Some(ref f) = prox_visita {
@@ -217,18 +773,41 @@ impl Atencion {
             } else {
                 None
             },
+            fecha: None,
+            peso: None,
+            vacunas: Vec::new(),
+            recetas: Vec::new(),
+            alergias: Vec::new(),
+            factura: None,
         }
     }
 
-    fn comparar_atencion(
-        &self,
-        nombre_mascota: &String,
-        nombre_duenio: &String,
-        telefono: &String,
-    ) -> bool {
-        self.mascota.nombre.eq(nombre_mascota)
-            && self.mascota.duenio.nombre.eq(nombre_duenio)
-            && self.mascota.duenio.telefono.eq(telefono)
+    fn set_fecha(&mut self, fecha: Fecha) {
+        if !fecha.es_fecha_valida() {
+            panic!("Fecha no valida");
+        }
+
+        self.fecha = Some(fecha);
+    }
+
+    fn set_peso(&mut self, peso: f64) {
+        if peso <= 0.0 {
+            panic!("Peso no valido");
+        }
+
+        self.peso = Some(peso);
+    }
+
+    fn agregar_vacuna(&mut self, vacuna: Vacuna) {
+        self.vacunas.push(vacuna);
+    }
+
+    fn agregar_receta(&mut self, receta: Receta) {
+        self.recetas.push(receta);
+    }
+
+    fn agregar_alergia(&mut self, alergia: String) {
+        self.alergias.push(alergia);
     }
 
     fn set_diagnostico(&mut self, diagnostico: String) {
@@ -246,11 +825,76 @@ impl Atencion {
     }
 }
 
+impl Vacuna {
+    fn new(
+        nombre: String,
+        lote: String,
+        fecha_aplicacion: Fecha,
+        proxima_dosis: Option<Fecha>,
+    ) -> Vacuna {
+        if proxima_dosis
+            .as_ref()
+            .is_some_and(|f| !f.es_mayor(&fecha_aplicacion))
+        {
+            panic!("La proxima dosis debe ser posterior a la aplicacion");
+        }
+
+        Vacuna {
+            nombre,
+            lote,
+            fecha_aplicacion,
+            proxima_dosis,
+        }
+    }
+}
+
+impl Receta {
+    fn new(
+        medicamento: String,
+        dosis_mg: f64,
+        frecuencia_horas: u32,
+        duracion_dias: u32,
+    ) -> Receta {
+        if dosis_mg <= 0.0 || frecuencia_horas == 0 || duracion_dias == 0 {
+            panic!("Receta no valida");
+        }
+
+        Receta {
+            medicamento,
+            dosis_mg,
+            frecuencia_horas,
+            duracion_dias,
+        }
+    }
+
+    // Cantidad total de tomas durante el tratamiento
+    fn cantidad_tomas(&self) -> u32 {
+        self.duracion_dias * 24 / self.frecuencia_horas
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum ErrorVeterinaria {
     Archivo,
     EliminarAtencion,
     ModificarAtencion,
+    Veterinario,
+    Horario,
+    TurnoOcupado,
+    Cita,
+    Mascota,
+    MascotaEnEspera,
+    Duenio,
+    Atencion,
+    AtencionFacturada,
+    Item,
+    ItemDuplicado,
+    StockInsuficiente,
+    Especie,
+    EspecieDuplicada,
+    Raza,
+    Peso,
+    Dosis,
 }
 
 impl Display for ErrorVeterinaria {
@@ -264,6 +908,33 @@ impl Display for ErrorVeterinaria {
             ErrorVeterinaria::ModificarAtencion => {
                 write!(f, "La atencion no se ha podido modificar")
             }
+            ErrorVeterinaria::Veterinario => write!(f, "El veterinario no existe"),
+            ErrorVeterinaria::Horario => {
+                write!(f, "El turno esta fuera del horario laboral del veterinario")
+            }
+            ErrorVeterinaria::TurnoOcupado => write!(f, "El turno ya se encuentra ocupado"),
+            ErrorVeterinaria::Cita => write!(f, "La cita no existe"),
+            ErrorVeterinaria::Mascota => write!(f, "La mascota no existe"),
+            ErrorVeterinaria::MascotaEnEspera => {
+                write!(f, "La mascota ya se encuentra en la cola de atencion")
+            }
+            ErrorVeterinaria::Duenio => write!(f, "El duenio no existe"),
+            ErrorVeterinaria::Atencion => write!(f, "La atencion no existe"),
+            ErrorVeterinaria::AtencionFacturada => write!(f, "La atencion ya fue facturada"),
+            ErrorVeterinaria::Item => write!(f, "El item no existe"),
+            ErrorVeterinaria::ItemDuplicado => write!(f, "Ya existe un item con ese codigo"),
+            ErrorVeterinaria::StockInsuficiente => {
+                write!(f, "No hay stock suficiente del item")
+            }
+            ErrorVeterinaria::Especie => write!(f, "La especie no existe en el catalogo"),
+            ErrorVeterinaria::EspecieDuplicada => write!(f, "La especie ya existe en el catalogo"),
+            ErrorVeterinaria::Raza => write!(f, "La raza no corresponde a la especie"),
+            ErrorVeterinaria::Peso => {
+                write!(f, "Se requiere el peso de la mascota para validar la dosis")
+            }
+            ErrorVeterinaria::Dosis => {
+                write!(f, "La dosis esta fuera del rango admitido para la especie")
+            }
         }
     }
 }
@@ -272,254 +943,412 @@ impl Display for ErrorVeterinaria {
 mod test {
     use super::*;
 
-    fn creacion_veterinaria() -> Veterinaria {
-        let mut veterinaria = Veterinaria::new(
+    fn hoy() -> Fecha {
+        Fecha::new(3, 6, 2024)
+    }
+
+    fn nueva_veterinaria(file_path: &str, path_cola: &str, path_pacientes: &str) -> Veterinaria {
+        Veterinaria::new(
             "Veterinaria".to_string(),
             "Direccion".to_string(),
             5,
+            file_path.to_string(),
+            path_cola.to_string(),
             Default::default(),
-        );
+            path_pacientes.to_string(),
+        )
+    }
 
-        let mut v = vec![
-            Mascota::new(
-                "Mascota1".to_string(),
-                10,
-                Animales::Perro,
-                Duenio::new(
-                    "Duenio1".to_string(),
-                    "Direccion duenio1".to_string(),
-                    "Telefono1".to_string(),
-                ),
-            ),
-            Mascota::new(
-                "Mascota2".to_string(),
-                15,
-                Animales::Gato,
-                Duenio::new(
-                    "Duenio2".to_string(),
-                    "Direccion duenio2".to_string(),
-                    "Telefono2".to_string(),
-                ),
-            ),
-            Mascota::new(
-                "Mascota3".to_string(),
-                4,
-                Animales::Otros,
-                Duenio::new(
-                    "Duenio3".to_string(),
-                    "Direccion duenio3".to_string(),
-                    "Telefono3".to_string(),
-                ),
-            ),
+    // Registra Mascota1, Mascota2 y Mascota3, cada una con su duenio
+    fn registrar_mascotas(veterinaria: &mut Veterinaria) -> Vec<u32> {
+        let datos = [
+            (Fecha::new(5, 4, 2014), "Perro"),
+            (Fecha::new(20, 8, 2009), "Gato"),
+            (Fecha::new(1, 12, 2020), "Otros"),
         ];
+        let mut ids = Vec::new();
+
+        for (i, (nacimiento, especie)) in datos.into_iter().enumerate() {
+            let id_duenio = veterinaria
+                .registrar_duenio(Duenio::new(
+                    format!("Duenio{}", i + 1),
+                    format!("Direccion duenio{}", i + 1),
+                    format!("Telefono{}", i + 1),
+                ))
+                .unwrap();
+
+            ids.push(
+                veterinaria
+                    .registrar_mascota(Mascota::new(
+                        format!("Mascota{}", i + 1),
+                        nacimiento,
+                        especie.to_string(),
+                        id_duenio,
+                    ))
+                    .unwrap(),
+            );
+        }
 
-        veterinaria.agregar_mascota_prioritaria(v.pop().unwrap());
-        veterinaria.agregar_mascota(v.pop().unwrap());
-        veterinaria.agregar_mascota(v.pop().unwrap());
+        ids
+    }
 
-        veterinaria
+    fn creacion_veterinaria(n: u32) -> (Veterinaria, Vec<u32>) {
+        let mut veterinaria = nueva_veterinaria(
+            "",
+            &format!("test_files/cola_veterinaria{}.json", n),
+            &format!("test_files/pacientes_veterinaria{}.json", n),
+        );
+        let mascotas = registrar_mascotas(&mut veterinaria);
+
+        let urgencias = [Urgencia::Critica, Urgencia::Media, Urgencia::Baja];
+        for (i, urgencia) in urgencias.into_iter().enumerate() {
+            veterinaria
+                .agregar_mascota(mascotas[2 - i], urgencia, hoy(), Hora::new(9, i as u32 * 5))
+                .unwrap();
+        }
+
+        (veterinaria, mascotas)
     }
 
     #[test]
     fn test_veterinaria_vacia() {
-        let mut veterinaria = Veterinaria::new(
-            "Veterinaria".to_string(),
-            "Direccion".to_string(),
-            5,
-            Default::default(),
-        );
-        let m1 = Mascota::new(
-            "Mascota".to_string(),
-            10,
-            Animales::Perro,
-            Duenio::new(
-                "Duenio".to_string(),
-                "Direccion duenio".to_string(),
-                "Telefono".to_string(),
-            ),
+        let mut veterinaria = nueva_veterinaria("", "", "");
+
+        assert_eq!(
+            veterinaria.atender_mascota(&hoy(), &Hora::new(9, 0)),
+            Ok(None)
         );
+        assert_eq!(veterinaria.retirar_mascota(1), Ok(false));
+        assert!(veterinaria
+            .espera_estimada(1, &hoy(), &Hora::new(9, 0))
+            .is_none());
+
+        assert!(veterinaria.get_atencion(1).is_none());
+        assert!(veterinaria.atenciones_mascota(1).is_empty());
+
+        // La mascota debe estar registrada
 
         let at1 = Atencion::new(
-            m1,
+            1,
             "Diagnostico".to_string(),
             "Tratamiento".to_string(),
             None,
         );
-
-        assert!(veterinaria.atender_mascota().is_none());
-
-        assert!(veterinaria
-            .buscar_atencion(
-                &"Mascota".to_string(),
-                &"Duenio".to_string(),
-                &"Telefono".to_string(),
-            )
-            .is_none());
+        let e = veterinaria.registrar_atencion(at1).unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Mascota);
+        println!("{}", e);
+        assert_eq!(
+            veterinaria.agregar_mascota(1, Urgencia::Baja, hoy(), Hora::new(9, 0)),
+            Err(ErrorVeterinaria::Mascota)
+        );
 
         assert_eq!(
-            veterinaria.modificar_diagnostico(&at1, "Nuevo Diagnostico".to_string()),
+            veterinaria.modificar_diagnostico(1, "Nuevo Diagnostico".to_string()),
             Err(ErrorVeterinaria::ModificarAtencion)
         );
         assert_eq!(
-            veterinaria.modificar_fecha_visita(&at1, Some(Fecha::new(5, 4, 2008))),
+            veterinaria.modificar_fecha_visita(1, Some(Fecha::new(5, 4, 2008))),
             Err(ErrorVeterinaria::ModificarAtencion)
         );
         assert_eq!(
-            veterinaria.eliminar_atencion(&at1),
+            veterinaria.eliminar_atencion(1),
             Err(ErrorVeterinaria::EliminarAtencion)
         );
     }
 
     #[test]
     fn test_veterinaria_registrar_mascotas() {
-        let mut veterinaria = Veterinaria::new(
-            "Veterinaria".to_string(),
-            "Direccion".to_string(),
-            5,
-            Default::default(),
-        );
-
-        let m2 = Mascota::new(
-            "Mascota2".to_string(),
-            15,
-            Animales::Gato,
-            Duenio::new(
-                "Duenio2".to_string(),
-                "Direccion duenio2".to_string(),
-                "Telefono2".to_string(),
-            ),
+        let mut veterinaria = nueva_veterinaria(
+            "",
+            "test_files/cola_veterinaria1.json",
+            "test_files/pacientes_veterinaria1.json",
         );
+        let mut v = registrar_mascotas(&mut veterinaria);
+        let (m2, m3) = (v[1], v[2]);
 
-        let mut v = vec![
-            Mascota::new(
-                "Mascota1".to_string(),
-                10,
-                Animales::Perro,
-                Duenio::new(
-                    "Duenio1".to_string(),
-                    "Direccion duenio1".to_string(),
-                    "Telefono1".to_string(),
-                ),
-            ),
-            Mascota::new(
-                "Mascota2".to_string(),
-                15,
-                Animales::Gato,
-                Duenio::new(
-                    "Duenio2".to_string(),
-                    "Direccion duenio2".to_string(),
-                    "Telefono2".to_string(),
-                ),
-            ),
-            Mascota::new(
-                "Mascota3".to_string(),
-                4,
-                Animales::Otros,
-                Duenio::new(
-                    "Duenio3".to_string(),
-                    "Direccion duenio3".to_string(),
-                    "Telefono3".to_string(),
-                ),
-            ),
-        ];
-
-        veterinaria.agregar_mascota_prioritaria(v.pop().unwrap());
-        veterinaria.agregar_mascota(v.pop().unwrap());
-        veterinaria.agregar_mascota(v.pop().unwrap());
+        let hora = Hora::new(10, 0);
+        veterinaria
+            .agregar_mascota(v.pop().unwrap(), Urgencia::Alta, hoy(), hora.clone())
+            .unwrap();
+        veterinaria
+            .agregar_mascota(v.pop().unwrap(), Urgencia::Baja, hoy(), hora.clone())
+            .unwrap();
+        veterinaria
+            .agregar_mascota(v.pop().unwrap(), Urgencia::Baja, hoy(), hora.clone())
+            .unwrap();
 
         // Comprubeba cola de atencion y que se haya otorgado la prioridad
         assert_eq!(veterinaria.cola_atencion.len(), 3);
         assert_eq!(
-            veterinaria.cola_atencion.front().unwrap().nombre,
-            "Mascota3"
+            veterinaria
+                .orden_atencion(&hoy(), &hora)
+                .first()
+                .unwrap()
+                .id_mascota,
+            m3
         );
 
-        assert_eq!(veterinaria.atender_mascota().unwrap().nombre, "Mascota3");
-        assert!(veterinaria.retirar_mascota(&m2));
+        assert_eq!(
+            veterinaria.atender_mascota(&hoy(), &hora).unwrap(),
+            Some(m3)
+        );
+        assert_eq!(veterinaria.retirar_mascota(m2), Ok(true));
+        assert_eq!(veterinaria.retirar_mascota(m2), Ok(false));
     }
 
-    #[should_panic]
     #[test]
-    fn test_registrar_atenciones() {
-        let mut veterinaria = Veterinaria::new(
-            "Veterinaria".to_string(),
-            "Direccion".to_string(),
-            5,
-            "test_files/veterinaria1.json".to_string(),
+    fn test_triage() {
+        let (mut veterinaria, mascotas) = creacion_veterinaria(2);
+
+        // Llegan Mascota3 (critica, 9:00), Mascota2 (media, 9:05) y Mascota1 (baja, 9:10)
+
+        let nombres = |veterinaria: &Veterinaria, hora: Hora| -> Vec<String> {
+            veterinaria
+                .orden_atencion(&hoy(), &hora)
+                .iter()
+                .map(|t| {
+                    veterinaria
+                        .get_mascota(t.id_mascota)
+                        .unwrap()
+                        .nombre
+                        .clone()
+                })
+                .collect()
+        };
+        assert_eq!(
+            nombres(&veterinaria, Hora::new(9, 10)),
+            vec!["Mascota3", "Mascota2", "Mascota1"]
         );
 
-        let m3 = Mascota::new(
-            "Mascota3".to_string(),
-            4,
-            Animales::Otros,
-            Duenio::new(
-                "Duenio3".to_string(),
-                "Direccion duenio3".to_string(),
-                "Telefono3".to_string(),
-            ),
+        // Tiempo de espera estimado segun las atenciones previas
+
+        assert_eq!(
+            veterinaria.espera_estimada(mascotas[2], &hoy(), &Hora::new(9, 10)),
+            Some(0)
+        );
+        assert_eq!(
+            veterinaria.espera_estimada(mascotas[0], &hoy(), &Hora::new(9, 10)),
+            Some(45 + 20)
         );
 
-        let m3_copy = Mascota::new(
-            "Mascota3".to_string(),
-            4,
-            Animales::Otros,
-            Duenio::new(
-                "Duenio3".to_string(),
-                "Direccion duenio3".to_string(),
-                "Telefono3".to_string(),
-            ),
+        // A igual urgencia se atiende primero a quien llego antes, aunque sea en el mismo minuto
+
+        let id_duenio = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Duenio4".to_string(),
+                "Direccion duenio4".to_string(),
+                "Telefono4".to_string(),
+            ))
+            .unwrap();
+        let m4 = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Mascota4".to_string(),
+                Fecha::new(1, 1, 2022),
+                "Perro".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+        let m5 = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Mascota5".to_string(),
+                Fecha::new(1, 1, 2022),
+                "Perro".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+
+        veterinaria
+            .agregar_mascota(m5, Urgencia::Media, hoy(), Hora::new(9, 15))
+            .unwrap();
+        veterinaria
+            .agregar_mascota(m4, Urgencia::Media, hoy(), Hora::new(9, 15))
+            .unwrap();
+        assert_eq!(
+            nombres(&veterinaria, Hora::new(9, 15)),
+            vec!["Mascota3", "Mascota2", "Mascota5", "Mascota4", "Mascota1"]
         );
 
-        let mut at1 = Atencion::new(
-            m3,
-            "Diagnostico".to_string(),
-            "Tratamiento".to_string(),
-            Some(Fecha::new(29, 2, 2020)),
+        // Envejecimiento: tras 30 minutos de espera la mascota de urgencia baja sube a media y
+        //supera a las que llegaron despues. Nunca alcanza a una urgencia critica
+
+        assert_eq!(
+            nombres(&veterinaria, Hora::new(9, 40)),
+            vec!["Mascota3", "Mascota2", "Mascota1", "Mascota5", "Mascota4"]
+        );
+        assert_eq!(
+            nombres(&veterinaria, Hora::new(12, 0)),
+            vec!["Mascota3", "Mascota2", "Mascota1", "Mascota5", "Mascota4"]
+        );
+        assert_eq!(
+            veterinaria.cola_atencion[2].prioridad(&hoy(), &Hora::new(12, 0)),
+            2
+        );
+
+        assert_eq!(
+            veterinaria
+                .atender_mascota(&hoy(), &Hora::new(9, 40))
+                .unwrap(),
+            Some(mascotas[2])
+        );
+        assert_eq!(
+            veterinaria
+                .atender_mascota(&hoy(), &Hora::new(9, 40))
+                .unwrap(),
+            Some(mascotas[1])
+        );
+        assert_eq!(
+            veterinaria.espera_estimada(m4, &hoy(), &Hora::new(9, 40)),
+            Some(15 + 20)
+        );
+
+        // Una mascota que ya esta esperando no puede volver a encolarse
+
+        let e = veterinaria
+            .agregar_mascota(m4, Urgencia::Critica, hoy(), Hora::new(9, 45))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::MascotaEnEspera);
+        println!("{}", e);
+        assert_eq!(veterinaria.cola_atencion.len(), 3);
+    }
+
+    #[test]
+    fn test_triage_cambio_de_dia() {
+        let (mut veterinaria, mascotas) = creacion_veterinaria(4);
+        let manana = Fecha::new(4, 6, 2024);
+
+        for _ in 0..3 {
+            veterinaria
+                .atender_mascota(&hoy(), &Hora::new(9, 10))
+                .unwrap();
+        }
+
+        // La espera cuenta desde la llegada aunque haya sido el dia anterior
+
+        veterinaria
+            .agregar_mascota(mascotas[0], Urgencia::Baja, hoy(), Hora::new(23, 50))
+            .unwrap();
+        veterinaria
+            .agregar_mascota(mascotas[1], Urgencia::Baja, manana.clone(), Hora::new(0, 5))
+            .unwrap();
+
+        let turno = &veterinaria.cola_atencion[0];
+        assert_eq!(turno.minutos_espera(&manana, &Hora::new(0, 20)), 30);
+        assert_eq!(turno.prioridad(&manana, &Hora::new(0, 20)), 1);
+        assert_eq!(turno.minutos_espera(&hoy(), &Hora::new(23, 0)), 0);
+
+        assert_eq!(
+            veterinaria.atender_mascota(&manana, &Hora::new(0, 20)),
+            Ok(Some(mascotas[0]))
+        );
+    }
+
+    #[test]
+    fn test_cola_archivo() {
+        let (mut veterinaria, mascotas) = creacion_veterinaria(3);
+        veterinaria
+            .atender_mascota(&hoy(), &Hora::new(9, 10))
+            .unwrap();
+
+        // Al reiniciar se recupera quien estaba esperando, con su urgencia y hora de llegada
+
+        let mut reiniciada =
+            nueva_veterinaria("", &veterinaria.path_cola, &veterinaria.path_pacientes);
+        assert!(reiniciada.recuperar_pacientes().is_ok());
+        assert!(reiniciada.recuperar_cola().is_ok());
+        assert_eq!(reiniciada.cola_atencion, veterinaria.cola_atencion);
+        assert_eq!(reiniciada.cola_atencion.len(), 2);
+        assert_eq!(reiniciada.cola_atencion[0].urgencia, Urgencia::Media);
+
+        // Si la cola no puede guardarse, no se modifica
+
+        reiniciada.path_cola = "/".to_string();
+        let m = reiniciada.cola_atencion[0].id_mascota;
+
+        let e = reiniciada
+            .agregar_mascota(mascotas[2], Urgencia::Alta, hoy(), Hora::new(10, 0))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Archivo);
+        assert_eq!(
+            reiniciada.atender_mascota(&hoy(), &Hora::new(10, 0)),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(
+            reiniciada.retirar_mascota(m),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(reiniciada.cola_atencion, veterinaria.cola_atencion);
+
+        // Un archivo con formato no valido
+
+        let mut f = File::create("test_files/cola_veterinaria_invalida.json").unwrap();
+        f.write_all(b"[{\"id_mascota\": \"uno\"}]").unwrap();
+        reiniciada.path_cola = "test_files/cola_veterinaria_invalida.json".to_string();
+        assert_eq!(reiniciada.recuperar_cola(), Err(ErrorVeterinaria::Archivo));
+
+        reiniciada.path_cola = "test_files/cola_inexistente.json".to_string();
+        assert!(reiniciada.recuperar_cola().is_ok());
+        assert!(reiniciada.cola_atencion.is_empty());
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_registrar_atenciones() {
+        let mut veterinaria = nueva_veterinaria(
+            "test_files/veterinaria1.json",
+            "",
+            "test_files/pacientes_veterinaria6.json",
         );
+        let mascotas = registrar_mascotas(&mut veterinaria);
+
+        // Dos atenciones con los mismos datos son atenciones distintas
 
-        veterinaria.registrar_atencion(Atencion::new(
-            m3_copy,
+        let at1 = Atencion::new(
+            mascotas[2],
             "Diagnostico".to_string(),
             "Tratamiento".to_string(),
             Some(Fecha::new(29, 2, 2020)),
-        ));
+        );
+        let id1 = veterinaria.registrar_atencion(at1.clone()).unwrap();
+        let id2 = veterinaria.registrar_atencion(at1).unwrap();
+        assert_ne!(id1, id2);
 
         assert!(veterinaria
-            .registro_atencion
-            .first()
+            .get_atencion(id1)
             .unwrap()
             .prox_visita
             .as_ref()
             .is_some_and(|f| f.eq(&Fecha::new(29, 2, 2020))));
+        assert_eq!(veterinaria.atenciones_mascota(mascotas[2]).len(), 2);
 
-        assert!(veterinaria
-            .buscar_atencion(
-                &"Mascota3".to_string(),
-                &"Duenio3".to_string(),
-                &"Telefono3".to_string()
-            )
-            .is_some_and(|f| f.eq(&at1)));
-
-        veterinaria.modificar_fecha_visita(&at1, Some(Fecha::new(1, 2, 2000)));
-
-        at1.set_prox_fecha(Some(Fecha::new(1, 2, 2000)));
+        // Modificar una atencion no cambia su identidad
 
-        veterinaria.modificar_diagnostico(&at1, "Nuevo diagnostico".to_string());
+        veterinaria
+            .modificar_fecha_visita(id1, Some(Fecha::new(1, 2, 2000)))
+            .unwrap();
+        veterinaria
+            .modificar_diagnostico(id1, "Nuevo diagnostico".to_string())
+            .unwrap();
         assert_eq!(
-            veterinaria.registro_atencion.first().unwrap().diagnostico,
+            veterinaria.get_atencion(id1).unwrap().diagnostico,
             "Nuevo diagnostico"
         );
+        assert_eq!(
+            veterinaria.get_atencion(id2).unwrap().diagnostico,
+            "Diagnostico"
+        );
 
+        assert_eq!(veterinaria.eliminar_atencion(id1), Ok(()));
         assert_eq!(
-            veterinaria.eliminar_atencion(&at1),
+            veterinaria.eliminar_atencion(id1),
             Err(ErrorVeterinaria::EliminarAtencion)
         );
-        at1.set_diagnostico("Nuevo diagnostico".to_string());
-        assert_eq!(veterinaria.eliminar_atencion(&at1), Ok(()));
+        assert_eq!(veterinaria.atenciones_mascota(mascotas[2]).len(), 1);
 
         // Intento introducir fecha no valida
 
-        at1.set_prox_fecha(Some(Fecha::new(29, 2, 2021)));
+        veterinaria
+            .modificar_fecha_visita(id2, Some(Fecha::new(29, 2, 2021)))
+            .unwrap();
     }
 
     fn abrir_archivo(path: &String) -> Result<Vec<Atencion>, ErrorVeterinaria> {
@@ -537,29 +1366,12 @@ mod test {
 
     #[test]
     fn test_veterinaria_archivo() {
-        let mut veterinaria = creacion_veterinaria();
+        let (mut veterinaria, mascotas) = creacion_veterinaria(4);
 
         veterinaria.file_path = "test_files/veterinaria2.json".to_string();
 
-        // Si existia archivo previo, corroboro sus datos
-
-        if let Ok(a) = abrir_archivo(&veterinaria.file_path) {
-            assert_eq!(a.len(), 1);
-            assert_eq!(a.first().unwrap().mascota.nombre, "Juancito");
-        };
-
-        let mascota = Mascota::new(
-            "Juancito".to_string(),
-            5,
-            Animales::Gato,
-            Duenio::new(
-                "Pedro".to_string(),
-                "155".to_string(),
-                "2217485463".to_string(),
-            ),
-        );
-        let mut atencion = Atencion::new(
-            mascota.clone(),
+        let atencion = Atencion::new(
+            mascotas[0],
             "Herida moderada".to_string(),
             "Gasa y alcohol".to_string(),
             Some(Fecha::new(5, 6, 2024)),
@@ -567,47 +1379,48 @@ mod test {
 
         // Registro atencion
 
-        assert!(veterinaria.registrar_atencion(atencion.clone()).is_ok());
+        let id = veterinaria.registrar_atencion(atencion).unwrap();
+
+        let a = abrir_archivo(&veterinaria.file_path).unwrap();
+        assert_eq!(a.len(), 1);
+        assert_eq!(a.first().unwrap().id_mascota, mascotas[0]);
 
         // Modifico diagnositco (el cambio debe apreciarse en el archivo)
 
         assert!(veterinaria
-            .modificar_diagnostico(&atencion, "Pequeña herida".to_string())
+            .modificar_diagnostico(id, "Pequeña herida".to_string())
             .is_ok());
 
         // Modifico fecha (el cambio debe apreciarse en el archivo)
 
-        atencion.diagnostico = "Pequeña herida".to_string();
-        assert!(veterinaria.modificar_fecha_visita(&atencion, None).is_ok());
+        assert!(veterinaria.modificar_fecha_visita(id, None).is_ok());
 
-        // Intento eliminar la atencion (no la encontrará ya que tiene los datos viejos)
+        let a = abrir_archivo(&veterinaria.file_path).unwrap();
+        assert_eq!(a.first().unwrap().diagnostico, "Pequeña herida");
+        assert!(a.first().unwrap().prox_visita.is_none());
 
-        assert_eq!(
-            veterinaria.eliminar_atencion(&atencion),
-            Err(ErrorVeterinaria::EliminarAtencion)
-        );
+        // Al reiniciar se recuperan las atenciones junto con las mascotas
+
+        let mut reiniciada =
+            nueva_veterinaria(&veterinaria.file_path, "", &veterinaria.path_pacientes);
+        assert!(reiniciada.recuperar_registros().is_ok());
+        assert_eq!(reiniciada.registro_atencion, veterinaria.registro_atencion);
+        assert_eq!(reiniciada.pacientes, veterinaria.pacientes);
+
+        assert_eq!(veterinaria.eliminar_atencion(id), Ok(()));
+        assert!(abrir_archivo(&veterinaria.file_path).unwrap().is_empty());
     }
 
     #[test]
     fn test_veterinaria_archivo_errores() {
-        let mut veterinaria = creacion_veterinaria();
+        let (mut veterinaria, mascotas) = creacion_veterinaria(5);
 
         veterinaria.file_path = "/".to_string();
 
         assert!(abrir_archivo(&veterinaria.file_path).is_err());
 
-        let mascota = Mascota::new(
-            "Juancito".to_string(),
-            5,
-            Animales::Gato,
-            Duenio::new(
-                "Pedro".to_string(),
-                "155".to_string(),
-                "2217485463".to_string(),
-            ),
-        );
         let atencion = Atencion::new(
-            mascota.clone(),
+            mascotas[0],
             "Herida moderada".to_string(),
             "Gasa y alcohol".to_string(),
             Some(Fecha::new(5, 6, 2024)),
@@ -615,12 +1428,12 @@ mod test {
 
         // Fuerzo error de eliminar atencion y modificar atencion (no hay atenciones validas)
 
-        match veterinaria.eliminar_atencion(&atencion) {
+        match veterinaria.eliminar_atencion(1) {
             Ok(_) => (),
             Err(e) => println!("{}", e),
         };
 
-        match veterinaria.modificar_diagnostico(&atencion, "test".to_string()) {
+        match veterinaria.modificar_diagnostico(1, "test".to_string()) {
             Ok(_) => (),
             Err(e) => println!("{}", e),
         };
@@ -628,5 +1441,6 @@ mod test {
         // Fuerzo el error al crear el archivo con un path no valido
 
         assert!(veterinaria.registrar_atencion(atencion).is_err());
+        assert!(veterinaria.recuperar_registros().is_err());
     }
 }
@@ -0,0 +1,769 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::{
+    Agenda, Cita, DiaSemana, ErrorVeterinaria, EstadoCita, Hora, HorarioLaboral, Veterinaria,
+    Veterinario,
+};
+use crate::tp3::ej03::Fecha;
+
+impl DiaSemana {
+    fn from_fecha(fecha: &Fecha) -> DiaSemana {
+        let f =
+            NaiveDate::from_ymd_opt(fecha.get_anio(), fecha.get_mes(), fecha.get_dia()).unwrap();
+
+        match f.weekday() {
+            Weekday::Mon => DiaSemana::Lunes,
+            Weekday::Tue => DiaSemana::Martes,
+            Weekday::Wed => DiaSemana::Miercoles,
+            Weekday::Thu => DiaSemana::Jueves,
+            Weekday::Fri => DiaSemana::Viernes,
+            Weekday::Sat => DiaSemana::Sabado,
+            Weekday::Sun => DiaSemana::Domingo,
+        }
+    }
+}
+
+impl HorarioLaboral {
+    fn new(dia: DiaSemana, desde: Hora, hasta: Hora) -> HorarioLaboral {
+        if desde.to_minutos() >= hasta.to_minutos() {
+            panic!("Horario no valido");
+        }
+
+        HorarioLaboral { dia, desde, hasta }
+    }
+}
+
+impl Cita {
+    fn fin(&self) -> u32 {
+        self.inicio.to_minutos() + self.duracion
+    }
+
+    fn se_superpone(&self, fecha: &Fecha, inicio: u32, fin: u32) -> bool {
+        self.fecha.eq(fecha) && self.inicio.to_minutos() < fin && inicio < self.fin()
+    }
+}
+
+impl Veterinaria {
+    // Si la agenda no puede guardarse, se descartan los cambios
+    fn guardar_agenda(&mut self, previa: Agenda) -> Result<(), ErrorVeterinaria> {
+        if let Err(e) = Self::escribir_archivo(&self.path_agenda, &self.agenda) {
+            self.agenda = previa;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn recuperar_agenda(&mut self) -> Result<(), ErrorVeterinaria> {
+        if let Some(agenda) = Self::leer_archivo(&self.path_agenda)? {
+            self.agenda = agenda;
+        }
+
+        Ok(())
+    }
+
+    // Solo afecta a los turnos que se reserven a partir de ahora
+    fn set_duracion_turno(&mut self, minutos: u32) -> Result<(), ErrorVeterinaria> {
+        if minutos == 0 {
+            panic!("Duracion no valida");
+        }
+
+        let previa = self.agenda.clone();
+        self.agenda.duracion_turno = minutos;
+
+        self.guardar_agenda(previa)
+    }
+
+    fn agregar_veterinario(
+        &mut self,
+        nombre: String,
+        horarios: Vec<HorarioLaboral>,
+    ) -> Result<u32, ErrorVeterinaria> {
+        let previa = self.agenda.clone();
+        let id = self
+            .agenda
+            .veterinarios
+            .iter()
+            .map(|v| v.id)
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        self.agenda.veterinarios.push(Veterinario {
+            id,
+            nombre,
+            horarios,
+        });
+        self.guardar_agenda(previa)?;
+
+        Ok(id)
+    }
+
+    fn get_veterinario(&self, id: u32) -> Option<&Veterinario> {
+        self.agenda.veterinarios.iter().find(|v| v.id == id)
+    }
+
+    fn get_cita(&self, id: u32) -> Option<&Cita> {
+        self.agenda.citas.iter().find(|c| c.id == id)
+    }
+
+    fn esta_en_horario(veterinario: &Veterinario, fecha: &Fecha, inicio: u32, fin: u32) -> bool {
+        let dia = DiaSemana::from_fecha(fecha);
+
+        veterinario
+            .horarios
+            .iter()
+            .any(|h| h.dia == dia && h.desde.to_minutos() <= inicio && fin <= h.hasta.to_minutos())
+    }
+
+    // Ni el veterinario ni la mascota pueden tener dos citas superpuestas. La cita ignorada
+    //es la que se esta reprogramando
+    fn esta_libre(
+        &self,
+        id_veterinario: u32,
+        id_mascota: u32,
+        fecha: &Fecha,
+        inicio: u32,
+        fin: u32,
+        ignorada: Option<u32>,
+    ) -> bool {
+        !self.agenda.citas.iter().any(|c| {
+            Some(c.id) != ignorada
+                && (c.id_veterinario == id_veterinario || c.id_mascota == id_mascota)
+                && c.se_superpone(fecha, inicio, fin)
+        })
+    }
+
+    fn validar_turno(
+        &self,
+        id_veterinario: u32,
+        id_mascota: u32,
+        fecha: &Fecha,
+        inicio: &Hora,
+        duracion: u32,
+        ignorada: Option<u32>,
+    ) -> Result<(), ErrorVeterinaria> {
+        if !fecha.es_fecha_valida() {
+            panic!("Fecha no valida");
+        }
+
+        let Some(veterinario) = self.get_veterinario(id_veterinario) else {
+            return Err(ErrorVeterinaria::Veterinario);
+        };
+
+        let inicio = inicio.to_minutos();
+        let fin = inicio + duracion;
+
+        if !Self::esta_en_horario(veterinario, fecha, inicio, fin) {
+            return Err(ErrorVeterinaria::Horario);
+        }
+
+        if !self.esta_libre(id_veterinario, id_mascota, fecha, inicio, fin, ignorada) {
+            return Err(ErrorVeterinaria::TurnoOcupado);
+        }
+
+        Ok(())
+    }
+
+    // Turnos libres del veterinario en el dia, segun la duracion de turno configurada
+    fn turnos_disponibles(&self, id_veterinario: u32, fecha: &Fecha) -> Vec<Hora> {
+        let Some(veterinario) = self.get_veterinario(id_veterinario) else {
+            return Vec::new();
+        };
+
+        let dia = DiaSemana::from_fecha(fecha);
+        let duracion = self.agenda.duracion_turno;
+        let mut turnos = Vec::new();
+
+        for h in veterinario.horarios.iter().filter(|h| h.dia == dia) {
+            let mut inicio = h.desde.to_minutos();
+
+            while inicio + duracion <= h.hasta.to_minutos() {
+                let ocupado = self.agenda.citas.iter().any(|c| {
+                    c.id_veterinario == id_veterinario
+                        && c.se_superpone(fecha, inicio, inicio + duracion)
+                });
+
+                if !ocupado {
+                    turnos.push(Hora::from_minutos(inicio));
+                }
+                inicio += duracion;
+            }
+        }
+
+        turnos.sort_by_key(|h| h.to_minutos());
+        turnos
+    }
+
+    fn reservar_cita(
+        &mut self,
+        id_veterinario: u32,
+        id_mascota: u32,
+        fecha: Fecha,
+        inicio: Hora,
+    ) -> Result<u32, ErrorVeterinaria> {
+        if self.get_mascota(id_mascota).is_none() {
+            return Err(ErrorVeterinaria::Mascota);
+        }
+
+        let duracion = self.agenda.duracion_turno;
+        self.validar_turno(id_veterinario, id_mascota, &fecha, &inicio, duracion, None)?;
+
+        self.agregar_cita(
+            id_veterinario,
+            id_mascota,
+            fecha,
+            inicio,
+            EstadoCita::Confirmada,
+            None,
+        )
+    }
+
+    fn agregar_cita(
+        &mut self,
+        id_veterinario: u32,
+        id_mascota: u32,
+        fecha: Fecha,
+        inicio: Hora,
+        estado: EstadoCita,
+        id_atencion: Option<u32>,
+    ) -> Result<u32, ErrorVeterinaria> {
+        let previa = self.agenda.clone();
+        let id = self.agenda.citas.iter().map(|c| c.id).max().unwrap_or(0) + 1;
+
+        self.agenda.citas.push(Cita {
+            id,
+            id_veterinario,
+            id_mascota,
+            fecha,
+            inicio,
+            duracion: self.agenda.duracion_turno,
+            estado,
+            id_atencion,
+        });
+        self.guardar_agenda(previa)?;
+
+        Ok(id)
+    }
+
+    fn confirmar_cita(&mut self, id: u32) -> Result<(), ErrorVeterinaria> {
+        let previa = self.agenda.clone();
+
+        let Some(cita) = self.agenda.citas.iter_mut().find(|c| c.id == id) else {
+            return Err(ErrorVeterinaria::Cita);
+        };
+        cita.estado = EstadoCita::Confirmada;
+
+        self.guardar_agenda(previa)
+    }
+
+    fn cancelar_cita(&mut self, id: u32) -> Result<(), ErrorVeterinaria> {
+        let previa = self.agenda.clone();
+
+        let Some(index) = self.agenda.citas.iter().position(|c| c.id == id) else {
+            return Err(ErrorVeterinaria::Cita);
+        };
+        self.agenda.citas.remove(index);
+
+        self.guardar_agenda(previa)
+    }
+
+    // La cita mantiene su veterinario, duracion y estado
+    fn reprogramar_cita(
+        &mut self,
+        id: u32,
+        fecha: Fecha,
+        inicio: Hora,
+    ) -> Result<(), ErrorVeterinaria> {
+        let Some(cita) = self.get_cita(id) else {
+            return Err(ErrorVeterinaria::Cita);
+        };
+
+        self.validar_turno(
+            cita.id_veterinario,
+            cita.id_mascota,
+            &fecha,
+            &inicio,
+            cita.duracion,
+            Some(id),
+        )?;
+
+        let previa = self.agenda.clone();
+        let cita = self.agenda.citas.iter_mut().find(|c| c.id == id).unwrap();
+        cita.fecha = fecha;
+        cita.inicio = inicio;
+
+        self.guardar_agenda(previa)
+    }
+
+    fn agenda_diaria(&self, id_veterinario: u32, fecha: &Fecha) -> Vec<&Cita> {
+        let mut citas: Vec<&Cita> = self
+            .agenda
+            .citas
+            .iter()
+            .filter(|c| c.id_veterinario == id_veterinario && c.fecha.eq(fecha))
+            .collect();
+
+        citas.sort_by_key(|c| c.inicio.to_minutos());
+        citas
+    }
+
+    // Reserva el primer turno libre del dia con cualquier veterinario. Si la mascota ya tiene
+    //una cita ese dia o no hay turnos libres, no se reserva nada
+    pub(super) fn reservar_tentativa(
+        &mut self,
+        id_atencion: u32,
+        id_mascota: u32,
+        fecha: &Fecha,
+    ) -> Result<(), ErrorVeterinaria> {
+        if self
+            .agenda
+            .citas
+            .iter()
+            .any(|c| c.id_mascota == id_mascota && c.fecha.eq(fecha))
+        {
+            return Ok(());
+        }
+
+        let duracion = self.agenda.duracion_turno;
+        let turno = self
+            .agenda
+            .veterinarios
+            .iter()
+            .flat_map(|v| {
+                self.turnos_disponibles(v.id, fecha)
+                    .into_iter()
+                    .map(move |h| (v.id, h))
+            })
+            .filter(|(_, h)| {
+                self.esta_libre(
+                    0,
+                    id_mascota,
+                    fecha,
+                    h.to_minutos(),
+                    h.to_minutos() + duracion,
+                    None,
+                )
+            })
+            .min_by_key(|(id, h)| (h.to_minutos(), *id));
+
+        match turno {
+            Some((id_veterinario, inicio)) => self
+                .agregar_cita(
+                    id_veterinario,
+                    id_mascota,
+                    fecha.clone(),
+                    inicio,
+                    EstadoCita::Tentativa,
+                    Some(id_atencion),
+                )
+                .map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    // Al cambiar la proxima visita de una atencion, se libera el turno tentativo que genero.
+    //Las citas guardadas antes de vincularse a una atencion se buscan por mascota y fecha
+    pub(super) fn reprogramar_tentativa(
+        &mut self,
+        id_atencion: u32,
+        id_mascota: u32,
+        anterior: Option<&Fecha>,
+        nueva: Option<&Fecha>,
+    ) -> Result<(), ErrorVeterinaria> {
+        let previa = self.agenda.clone();
+        let cantidad = self.agenda.citas.len();
+
+        self.agenda.citas.retain(|c| {
+            let generada = match c.id_atencion {
+                Some(id) => id == id_atencion,
+                None => c.id_mascota == id_mascota && anterior.is_some_and(|f| c.fecha.eq(f)),
+            };
+            !(c.estado == EstadoCita::Tentativa && generada)
+        });
+
+        if self.agenda.citas.len() != cantidad {
+            self.guardar_agenda(previa)?;
+        }
+
+        match nueva {
+            Some(fecha) => self.reservar_tentativa(id_atencion, id_mascota, fecha),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Atencion, Duenio, Mascota};
+    use super::*;
+    use std::{fs::File, io::prelude::*};
+
+    fn nueva_veterinaria(n: u32) -> Veterinaria {
+        Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            Default::default(),
+            Default::default(),
+            format!("test_files/agenda{}.json", n),
+            format!("test_files/pacientes_agenda{}.json", n),
+        )
+    }
+
+    fn crear_veterinaria(n: u32) -> (Veterinaria, Vec<u32>, Vec<u32>) {
+        let mut veterinaria = nueva_veterinaria(n);
+
+        let lopez = veterinaria
+            .agregar_veterinario(
+                "Dra. Lopez".to_string(),
+                vec![
+                    HorarioLaboral::new(DiaSemana::Lunes, Hora::new(9, 0), Hora::new(12, 0)),
+                    HorarioLaboral::new(DiaSemana::Miercoles, Hora::new(14, 0), Hora::new(16, 0)),
+                ],
+            )
+            .unwrap();
+        let gomez = veterinaria
+            .agregar_veterinario(
+                "Dr. Gomez".to_string(),
+                vec![HorarioLaboral::new(
+                    DiaSemana::Lunes,
+                    Hora::new(9, 0),
+                    Hora::new(11, 0),
+                )],
+            )
+            .unwrap();
+
+        let pedro = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+        let ana = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Ana".to_string(),
+                "7 y 50".to_string(),
+                "2214567890".to_string(),
+            ))
+            .unwrap();
+
+        let mascotas = vec![
+            veterinaria
+                .registrar_mascota(Mascota::new(
+                    "Toby".to_string(),
+                    Fecha::new(1, 1, 2021),
+                    "Perro".to_string(),
+                    pedro,
+                ))
+                .unwrap(),
+            veterinaria
+                .registrar_mascota(Mascota::new(
+                    "Michi".to_string(),
+                    Fecha::new(1, 1, 2019),
+                    "Gato".to_string(),
+                    ana,
+                ))
+                .unwrap(),
+        ];
+
+        (veterinaria, vec![lopez, gomez], mascotas)
+    }
+
+    #[test]
+    fn test_reservar_citas() {
+        let (mut veterinaria, vets, mascotas) = crear_veterinaria(1);
+        let lunes = Fecha::new(6, 5, 2024);
+
+        assert_eq!(vets, vec![1, 2]);
+        assert_eq!(veterinaria.turnos_disponibles(vets[0], &lunes).len(), 6);
+        assert!(veterinaria
+            .turnos_disponibles(vets[0], &Fecha::new(7, 5, 2024))
+            .is_empty());
+
+        let c1 = veterinaria
+            .reservar_cita(vets[0], mascotas[0], lunes.clone(), Hora::new(9, 30))
+            .unwrap();
+
+        // Turnos superpuestos, fuera de horario o de un veterinario inexistente
+
+        let e = veterinaria
+            .reservar_cita(vets[0], mascotas[1], lunes.clone(), Hora::new(9, 45))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::TurnoOcupado);
+        println!("{}", e);
+
+        let e = veterinaria
+            .reservar_cita(vets[0], mascotas[1], lunes.clone(), Hora::new(11, 45))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Horario);
+        println!("{}", e);
+
+        assert_eq!(
+            veterinaria.reservar_cita(
+                vets[0],
+                mascotas[1],
+                Fecha::new(7, 5, 2024),
+                Hora::new(9, 30)
+            ),
+            Err(ErrorVeterinaria::Horario)
+        );
+
+        let e = veterinaria
+            .reservar_cita(9, mascotas[1], lunes.clone(), Hora::new(9, 30))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Veterinario);
+        println!("{}", e);
+
+        let e = veterinaria
+            .reservar_cita(vets[0], 9, lunes.clone(), Hora::new(10, 30))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Mascota);
+        println!("{}", e);
+
+        // La misma mascota no puede estar con dos veterinarios a la vez
+
+        assert_eq!(
+            veterinaria.reservar_cita(vets[1], mascotas[0], lunes.clone(), Hora::new(9, 30)),
+            Err(ErrorVeterinaria::TurnoOcupado)
+        );
+        let c2 = veterinaria
+            .reservar_cita(vets[1], mascotas[1], lunes.clone(), Hora::new(9, 30))
+            .unwrap();
+
+        // Con turnos de 20 minutos cambian los turnos libres, pero no las citas ya reservadas
+
+        assert!(veterinaria.set_duracion_turno(20).is_ok());
+        let turnos: Vec<u32> = veterinaria
+            .turnos_disponibles(vets[0], &lunes)
+            .iter()
+            .map(|h| h.to_minutos())
+            .collect();
+        assert_eq!(turnos.len(), 7);
+        assert_eq!(turnos[..3], [9 * 60, 10 * 60, 10 * 60 + 20]);
+        assert_eq!(veterinaria.get_cita(c1).unwrap().duracion, 30);
+
+        // Reprogramar y cancelar
+
+        let c3 = veterinaria
+            .reservar_cita(vets[0], mascotas[1], lunes.clone(), Hora::new(10, 0))
+            .unwrap();
+        assert_eq!(
+            veterinaria.reprogramar_cita(c1, lunes.clone(), Hora::new(10, 10)),
+            Err(ErrorVeterinaria::TurnoOcupado)
+        );
+        assert!(veterinaria
+            .reprogramar_cita(c1, lunes.clone(), Hora::new(9, 15))
+            .is_ok());
+        assert!(veterinaria
+            .reprogramar_cita(c1, lunes.clone(), Hora::new(9, 0))
+            .is_ok());
+        assert!(veterinaria
+            .reprogramar_cita(c1, lunes.clone(), Hora::new(11, 30))
+            .is_ok());
+
+        let agenda: Vec<u32> = veterinaria
+            .agenda_diaria(vets[0], &lunes)
+            .iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(agenda, vec![c3, c1]);
+
+        assert!(veterinaria.cancelar_cita(c2).is_ok());
+        let e = veterinaria.cancelar_cita(c2).unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Cita);
+        println!("{}", e);
+        assert_eq!(
+            veterinaria.reprogramar_cita(c2, lunes.clone(), Hora::new(9, 0)),
+            Err(ErrorVeterinaria::Cita)
+        );
+        assert!(veterinaria.agenda_diaria(vets[1], &lunes).is_empty());
+    }
+
+    #[test]
+    fn test_agenda_diaria() {
+        let (mut veterinaria, vets, mascotas) = crear_veterinaria(2);
+        let lunes = Fecha::new(6, 5, 2024);
+
+        for (i, hora) in [Hora::new(11, 0), Hora::new(9, 0)].into_iter().enumerate() {
+            veterinaria
+                .reservar_cita(vets[0], mascotas[i], lunes.clone(), hora)
+                .unwrap();
+        }
+
+        let agenda = veterinaria.agenda_diaria(vets[0], &lunes);
+        assert_eq!(agenda.len(), 2);
+        assert_eq!(agenda[0].id_mascota, mascotas[1]);
+        assert_eq!(agenda[1].inicio, Hora::new(11, 0));
+        assert!(veterinaria
+            .agenda_diaria(vets[0], &Fecha::new(13, 5, 2024))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_citas_tentativas() {
+        let (mut veterinaria, vets, mascotas) = crear_veterinaria(3);
+        veterinaria.file_path = "test_files/veterinaria_agenda.json".to_string();
+
+        // La proxima visita reserva el primer turno libre del dia
+
+        let atencion = Atencion::new(
+            mascotas[0],
+            "Otitis".to_string(),
+            "Gotas".to_string(),
+            Some(Fecha::new(8, 5, 2024)),
+        );
+        let id_atencion = veterinaria.registrar_atencion(atencion).unwrap();
+
+        let citas = veterinaria.agenda_diaria(vets[0], &Fecha::new(8, 5, 2024));
+        assert_eq!(citas.len(), 1);
+        assert_eq!(citas[0].estado, EstadoCita::Tentativa);
+        assert_eq!(citas[0].inicio, Hora::new(14, 0));
+
+        // Al cambiar la proxima visita se mueve el turno tentativo
+
+        veterinaria
+            .reservar_cita(
+                vets[0],
+                mascotas[1],
+                Fecha::new(6, 5, 2024),
+                Hora::new(9, 0),
+            )
+            .unwrap();
+        veterinaria
+            .modificar_fecha_visita(id_atencion, Some(Fecha::new(6, 5, 2024)))
+            .unwrap();
+
+        assert!(veterinaria
+            .agenda_diaria(vets[0], &Fecha::new(8, 5, 2024))
+            .is_empty());
+        let citas = veterinaria.agenda_diaria(vets[1], &Fecha::new(6, 5, 2024));
+        assert_eq!(citas.len(), 1);
+        assert_eq!(citas[0].inicio, Hora::new(9, 0));
+
+        let id = citas[0].id;
+        assert!(veterinaria.confirmar_cita(id).is_ok());
+        assert_eq!(veterinaria.confirmar_cita(99), Err(ErrorVeterinaria::Cita));
+
+        // Una cita confirmada no se libera al quitar la proxima visita
+
+        veterinaria
+            .modificar_fecha_visita(id_atencion, None)
+            .unwrap();
+        assert_eq!(
+            veterinaria.get_cita(id).unwrap().estado,
+            EstadoCita::Confirmada
+        );
+
+        // Sin turnos libres no se reserva nada
+
+        let domingo = Atencion::new(
+            mascotas[1],
+            "Control".to_string(),
+            "Ninguno".to_string(),
+            Some(Fecha::new(12, 5, 2024)),
+        );
+        veterinaria.registrar_atencion(domingo).unwrap();
+        assert_eq!(veterinaria.agenda.citas.len(), 2);
+
+        // Al eliminar una atencion se libera solo su turno tentativo
+
+        let lunes = Fecha::new(13, 5, 2024);
+        let primera = Atencion::new(
+            mascotas[0],
+            "Vacuna".to_string(),
+            "Ninguno".to_string(),
+            Some(lunes.clone()),
+        );
+        let id_primera = veterinaria.registrar_atencion(primera).unwrap();
+        let segunda = Atencion::new(
+            mascotas[0],
+            "Control".to_string(),
+            "Ninguno".to_string(),
+            Some(lunes.clone()),
+        );
+        let id_segunda = veterinaria.registrar_atencion(segunda).unwrap();
+        assert_eq!(veterinaria.agenda_diaria(vets[0], &lunes).len(), 1);
+
+        veterinaria.eliminar_atencion(id_segunda).unwrap();
+        assert_eq!(veterinaria.agenda_diaria(vets[0], &lunes).len(), 1);
+
+        veterinaria.eliminar_atencion(id_primera).unwrap();
+        assert!(veterinaria.agenda_diaria(vets[0], &lunes).is_empty());
+        assert_eq!(veterinaria.agenda.citas.len(), 2);
+
+        // Si no puede reservarse el turno o guardarse el registro, no se registra la atencion
+
+        let atenciones = veterinaria.registro_atencion.clone();
+        let citas = veterinaria.agenda.citas.clone();
+        let control = Atencion::new(
+            mascotas[0],
+            "Control".to_string(),
+            "Ninguno".to_string(),
+            Some(lunes.clone()),
+        );
+
+        let path_agenda = std::mem::replace(&mut veterinaria.path_agenda, "/".to_string());
+        assert_eq!(
+            veterinaria.registrar_atencion(control.clone()),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        veterinaria.path_agenda = path_agenda;
+
+        veterinaria.file_path = "/".to_string();
+        assert_eq!(
+            veterinaria.registrar_atencion(control),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(veterinaria.registro_atencion, atenciones);
+        assert_eq!(veterinaria.agenda.citas, citas);
+    }
+
+    #[test]
+    fn test_agenda_archivo() {
+        let (mut veterinaria, vets, mascotas) = crear_veterinaria(4);
+        let lunes = Fecha::new(6, 5, 2024);
+
+        veterinaria
+            .reservar_cita(vets[0], mascotas[0], lunes.clone(), Hora::new(9, 0))
+            .unwrap();
+
+        let mut reiniciada = nueva_veterinaria(4);
+        assert!(reiniciada.recuperar_pacientes().is_ok());
+        assert!(reiniciada.recuperar_agenda().is_ok());
+        assert_eq!(reiniciada.agenda, veterinaria.agenda);
+
+        // Si la agenda no puede guardarse, no se modifica
+
+        reiniciada.path_agenda = "/".to_string();
+        assert_eq!(
+            reiniciada.reservar_cita(vets[0], mascotas[1], lunes.clone(), Hora::new(10, 0)),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(reiniciada.cancelar_cita(1), Err(ErrorVeterinaria::Archivo));
+        assert_eq!(
+            reiniciada.agregar_veterinario("Dr. Perez".to_string(), Vec::new()),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(reiniciada.agenda, veterinaria.agenda);
+
+        // Un archivo con formato no valido
+
+        File::create("test_files/agenda_invalida.json")
+            .unwrap()
+            .write_all(b"{\"citas\": 1}")
+            .unwrap();
+        reiniciada.path_agenda = "test_files/agenda_invalida.json".to_string();
+        assert_eq!(
+            reiniciada.recuperar_agenda(),
+            Err(ErrorVeterinaria::Archivo)
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_horario_no_valido() {
+        HorarioLaboral::new(DiaSemana::Lunes, Hora::new(12, 0), Hora::new(9, 0));
+    }
+}
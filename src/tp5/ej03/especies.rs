@@ -0,0 +1,505 @@
+use super::{
+    Atencion, ErrorVeterinaria, Especie, Mascota, RangoDosis, Vacuna, VacunaCalendario, Veterinaria,
+};
+use crate::tp3::ej03::Fecha;
+
+impl Especie {
+    fn new(nombre: String) -> Especie {
+        Especie {
+            nombre,
+            razas: Vec::new(),
+            calendario: Vec::new(),
+            rangos_dosis: Vec::new(),
+        }
+    }
+
+    // Especies disponibles antes del catalogo, sin razas, calendario ni rangos de dosis
+    pub(super) fn catalogo_base() -> Vec<Especie> {
+        ["Perro", "Gato", "Caballo", "Otros"]
+            .into_iter()
+            .map(|nombre| Especie::new(nombre.to_string()))
+            .collect()
+    }
+
+    fn tiene_raza(&self, raza: &str) -> bool {
+        self.razas.iter().any(|r| r.eq_ignore_ascii_case(raza))
+    }
+
+    // Solo se validan los medicamentos con rangos de dosis cargados para la especie
+    fn validar_dosis(
+        &self,
+        medicamento: &str,
+        dosis_mg: f64,
+        peso: Option<f64>,
+    ) -> Result<(), ErrorVeterinaria> {
+        let rangos: Vec<&RangoDosis> = self
+            .rangos_dosis
+            .iter()
+            .filter(|r| r.medicamento.eq_ignore_ascii_case(medicamento))
+            .collect();
+
+        if rangos.is_empty() {
+            return Ok(());
+        }
+
+        let Some(peso) = peso else {
+            return Err(ErrorVeterinaria::Peso);
+        };
+
+        match rangos.iter().find(|r| r.contiene_peso(peso)) {
+            Some(r) if r.dosis_min_mg <= dosis_mg && dosis_mg <= r.dosis_max_mg => Ok(()),
+            _ => Err(ErrorVeterinaria::Dosis),
+        }
+    }
+}
+
+impl VacunaCalendario {
+    fn new(vacuna: String, edad_semanas: u32, refuerzo_dias: Option<u32>) -> VacunaCalendario {
+        if refuerzo_dias == Some(0) {
+            panic!("Refuerzo no valido");
+        }
+
+        VacunaCalendario {
+            vacuna,
+            edad_semanas,
+            refuerzo_dias,
+        }
+    }
+}
+
+impl RangoDosis {
+    fn new(
+        medicamento: String,
+        peso_desde: f64,
+        peso_hasta: f64,
+        dosis_min_mg: f64,
+        dosis_max_mg: f64,
+    ) -> RangoDosis {
+        if peso_desde < 0.0 || peso_desde >= peso_hasta || dosis_min_mg > dosis_max_mg {
+            panic!("Rango de dosis no valido");
+        }
+
+        RangoDosis {
+            medicamento,
+            peso_desde,
+            peso_hasta,
+            dosis_min_mg,
+            dosis_max_mg,
+        }
+    }
+
+    fn contiene_peso(&self, peso: f64) -> bool {
+        self.peso_desde <= peso && peso < self.peso_hasta
+    }
+}
+
+impl Veterinaria {
+    fn get_especie(&self, nombre: &str) -> Option<&Especie> {
+        self.pacientes
+            .especies
+            .iter()
+            .find(|e| e.nombre.eq_ignore_ascii_case(nombre))
+    }
+
+    fn agregar_especie(&mut self, especie: Especie) -> Result<(), ErrorVeterinaria> {
+        if self.get_especie(&especie.nombre).is_some() {
+            return Err(ErrorVeterinaria::EspecieDuplicada);
+        }
+
+        let previos = self.pacientes.clone();
+        self.pacientes.especies.push(especie);
+
+        self.guardar_pacientes(previos)
+    }
+
+    fn modificar_especie(
+        &mut self,
+        nombre: &str,
+        modificacion: impl FnOnce(&mut Especie),
+    ) -> Result<(), ErrorVeterinaria> {
+        let previos = self.pacientes.clone();
+
+        let Some(especie) = self
+            .pacientes
+            .especies
+            .iter_mut()
+            .find(|e| e.nombre.eq_ignore_ascii_case(nombre))
+        else {
+            return Err(ErrorVeterinaria::Especie);
+        };
+        modificacion(especie);
+
+        self.guardar_pacientes(previos)
+    }
+
+    fn agregar_raza(&mut self, especie: &str, raza: String) -> Result<(), ErrorVeterinaria> {
+        self.modificar_especie(especie, |e| {
+            if !e.tiene_raza(&raza) {
+                e.razas.push(raza);
+            }
+        })
+    }
+
+    fn agregar_vacuna_calendario(
+        &mut self,
+        especie: &str,
+        vacuna: VacunaCalendario,
+    ) -> Result<(), ErrorVeterinaria> {
+        self.modificar_especie(especie, |e| e.calendario.push(vacuna))
+    }
+
+    fn agregar_rango_dosis(
+        &mut self,
+        especie: &str,
+        rango: RangoDosis,
+    ) -> Result<(), ErrorVeterinaria> {
+        self.modificar_especie(especie, |e| e.rangos_dosis.push(rango))
+    }
+
+    // La especie debe estar en el catalogo y, si se indica, la raza debe pertenecer a ella
+    pub(super) fn validar_mascota(&self, mascota: &Mascota) -> Result<(), ErrorVeterinaria> {
+        let Some(especie) = self.get_especie(&mascota.especie) else {
+            return Err(ErrorVeterinaria::Especie);
+        };
+
+        match &mascota.raza {
+            Some(raza) if !especie.tiene_raza(raza) => Err(ErrorVeterinaria::Raza),
+            _ => Ok(()),
+        }
+    }
+
+    // Las dosis se validan con el peso registrado en la misma atencion
+    pub(super) fn validar_recetas(
+        &self,
+        mascota: &Mascota,
+        atencion: &Atencion,
+    ) -> Result<(), ErrorVeterinaria> {
+        let Some(especie) = self.get_especie(&mascota.especie) else {
+            return Err(ErrorVeterinaria::Especie);
+        };
+
+        for receta in &atencion.recetas {
+            especie.validar_dosis(&receta.medicamento, receta.dosis_mg, atencion.peso)?;
+        }
+
+        Ok(())
+    }
+
+    // Vacunas del calendario de la especie que corresponde aplicar a la fecha dada: las que
+    //nunca se aplicaron y la mascota ya tiene edad para recibir, y los refuerzos vencidos. Si
+    //se desconoce la fecha de nacimiento, toda vacuna no aplicada se considera pendiente
+    fn vacunas_pendientes(&self, id_mascota: u32, fecha_actual: &Fecha) -> Vec<&VacunaCalendario> {
+        let Some(mascota) = self.get_mascota(id_mascota) else {
+            return Vec::new();
+        };
+        let Some(especie) = self.get_especie(&mascota.especie) else {
+            return Vec::new();
+        };
+
+        let aplicadas: Vec<&Vacuna> = self
+            .atenciones_mascota(id_mascota)
+            .into_iter()
+            .flat_map(|at| &at.vacunas)
+            .collect();
+        let semanas = mascota.edad_semanas(fecha_actual);
+
+        especie
+            .calendario
+            .iter()
+            .filter(|vc| {
+                let ultima = aplicadas
+                    .iter()
+                    .filter(|v| v.nombre.eq_ignore_ascii_case(&vc.vacuna))
+                    .map(|v| &v.fecha_aplicacion)
+                    .reduce(|a, b| if b.es_mayor(a) { b } else { a });
+
+                match (ultima, vc.refuerzo_dias) {
+                    (None, _) => semanas.is_none_or(|s| s >= vc.edad_semanas),
+                    (Some(f), Some(dias)) => f.dias_hasta(fecha_actual) >= dias as i64,
+                    (Some(_), None) => false,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Duenio, Receta};
+    use super::*;
+
+    fn crear_veterinaria(n: u32) -> (Veterinaria, u32) {
+        let mut veterinaria = Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            format!("test_files/atenciones_especies{}.json", n),
+            Default::default(),
+            Default::default(),
+            format!("test_files/pacientes_especies{}.json", n),
+        );
+
+        veterinaria
+            .agregar_raza("Perro", "Caniche".to_string())
+            .unwrap();
+        veterinaria
+            .agregar_raza("perro", "Labrador".to_string())
+            .unwrap();
+        veterinaria
+            .agregar_vacuna_calendario(
+                "Perro",
+                VacunaCalendario::new("Sextuple".to_string(), 8, Some(365)),
+            )
+            .unwrap();
+        veterinaria
+            .agregar_vacuna_calendario(
+                "Perro",
+                VacunaCalendario::new("Antirrabica".to_string(), 16, None),
+            )
+            .unwrap();
+
+        // Franjas de peso para la amoxicilina en perros
+
+        veterinaria
+            .agregar_rango_dosis(
+                "Perro",
+                RangoDosis::new("Amoxicilina".to_string(), 0.0, 10.0, 50.0, 200.0),
+            )
+            .unwrap();
+        veterinaria
+            .agregar_rango_dosis(
+                "Perro",
+                RangoDosis::new("Amoxicilina".to_string(), 10.0, 40.0, 200.0, 500.0),
+            )
+            .unwrap();
+
+        let id_duenio = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+
+        (veterinaria, id_duenio)
+    }
+
+    #[test]
+    fn test_edad() {
+        let mascota = Mascota::new(
+            "Toby".to_string(),
+            Fecha::new(10, 3, 2021),
+            "Perro".to_string(),
+            1,
+        );
+
+        assert_eq!(mascota.edad(&Fecha::new(9, 3, 2024)), Some(2));
+        assert_eq!(mascota.edad(&Fecha::new(10, 3, 2024)), Some(3));
+        assert_eq!(mascota.edad(&Fecha::new(1, 1, 2020)), Some(0));
+        assert_eq!(mascota.edad_semanas(&Fecha::new(24, 3, 2021)), Some(2));
+
+        let mut previa = mascota.clone();
+        previa.fecha_nacimiento = None;
+        assert!(previa.edad(&Fecha::new(10, 3, 2024)).is_none());
+    }
+
+    #[test]
+    fn test_catalogo_especies() {
+        let (mut veterinaria, id_duenio) = crear_veterinaria(1);
+
+        // Nuevas especies sin modificar el codigo
+
+        let mut conejo = Especie::new("Conejo".to_string());
+        conejo.razas.push("Belier".to_string());
+        assert!(veterinaria.agregar_especie(conejo).is_ok());
+
+        let e = veterinaria
+            .agregar_especie(Especie::new("conejo".to_string()))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::EspecieDuplicada);
+        println!("{}", e);
+
+        assert_eq!(
+            veterinaria.agregar_raza("Hurón", "Angora".to_string()),
+            Err(ErrorVeterinaria::Especie)
+        );
+        assert_eq!(veterinaria.get_especie("PERRO").unwrap().razas.len(), 2);
+
+        // Al registrar una mascota se valida su especie y raza
+
+        let mut tambor = Mascota::new(
+            "Tambor".to_string(),
+            Fecha::new(1, 2, 2023),
+            "Conejo".to_string(),
+            id_duenio,
+        );
+        tambor.set_raza("belier".to_string());
+        assert!(veterinaria.registrar_mascota(tambor.clone()).is_ok());
+
+        tambor.set_raza("Caniche".to_string());
+        let e = veterinaria.registrar_mascota(tambor).unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Raza);
+        println!("{}", e);
+
+        let e = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Nemo".to_string(),
+                Fecha::new(1, 2, 2023),
+                "Pez".to_string(),
+                id_duenio,
+            ))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Especie);
+        println!("{}", e);
+
+        // El catalogo se guarda junto con el registro de pacientes
+
+        let mut reiniciada = Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            veterinaria.path_pacientes.clone(),
+        );
+        assert!(reiniciada.recuperar_pacientes().is_ok());
+        assert_eq!(
+            reiniciada.pacientes.especies,
+            veterinaria.pacientes.especies
+        );
+    }
+
+    #[test]
+    fn test_validar_dosis() {
+        let (mut veterinaria, id_duenio) = crear_veterinaria(2);
+        let toby = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(10, 3, 2021),
+                "Perro".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+        let michi = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Michi".to_string(),
+                Fecha::new(10, 3, 2021),
+                "Gato".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+
+        let atencion = |id_mascota: u32, peso: Option<f64>, dosis_mg: f64| -> Atencion {
+            let mut at = Atencion::new(
+                id_mascota,
+                "Otitis".to_string(),
+                "Antibiotico".to_string(),
+                None,
+            );
+            if let Some(p) = peso {
+                at.set_peso(p);
+            }
+            at.agregar_receta(Receta::new("amoxicilina".to_string(), dosis_mg, 12, 7));
+            at
+        };
+
+        // La dosis admitida depende de la franja de peso
+
+        assert!(veterinaria
+            .registrar_atencion(atencion(toby, Some(8.0), 150.0))
+            .is_ok());
+        assert!(veterinaria
+            .registrar_atencion(atencion(toby, Some(10.0), 250.0))
+            .is_ok());
+
+        let e = veterinaria
+            .registrar_atencion(atencion(toby, Some(8.0), 250.0))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Dosis);
+        println!("{}", e);
+        assert_eq!(
+            veterinaria.registrar_atencion(atencion(toby, Some(45.0), 300.0)),
+            Err(ErrorVeterinaria::Dosis)
+        );
+
+        let e = veterinaria
+            .registrar_atencion(atencion(toby, None, 150.0))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Peso);
+        println!("{}", e);
+
+        // Sin rangos cargados para la especie, la receta no se valida
+
+        assert!(veterinaria
+            .registrar_atencion(atencion(michi, None, 1000.0))
+            .is_ok());
+        assert_eq!(veterinaria.registro_atencion.len(), 3);
+    }
+
+    #[test]
+    fn test_vacunas_pendientes() {
+        let (mut veterinaria, id_duenio) = crear_veterinaria(3);
+        let toby = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2024),
+                "Perro".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+
+        let pendientes = |veterinaria: &Veterinaria, fecha: Fecha| -> Vec<String> {
+            veterinaria
+                .vacunas_pendientes(toby, &fecha)
+                .iter()
+                .map(|v| v.vacuna.clone())
+                .collect()
+        };
+
+        // Segun la edad: la sextuple a las 8 semanas y la antirrabica a las 16
+
+        assert!(pendientes(&veterinaria, Fecha::new(15, 1, 2024)).is_empty());
+        assert_eq!(
+            pendientes(&veterinaria, Fecha::new(1, 3, 2024)),
+            vec!["Sextuple"]
+        );
+        assert_eq!(
+            pendientes(&veterinaria, Fecha::new(1, 5, 2024)),
+            vec!["Sextuple", "Antirrabica"]
+        );
+
+        let mut at = Atencion::new(toby, "Vacunacion".to_string(), "Ninguno".to_string(), None);
+        at.agregar_vacuna(Vacuna::new(
+            "Sextuple".to_string(),
+            "SX-1".to_string(),
+            Fecha::new(1, 3, 2024),
+            None,
+        ));
+        at.agregar_vacuna(Vacuna::new(
+            "Antirrabica".to_string(),
+            "AR-1".to_string(),
+            Fecha::new(1, 5, 2024),
+            None,
+        ));
+        veterinaria.registrar_atencion(at).unwrap();
+
+        // Luego solo queda pendiente el refuerzo anual de la sextuple
+
+        assert!(pendientes(&veterinaria, Fecha::new(1, 6, 2024)).is_empty());
+        assert_eq!(
+            pendientes(&veterinaria, Fecha::new(1, 3, 2025)),
+            vec!["Sextuple"]
+        );
+
+        assert!(veterinaria
+            .vacunas_pendientes(99, &Fecha::new(1, 3, 2025))
+            .is_empty());
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_rango_dosis_no_valido() {
+        RangoDosis::new("Amoxicilina".to_string(), 10.0, 5.0, 50.0, 200.0);
+    }
+}
@@ -0,0 +1,545 @@
+use super::{ErrorVeterinaria, Factura, Item, LineaFactura, MedioPago, TipoItem, Veterinaria};
+use crate::tp3::ej03::Fecha;
+
+const MEDIOS_PAGO: [MedioPago; 4] = [
+    MedioPago::Efectivo,
+    MedioPago::Debito,
+    MedioPago::Credito,
+    MedioPago::Transferencia,
+];
+
+// Resumen de lo facturado en un dia. Solo se incluyen los medios de pago utilizados
+#[derive(Debug, PartialEq)]
+struct CajaDiaria {
+    facturas: u32,
+    por_medio_pago: Vec<(MedioPago, f64)>,
+    total: f64,
+}
+
+impl Item {
+    fn new(
+        codigo: String,
+        descripcion: String,
+        tipo: TipoItem,
+        precio: f64,
+        stock: u32,
+        stock_minimo: u32,
+    ) -> Item {
+        if precio < 0.0 {
+            panic!("Precio no valido");
+        }
+
+        Item {
+            codigo,
+            descripcion,
+            tipo,
+            precio,
+            stock,
+            stock_minimo,
+        }
+    }
+
+    fn controla_stock(&self) -> bool {
+        self.tipo != TipoItem::Servicio
+    }
+
+    fn stock_bajo(&self) -> bool {
+        self.controla_stock() && self.stock <= self.stock_minimo
+    }
+}
+
+impl LineaFactura {
+    fn subtotal(&self) -> f64 {
+        self.cantidad as f64 * self.precio_unitario
+    }
+}
+
+impl Factura {
+    fn total(&self) -> f64 {
+        self.lineas.iter().map(|l| l.subtotal()).sum()
+    }
+}
+
+impl Veterinaria {
+    // Si el inventario no puede guardarse, se descartan los cambios
+    fn guardar_inventario(&mut self, previo: Vec<Item>) -> Result<(), ErrorVeterinaria> {
+        if let Err(e) = Self::escribir_archivo(&self.path_inventario, &self.inventario) {
+            self.inventario = previo;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn recuperar_inventario(&mut self) -> Result<(), ErrorVeterinaria> {
+        if let Some(inventario) = Self::leer_archivo(&self.path_inventario)? {
+            self.inventario = inventario;
+        }
+
+        Ok(())
+    }
+
+    fn get_item(&self, codigo: &str) -> Option<&Item> {
+        self.inventario.iter().find(|i| i.codigo == codigo)
+    }
+
+    fn agregar_item(&mut self, item: Item) -> Result<(), ErrorVeterinaria> {
+        if self.get_item(&item.codigo).is_some() {
+            return Err(ErrorVeterinaria::ItemDuplicado);
+        }
+
+        let previo = self.inventario.clone();
+        self.inventario.push(item);
+
+        self.guardar_inventario(previo)
+    }
+
+    // Las facturas ya emitidas conservan el precio anterior
+    fn actualizar_precio(&mut self, codigo: &str, precio: f64) -> Result<(), ErrorVeterinaria> {
+        if precio < 0.0 {
+            panic!("Precio no valido");
+        }
+
+        let previo = self.inventario.clone();
+
+        let Some(item) = self.inventario.iter_mut().find(|i| i.codigo == codigo) else {
+            return Err(ErrorVeterinaria::Item);
+        };
+        item.precio = precio;
+
+        self.guardar_inventario(previo)
+    }
+
+    fn reponer_stock(&mut self, codigo: &str, cantidad: u32) -> Result<(), ErrorVeterinaria> {
+        let previo = self.inventario.clone();
+
+        let Some(item) = self.inventario.iter_mut().find(|i| i.codigo == codigo) else {
+            return Err(ErrorVeterinaria::Item);
+        };
+        item.stock += cantidad;
+
+        self.guardar_inventario(previo)
+    }
+
+    // Medicamentos e insumos cuyo stock llego al minimo
+    fn alertas_stock(&self) -> Vec<&Item> {
+        self.inventario.iter().filter(|i| i.stock_bajo()).collect()
+    }
+
+    // Genera la factura de la atencion con los servicios e insumos consumidos y descuenta el
+    //stock. Si falta algun item o no alcanza el stock, no se modifica nada
+    fn facturar_atencion(
+        &mut self,
+        id_atencion: u32,
+        consumos: &[(&str, u32)],
+        medio_pago: MedioPago,
+        fecha: Fecha,
+    ) -> Result<u32, ErrorVeterinaria> {
+        let Some(index) = self.get_pos_atencion(id_atencion) else {
+            return Err(ErrorVeterinaria::Atencion);
+        };
+
+        if self.registro_atencion[index].factura.is_some() {
+            return Err(ErrorVeterinaria::AtencionFacturada);
+        }
+
+        let mut inventario = self.inventario.clone();
+        let mut lineas = Vec::new();
+
+        for (codigo, cantidad) in consumos {
+            let Some(item) = inventario.iter_mut().find(|i| i.codigo == *codigo) else {
+                return Err(ErrorVeterinaria::Item);
+            };
+
+            if item.controla_stock() {
+                if item.stock < *cantidad {
+                    return Err(ErrorVeterinaria::StockInsuficiente);
+                }
+                item.stock -= cantidad;
+            }
+
+            lineas.push(LineaFactura {
+                codigo: item.codigo.clone(),
+                descripcion: item.descripcion.clone(),
+                cantidad: *cantidad,
+                precio_unitario: item.precio,
+            });
+        }
+
+        let numero = self
+            .registro_atencion
+            .iter()
+            .filter_map(|at| at.factura.as_ref().map(|f| f.numero))
+            .max()
+            .unwrap_or(0)
+            + 1;
+
+        let previo = std::mem::replace(&mut self.inventario, inventario);
+        self.registro_atencion[index].factura = Some(Factura {
+            numero,
+            fecha,
+            medio_pago,
+            lineas,
+        });
+
+        if let Err(e) = Self::escribir_archivo(&self.path_inventario, &self.inventario)
+            .and_then(|_| self.actualizar_archivo(&self.registro_atencion))
+        {
+            self.inventario = previo;
+            self.registro_atencion[index].factura = None;
+            return Err(e);
+        }
+
+        Ok(numero)
+    }
+
+    fn get_factura(&self, id_atencion: u32) -> Option<&Factura> {
+        self.get_atencion(id_atencion)?.factura.as_ref()
+    }
+
+    fn caja_diaria(&self, fecha: &Fecha) -> CajaDiaria {
+        let facturas: Vec<&Factura> = self
+            .registro_atencion
+            .iter()
+            .filter_map(|at| at.factura.as_ref())
+            .filter(|f| f.fecha.eq(fecha))
+            .collect();
+
+        let por_medio_pago: Vec<(MedioPago, f64)> = MEDIOS_PAGO
+            .into_iter()
+            .filter_map(|medio| {
+                let del_medio: Vec<&&Factura> =
+                    facturas.iter().filter(|f| f.medio_pago == medio).collect();
+
+                match del_medio.is_empty() {
+                    true => None,
+                    false => Some((medio, del_medio.iter().map(|f| f.total()).sum())),
+                }
+            })
+            .collect();
+
+        CajaDiaria {
+            facturas: facturas.len() as u32,
+            total: por_medio_pago.iter().map(|(_, monto)| monto).sum(),
+            por_medio_pago,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Atencion, Duenio, Mascota};
+    use super::*;
+    use std::{fs::File, io::prelude::*};
+
+    fn nueva_veterinaria(n: u32) -> Veterinaria {
+        Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            format!("test_files/atenciones_facturacion{}.json", n),
+            Default::default(),
+            Default::default(),
+            format!("test_files/pacientes_facturacion{}.json", n),
+        )
+    }
+
+    // Carga la lista de precios y registra dos atenciones de la misma mascota
+    fn crear_veterinaria(n: u32) -> (Veterinaria, Vec<u32>) {
+        let mut veterinaria = nueva_veterinaria(n);
+
+        let items = [
+            ("CONS", "Consulta", TipoItem::Servicio, 5000.0, 0, 0),
+            (
+                "AMOX",
+                "Amoxicilina 250mg",
+                TipoItem::Medicamento,
+                800.0,
+                10,
+                3,
+            ),
+            ("GASA", "Gasa esteril", TipoItem::Insumo, 150.0, 20, 5),
+        ];
+        for (codigo, descripcion, tipo, precio, stock, minimo) in items {
+            veterinaria
+                .agregar_item(Item::new(
+                    codigo.to_string(),
+                    descripcion.to_string(),
+                    tipo,
+                    precio,
+                    stock,
+                    minimo,
+                ))
+                .unwrap();
+        }
+
+        let id_duenio = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+        let toby = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2021),
+                "Perro".to_string(),
+                id_duenio,
+            ))
+            .unwrap();
+
+        let mut atenciones = Vec::new();
+        for diagnostico in ["Herida", "Otitis"] {
+            atenciones.push(
+                veterinaria
+                    .registrar_atencion(Atencion::new(
+                        toby,
+                        diagnostico.to_string(),
+                        "Tratamiento".to_string(),
+                        None,
+                    ))
+                    .unwrap(),
+            );
+        }
+
+        (veterinaria, atenciones)
+    }
+
+    #[test]
+    fn test_inventario() {
+        let (mut veterinaria, _) = crear_veterinaria(1);
+
+        let e = veterinaria
+            .agregar_item(Item::new(
+                "CONS".to_string(),
+                "Consulta a domicilio".to_string(),
+                TipoItem::Servicio,
+                9000.0,
+                0,
+                0,
+            ))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::ItemDuplicado);
+        println!("{}", e);
+
+        assert!(veterinaria.actualizar_precio("CONS", 5500.0).is_ok());
+        assert_eq!(veterinaria.get_item("CONS").unwrap().precio, 5500.0);
+        assert!(veterinaria.reponer_stock("GASA", 5).is_ok());
+        assert_eq!(veterinaria.get_item("GASA").unwrap().stock, 25);
+
+        let e = veterinaria.reponer_stock("VACU", 5).unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Item);
+        println!("{}", e);
+        assert_eq!(
+            veterinaria.actualizar_precio("VACU", 10.0),
+            Err(ErrorVeterinaria::Item)
+        );
+
+        // Los servicios nunca generan alertas de stock
+
+        assert!(veterinaria.alertas_stock().is_empty());
+        assert!(veterinaria.get_item("VACU").is_none());
+    }
+
+    #[test]
+    fn test_facturar_atencion() {
+        let (mut veterinaria, atenciones) = crear_veterinaria(2);
+        let fecha = Fecha::new(6, 5, 2024);
+
+        let numero = veterinaria
+            .facturar_atencion(
+                atenciones[0],
+                &[("CONS", 1), ("AMOX", 7), ("GASA", 4)],
+                MedioPago::Efectivo,
+                fecha.clone(),
+            )
+            .unwrap();
+        assert_eq!(numero, 1);
+
+        let factura = veterinaria.get_factura(atenciones[0]).unwrap();
+        assert_eq!(factura.lineas.len(), 3);
+        assert_eq!(factura.lineas[1].subtotal(), 5600.0);
+        assert_eq!(factura.total(), 5000.0 + 5600.0 + 600.0);
+
+        // Se descuenta el stock y se avisa del que llego al minimo
+
+        assert_eq!(veterinaria.get_item("AMOX").unwrap().stock, 3);
+        assert_eq!(veterinaria.get_item("GASA").unwrap().stock, 16);
+        let alertas: Vec<&str> = veterinaria
+            .alertas_stock()
+            .iter()
+            .map(|i| i.codigo.as_str())
+            .collect();
+        assert_eq!(alertas, vec!["AMOX"]);
+
+        // Una atencion se factura una sola vez
+
+        let e = veterinaria
+            .facturar_atencion(atenciones[0], &[], MedioPago::Debito, fecha.clone())
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::AtencionFacturada);
+        println!("{}", e);
+
+        let e = veterinaria
+            .facturar_atencion(99, &[], MedioPago::Debito, fecha.clone())
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Atencion);
+        println!("{}", e);
+
+        // Sin stock suficiente o con un item inexistente, no se modifica nada
+
+        let e = veterinaria
+            .facturar_atencion(
+                atenciones[1],
+                &[("GASA", 2), ("AMOX", 2), ("AMOX", 2)],
+                MedioPago::Debito,
+                fecha.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::StockInsuficiente);
+        println!("{}", e);
+
+        assert_eq!(
+            veterinaria.facturar_atencion(
+                atenciones[1],
+                &[("GASA", 2), ("VACU", 1)],
+                MedioPago::Debito,
+                fecha.clone(),
+            ),
+            Err(ErrorVeterinaria::Item)
+        );
+        assert_eq!(veterinaria.get_item("GASA").unwrap().stock, 16);
+        assert!(veterinaria.get_factura(atenciones[1]).is_none());
+
+        // El cambio de precio no afecta a las facturas emitidas
+
+        veterinaria.actualizar_precio("CONS", 6000.0).unwrap();
+        assert_eq!(
+            veterinaria.facturar_atencion(atenciones[1], &[("CONS", 1)], MedioPago::Debito, fecha),
+            Ok(2)
+        );
+        assert_eq!(
+            veterinaria.get_factura(atenciones[0]).unwrap().lineas[0].precio_unitario,
+            5000.0
+        );
+        assert_eq!(
+            veterinaria.get_factura(atenciones[1]).unwrap().total(),
+            6000.0
+        );
+    }
+
+    #[test]
+    fn test_caja_diaria() {
+        let (mut veterinaria, atenciones) = crear_veterinaria(3);
+        let lunes = Fecha::new(6, 5, 2024);
+
+        veterinaria
+            .facturar_atencion(
+                atenciones[0],
+                &[("CONS", 1), ("GASA", 2)],
+                MedioPago::Transferencia,
+                lunes.clone(),
+            )
+            .unwrap();
+        veterinaria
+            .facturar_atencion(
+                atenciones[1],
+                &[("CONS", 1)],
+                MedioPago::Efectivo,
+                lunes.clone(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            veterinaria.caja_diaria(&lunes),
+            CajaDiaria {
+                facturas: 2,
+                por_medio_pago: vec![
+                    (MedioPago::Efectivo, 5000.0),
+                    (MedioPago::Transferencia, 5300.0)
+                ],
+                total: 10300.0,
+            }
+        );
+
+        let caja = veterinaria.caja_diaria(&Fecha::new(7, 5, 2024));
+        assert_eq!(caja.facturas, 0);
+        assert!(caja.por_medio_pago.is_empty());
+        assert_eq!(caja.total, 0.0);
+    }
+
+    #[test]
+    fn test_facturacion_archivo() {
+        let (mut veterinaria, atenciones) = crear_veterinaria(4);
+
+        veterinaria
+            .facturar_atencion(
+                atenciones[0],
+                &[("CONS", 1), ("AMOX", 2)],
+                MedioPago::Credito,
+                Fecha::new(6, 5, 2024),
+            )
+            .unwrap();
+
+        // Las facturas se guardan con las atenciones y el stock en el inventario
+
+        let mut reiniciada = nueva_veterinaria(4);
+        assert!(reiniciada.recuperar_registros().is_ok());
+        assert!(reiniciada.recuperar_inventario().is_ok());
+        assert_eq!(reiniciada.inventario, veterinaria.inventario);
+        assert_eq!(
+            reiniciada.get_factura(atenciones[0]),
+            veterinaria.get_factura(atenciones[0])
+        );
+        assert_eq!(
+            reiniciada.path_inventario,
+            "test_files/atenciones_facturacion4.inventario.json"
+        );
+
+        // Si los archivos no pueden guardarse, no se factura
+
+        reiniciada.file_path = "/".to_string();
+        reiniciada.path_inventario = "/".to_string();
+        assert_eq!(
+            reiniciada.facturar_atencion(
+                atenciones[1],
+                &[("GASA", 1)],
+                MedioPago::Efectivo,
+                Fecha::new(6, 5, 2024),
+            ),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(
+            reiniciada.reponer_stock("GASA", 1),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(reiniciada.inventario, veterinaria.inventario);
+        assert!(reiniciada.get_factura(atenciones[1]).is_none());
+
+        // Un archivo con formato no valido
+
+        File::create("test_files/inventario_invalido.json")
+            .unwrap()
+            .write_all(b"[{\"codigo\": 1}]")
+            .unwrap();
+        reiniciada.path_inventario = "test_files/inventario_invalido.json".to_string();
+        assert_eq!(
+            reiniciada.recuperar_inventario(),
+            Err(ErrorVeterinaria::Archivo)
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_precio_no_valido() {
+        Item::new(
+            "CONS".to_string(),
+            "Consulta".to_string(),
+            TipoItem::Servicio,
+            -1.0,
+            0,
+            0,
+        );
+    }
+}
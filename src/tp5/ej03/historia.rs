@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+
+use super::{Atencion, Duenio, Mascota, Receta, Vacuna, Veterinaria};
+use crate::tp3::ej03::Fecha;
+
+// Historia clinica de una mascota, armada a partir de sus atenciones
+#[derive(Debug)]
+struct HistoriaClinica<'a> {
+    mascota: &'a Mascota,
+    duenio: &'a Duenio,
+    visitas: Vec<&'a Atencion>,
+}
+
+// Las fechas desconocidas se ordenan antes que cualquier fecha
+fn comparar_fechas(a: Option<&Fecha>, b: Option<&Fecha>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => 0.cmp(&a.dias_hasta(b)),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+impl<'a> HistoriaClinica<'a> {
+    fn vacunas(&self) -> Vec<&'a Vacuna> {
+        let mut vacunas: Vec<&Vacuna> = self.visitas.iter().flat_map(|v| &v.vacunas).collect();
+        vacunas
+            .sort_by(|a, b| comparar_fechas(Some(&a.fecha_aplicacion), Some(&b.fecha_aplicacion)));
+
+        vacunas
+    }
+
+    // Cada receta junto con la fecha de la visita en que se indico
+    fn recetas(&self) -> Vec<(Option<&'a Fecha>, &'a Receta)> {
+        self.visitas
+            .iter()
+            .flat_map(|v| v.recetas.iter().map(|r| (v.fecha.as_ref(), r)))
+            .collect()
+    }
+
+    // Solo se incluyen las visitas con fecha en las que se registro el peso
+    fn historial_peso(&self) -> Vec<(&'a Fecha, f64)> {
+        self.visitas
+            .iter()
+            .filter_map(|v| Some((v.fecha.as_ref()?, v.peso?)))
+            .collect()
+    }
+
+    fn alergias(&self) -> Vec<&'a str> {
+        let mut alergias: Vec<&str> = Vec::new();
+
+        for alergia in self.visitas.iter().flat_map(|v| &v.alergias) {
+            if !alergias.iter().any(|a| a.eq_ignore_ascii_case(alergia)) {
+                alergias.push(alergia);
+            }
+        }
+
+        alergias
+    }
+
+    // Ultima aplicacion de cada vacuna, ya que un refuerzo reemplaza a la dosis anterior
+    fn vacunas_vigentes(&self) -> Vec<&'a Vacuna> {
+        let mut vigentes: Vec<&Vacuna> = Vec::new();
+
+        for vacuna in self.vacunas() {
+            match vigentes
+                .iter_mut()
+                .find(|v| v.nombre.eq_ignore_ascii_case(&vacuna.nombre))
+            {
+                Some(v) => *v = vacuna,
+                None => vigentes.push(vacuna),
+            }
+        }
+
+        vigentes
+    }
+}
+
+impl Veterinaria {
+    // Agrupa las atenciones por mascota, de la visita mas antigua a la mas reciente. Los datos
+    //de la mascota y su duenio son los actuales del registro
+    fn historias_clinicas(&self) -> Vec<HistoriaClinica<'_>> {
+        let mut historias: Vec<HistoriaClinica> = Vec::new();
+
+        for at in &self.registro_atencion {
+            match historias.iter_mut().find(|h| h.mascota.id == at.id_mascota) {
+                Some(h) => h.visitas.push(at),
+                None => {
+                    let Some(mascota) = self.get_mascota(at.id_mascota) else {
+                        continue;
+                    };
+                    let Some(duenio) = self.get_duenio(mascota.id_duenio) else {
+                        continue;
+                    };
+
+                    historias.push(HistoriaClinica {
+                        mascota,
+                        duenio,
+                        visitas: vec![at],
+                    });
+                }
+            }
+        }
+
+        for h in historias.iter_mut() {
+            h.visitas
+                .sort_by(|a, b| comparar_fechas(a.fecha.as_ref(), b.fecha.as_ref()));
+        }
+
+        historias
+    }
+
+    fn historia_clinica(&self, id_mascota: u32) -> Option<HistoriaClinica<'_>> {
+        self.historias_clinicas()
+            .into_iter()
+            .find(|h| h.mascota.id == id_mascota)
+    }
+
+    // Busca por parte del nombre de la mascota o del duenio, sin distinguir mayusculas
+    fn buscar_historias(&self, consulta: &str) -> Vec<HistoriaClinica<'_>> {
+        let consulta = consulta.to_lowercase();
+
+        self.historias_clinicas()
+            .into_iter()
+            .filter(|h| {
+                h.mascota.nombre.to_lowercase().contains(&consulta)
+                    || h.duenio.nombre.to_lowercase().contains(&consulta)
+            })
+            .collect()
+    }
+
+    // Vacunas cuyo refuerzo vence dentro de los proximos dias (o ya vencio), ordenadas por
+    //fecha de la proxima dosis
+    fn vacunas_a_vencer(&self, dias: u32, fecha_actual: &Fecha) -> Vec<(&Mascota, &Vacuna)> {
+        let mut limite = fecha_actual.clone();
+        limite.sumar_dias(dias + 1);
+
+        let mut vacunas: Vec<(&Mascota, &Vacuna)> = self
+            .historias_clinicas()
+            .into_iter()
+            .flat_map(|h| {
+                h.vacunas_vigentes()
+                    .into_iter()
+                    .map(move |v| (h.mascota, v))
+            })
+            .filter(|(_, v)| v.proxima_dosis.as_ref().is_some_and(|f| limite.es_mayor(f)))
+            .collect();
+
+        vacunas.sort_by(|a, b| {
+            comparar_fechas(a.1.proxima_dosis.as_ref(), b.1.proxima_dosis.as_ref())
+        });
+
+        vacunas
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn crear_veterinaria(n: u32) -> (Veterinaria, Vec<u32>) {
+        let mut veterinaria = Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            format!("test_files/historias{}.json", n),
+            Default::default(),
+            Default::default(),
+            format!("test_files/pacientes_historias{}.json", n),
+        );
+
+        let pedro = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+        let ana = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Ana".to_string(),
+                "7 y 50".to_string(),
+                "2214567890".to_string(),
+            ))
+            .unwrap();
+
+        let toby = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2021),
+                "Perro".to_string(),
+                pedro,
+            ))
+            .unwrap();
+        let michi = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Michi".to_string(),
+                Fecha::new(1, 1, 2019),
+                "Gato".to_string(),
+                ana,
+            ))
+            .unwrap();
+
+        // Primera visita de Toby: vacunas y peso
+
+        let mut at1 = Atencion::new(
+            toby,
+            "Control anual".to_string(),
+            "Ninguno".to_string(),
+            Some(Fecha::new(10, 3, 2025)),
+        );
+        at1.set_fecha(Fecha::new(10, 3, 2024));
+        at1.set_peso(12.5);
+        at1.agregar_vacuna(Vacuna::new(
+            "Antirrabica".to_string(),
+            "AR-2231".to_string(),
+            Fecha::new(10, 3, 2024),
+            Some(Fecha::new(10, 3, 2025)),
+        ));
+        at1.agregar_vacuna(Vacuna::new(
+            "Sextuple".to_string(),
+            "SX-118".to_string(),
+            Fecha::new(10, 3, 2024),
+            Some(Fecha::new(10, 4, 2024)),
+        ));
+
+        // Segunda visita: refuerzo de la sextuple, receta y alergia detectada
+
+        let mut at2 = Atencion::new(toby, "Otitis".to_string(), "Gotas".to_string(), None);
+        at2.set_fecha(Fecha::new(12, 4, 2024));
+        at2.set_peso(13.1);
+        at2.agregar_vacuna(Vacuna::new(
+            "Sextuple".to_string(),
+            "SX-204".to_string(),
+            Fecha::new(12, 4, 2024),
+            Some(Fecha::new(12, 4, 2025)),
+        ));
+        at2.agregar_receta(Receta::new("Amoxicilina".to_string(), 250.0, 12, 7));
+        at2.agregar_alergia("Penicilina".to_string());
+
+        // Visita de Michi (sin fecha, como en los registros previos)
+
+        let mut at3 = Atencion::new(michi, "Control".to_string(), "Ninguno".to_string(), None);
+        at3.agregar_vacuna(Vacuna::new(
+            "Triple felina".to_string(),
+            "TF-77".to_string(),
+            Fecha::new(1, 2, 2024),
+            Some(Fecha::new(1, 5, 2024)),
+        ));
+        at3.agregar_alergia("penicilina".to_string());
+        at3.agregar_alergia("Pollo".to_string());
+
+        // Se registran desordenadas: la historia se ordena por fecha
+
+        for at in [at2, at3, at1] {
+            veterinaria.registrar_atencion(at).unwrap();
+        }
+
+        (veterinaria, vec![toby, michi])
+    }
+
+    #[test]
+    fn test_historia_clinica() {
+        let (mut veterinaria, mascotas) = crear_veterinaria(1);
+
+        let historia = veterinaria.historia_clinica(mascotas[0]).unwrap();
+        assert_eq!(historia.mascota.id, mascotas[0]);
+        assert_eq!(historia.duenio.nombre, "Pedro");
+        assert_eq!(historia.visitas.len(), 2);
+        assert_eq!(historia.visitas[0].diagnostico, "Control anual");
+
+        let lotes: Vec<&str> = historia.vacunas().iter().map(|v| v.lote.as_str()).collect();
+        assert_eq!(lotes, vec!["AR-2231", "SX-118", "SX-204"]);
+
+        let recetas = historia.recetas();
+        assert_eq!(recetas.len(), 1);
+        assert_eq!(recetas[0].0, Some(&Fecha::new(12, 4, 2024)));
+        assert_eq!(recetas[0].1.cantidad_tomas(), 14);
+
+        assert_eq!(
+            historia.historial_peso(),
+            vec![
+                (&Fecha::new(10, 3, 2024), 12.5),
+                (&Fecha::new(12, 4, 2024), 13.1)
+            ]
+        );
+        assert_eq!(historia.alergias(), vec!["Penicilina"]);
+
+        // Otro perro de Pedro con el mismo nombre tiene su propia historia
+
+        let pedro = veterinaria.get_mascota(mascotas[0]).unwrap().id_duenio;
+        let toby2 = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2023),
+                "Perro".to_string(),
+                pedro,
+            ))
+            .unwrap();
+        assert!(veterinaria.historia_clinica(toby2).is_none());
+        assert_eq!(veterinaria.buscar_historias("toby").len(), 1);
+
+        // Busqueda por mascota o por duenio
+
+        let historias = veterinaria.buscar_historias("ana");
+        assert_eq!(historias.len(), 1);
+        assert_eq!(historias[0].mascota.id, mascotas[1]);
+        assert_eq!(historias[0].alergias(), vec!["penicilina", "Pollo"]);
+        assert!(historias[0].historial_peso().is_empty());
+
+        assert_eq!(veterinaria.buscar_historias("TOB").len(), 1);
+        assert_eq!(veterinaria.buscar_historias("").len(), 2);
+        assert!(veterinaria.buscar_historias("Firulais").is_empty());
+    }
+
+    #[test]
+    fn test_vacunas_a_vencer() {
+        let (veterinaria, mascotas) = crear_veterinaria(2);
+
+        // El refuerzo de la sextuple reemplaza a la primera dosis
+
+        let vacunas = veterinaria.vacunas_a_vencer(30, &Fecha::new(15, 4, 2024));
+        assert_eq!(vacunas.len(), 1);
+        assert_eq!(vacunas[0].0.id, mascotas[1]);
+        assert_eq!(vacunas[0].1.lote, "TF-77");
+
+        // Las vencidas tambien se informan, ordenadas por fecha
+
+        let vacunas = veterinaria.vacunas_a_vencer(10, &Fecha::new(5, 3, 2025));
+        let lotes: Vec<&str> = vacunas.iter().map(|v| v.1.lote.as_str()).collect();
+        assert_eq!(lotes, vec!["TF-77", "AR-2231"]);
+
+        assert_eq!(
+            veterinaria
+                .vacunas_a_vencer(365, &Fecha::new(15, 4, 2024))
+                .len(),
+            3
+        );
+        assert!(veterinaria
+            .vacunas_a_vencer(10, &Fecha::new(1, 1, 2024))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_historia_archivo() {
+        let (veterinaria, _) = crear_veterinaria(3);
+
+        // Los datos clinicos se guardan junto con las atenciones
+
+        let buf = std::fs::read_to_string(&veterinaria.file_path).unwrap();
+        let atenciones: Vec<Atencion> = serde_json::from_str(&buf).unwrap();
+        assert_eq!(atenciones, veterinaria.registro_atencion);
+
+        // Las atenciones con el formato previo se leen sin datos clinicos
+
+        let previo = r#"[{
+            "id_mascota": 1,
+            "diagnostico": "Herida",
+            "tratamiento": "Gasa",
+            "prox_visita": null
+        }]"#;
+        let atenciones: Vec<Atencion> = serde_json::from_str(previo).unwrap();
+        assert!(atenciones[0].vacunas.is_empty());
+        assert!(atenciones[0].fecha.is_none());
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_vacuna_no_valida() {
+        Vacuna::new(
+            "Antirrabica".to_string(),
+            "AR-1".to_string(),
+            Fecha::new(10, 3, 2024),
+            Some(Fecha::new(10, 3, 2024)),
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_receta_no_valida() {
+        Receta::new("Amoxicilina".to_string(), 250.0, 0, 7);
+    }
+}
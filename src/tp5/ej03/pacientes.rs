@@ -0,0 +1,537 @@
+use chrono::{DateTime, Datelike, Local};
+use serde::Deserialize;
+
+use super::{Duenio, ErrorVeterinaria, Mascota, Pacientes, Veterinaria};
+use crate::tp3::ej03::Fecha;
+
+// Formato previo, en el que cada atencion guardaba una copia de la mascota y su duenio
+#[derive(Deserialize)]
+struct MascotaPrevia {
+    nombre: String,
+    #[serde(default)]
+    edad: Option<u32>,
+    tipo: String,
+    duenio: Duenio,
+}
+
+// Fecha de la ultima modificacion del archivo, que se toma como la fecha en la que se
+//registro la edad del formato previo
+fn fecha_archivo(path: &str) -> Option<Fecha> {
+    let modificado = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let fecha = DateTime::<Local>::from(modificado).date_naive();
+
+    Some(Fecha::new(fecha.day(), fecha.month(), fecha.year()))
+}
+
+// La mascota cumplio los anios indicados en la fecha de registro. Un 29 de febrero se
+//corre al 28 si el anio de nacimiento no es bisiesto
+fn estimar_nacimiento(edad: u32, fecha_registro: &Fecha) -> Fecha {
+    let anio = fecha_registro.get_anio() - edad as i32;
+    let dia = match (fecha_registro.get_dia(), fecha_registro.get_mes()) {
+        (29, 2) if !Fecha::new(1, 1, anio).es_bisiesto() => 28,
+        (dia, _) => dia,
+    };
+
+    Fecha::new(dia, fecha_registro.get_mes(), anio)
+}
+
+impl Veterinaria {
+    // Si el registro no puede guardarse, se descartan los cambios
+    pub(super) fn guardar_pacientes(&mut self, previos: Pacientes) -> Result<(), ErrorVeterinaria> {
+        if let Err(e) = Self::escribir_archivo(&self.path_pacientes, &self.pacientes) {
+            self.pacientes = previos;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    // El formato previo guardaba la edad de la mascota en lugar de su fecha de nacimiento
+    pub(super) fn recuperar_pacientes(&mut self) -> Result<(), ErrorVeterinaria> {
+        let Some(mut valor) = Self::leer_archivo::<serde_json::Value>(&self.path_pacientes)? else {
+            return Ok(());
+        };
+
+        let fecha_registro = fecha_archivo(&self.path_pacientes);
+        if let (Some(mascotas), Some(fecha)) = (
+            valor.get_mut("mascotas").and_then(|m| m.as_array_mut()),
+            fecha_registro,
+        ) {
+            for mascota in mascotas.iter_mut().filter_map(|m| m.as_object_mut()) {
+                let Some(edad) = mascota.get("edad").and_then(|e| e.as_u64()) else {
+                    continue;
+                };
+
+                if mascota.get("fecha_nacimiento").is_none() {
+                    let nacimiento = estimar_nacimiento(edad as u32, &fecha);
+                    mascota.insert(
+                        "fecha_nacimiento".to_string(),
+                        serde_json::to_value(nacimiento).unwrap(),
+                    );
+                }
+            }
+        }
+
+        let Ok(pacientes) = serde_json::from_value::<Pacientes>(valor) else {
+            return Err(ErrorVeterinaria::Archivo);
+        };
+
+        self.pacientes = pacientes;
+
+        Ok(())
+    }
+
+    fn generar_id_duenio(&self) -> u32 {
+        self.pacientes
+            .duenios
+            .iter()
+            .map(|d| d.id)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    fn generar_id_mascota(&self) -> u32 {
+        self.pacientes
+            .mascotas
+            .iter()
+            .map(|m| m.id)
+            .max()
+            .unwrap_or(0)
+            + 1
+    }
+
+    pub(super) fn registrar_duenio(&mut self, mut duenio: Duenio) -> Result<u32, ErrorVeterinaria> {
+        let previos = self.pacientes.clone();
+
+        duenio.id = self.generar_id_duenio();
+        let id = duenio.id;
+        self.pacientes.duenios.push(duenio);
+        self.guardar_pacientes(previos)?;
+
+        Ok(id)
+    }
+
+    // Un mismo duenio puede tener varias mascotas con el mismo nombre, cada una con su id
+    pub(super) fn registrar_mascota(
+        &mut self,
+        mut mascota: Mascota,
+    ) -> Result<u32, ErrorVeterinaria> {
+        if self.get_duenio(mascota.id_duenio).is_none() {
+            return Err(ErrorVeterinaria::Duenio);
+        }
+        self.validar_mascota(&mascota)?;
+
+        let previos = self.pacientes.clone();
+
+        mascota.id = self.generar_id_mascota();
+        let id = mascota.id;
+        self.pacientes.mascotas.push(mascota);
+        self.guardar_pacientes(previos)?;
+
+        Ok(id)
+    }
+
+    pub(super) fn get_duenio(&self, id: u32) -> Option<&Duenio> {
+        self.pacientes.duenios.iter().find(|d| d.id == id)
+    }
+
+    pub(super) fn get_mascota(&self, id: u32) -> Option<&Mascota> {
+        self.pacientes.mascotas.iter().find(|m| m.id == id)
+    }
+
+    // Las atenciones referencian a la mascota por su id, por lo que el historial se conserva
+    fn modificar_contacto(
+        &mut self,
+        id_duenio: u32,
+        direccion: String,
+        telefono: String,
+    ) -> Result<(), ErrorVeterinaria> {
+        let previos = self.pacientes.clone();
+
+        let Some(duenio) = self
+            .pacientes
+            .duenios
+            .iter_mut()
+            .find(|d| d.id == id_duenio)
+        else {
+            return Err(ErrorVeterinaria::Duenio);
+        };
+        duenio.direccion = direccion;
+        duenio.telefono = telefono;
+
+        self.guardar_pacientes(previos)
+    }
+
+    fn mascotas_duenio(&self, id_duenio: u32) -> Vec<&Mascota> {
+        self.pacientes
+            .mascotas
+            .iter()
+            .filter(|m| m.id_duenio == id_duenio)
+            .collect()
+    }
+
+    // Busca por parte del nombre de la mascota o de su duenio, sin distinguir mayusculas
+    fn buscar_mascotas(&self, consulta: &str) -> Vec<&Mascota> {
+        let consulta = consulta.to_lowercase();
+
+        self.pacientes
+            .mascotas
+            .iter()
+            .filter(|m| {
+                m.nombre.to_lowercase().contains(&consulta)
+                    || self
+                        .get_duenio(m.id_duenio)
+                        .is_some_and(|d| d.nombre.to_lowercase().contains(&consulta))
+            })
+            .collect()
+    }
+
+    fn buscar_duenios(&self, consulta: &str) -> Vec<&Duenio> {
+        let consulta = consulta.to_lowercase();
+
+        self.pacientes
+            .duenios
+            .iter()
+            .filter(|d| d.nombre.to_lowercase().contains(&consulta))
+            .collect()
+    }
+
+    // Registra (o reutiliza) la mascota de un registro con el formato previo, sin guardar el
+    //archivo. El duenio se identifica por nombre y telefono, como en las busquedas previas
+    pub(super) fn migrar_mascota(&mut self, valor: serde_json::Value) -> Option<u32> {
+        let previa = serde_json::from_value::<MascotaPrevia>(valor).ok()?;
+
+        let id_duenio = match self
+            .pacientes
+            .duenios
+            .iter()
+            .find(|d| d.nombre == previa.duenio.nombre && d.telefono == previa.duenio.telefono)
+        {
+            Some(d) => d.id,
+            None => {
+                let mut duenio = previa.duenio;
+                duenio.id = self.generar_id_duenio();
+                self.pacientes.duenios.push(duenio);
+                self.pacientes.duenios.last().unwrap().id
+            }
+        };
+
+        if let Some(m) = self.pacientes.mascotas.iter().find(|m| {
+            m.nombre == previa.nombre && m.especie == previa.tipo && m.id_duenio == id_duenio
+        }) {
+            return Some(m.id);
+        }
+
+        let fecha_registro = fecha_archivo(&self.file_path);
+        let mascota = Mascota {
+            id: self.generar_id_mascota(),
+            nombre: previa.nombre,
+            fecha_nacimiento: previa
+                .edad
+                .zip(fecha_registro)
+                .map(|(edad, fecha)| estimar_nacimiento(edad, &fecha)),
+            especie: previa.tipo,
+            raza: None,
+            id_duenio,
+        };
+        self.pacientes.mascotas.push(mascota);
+
+        Some(self.pacientes.mascotas.last().unwrap().id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::Atencion;
+    use super::*;
+    use std::{fs::File, io::prelude::*};
+
+    fn crear_veterinaria(n: u32) -> Veterinaria {
+        Veterinaria::new(
+            "Veterinaria".to_string(),
+            "Direccion".to_string(),
+            5,
+            format!("test_files/atenciones_pacientes{}.json", n),
+            Default::default(),
+            Default::default(),
+            format!("test_files/pacientes{}.json", n),
+        )
+    }
+
+    #[test]
+    fn test_registro_pacientes() {
+        let mut veterinaria = crear_veterinaria(1);
+
+        let pedro = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro Perez".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+        let ana = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Ana".to_string(),
+                "7 y 50".to_string(),
+                "2214567890".to_string(),
+            ))
+            .unwrap();
+        assert_eq!((pedro, ana), (1, 2));
+
+        // Dos perros con el mismo nombre y duenio son mascotas distintas
+
+        let toby = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2021),
+                "Perro".to_string(),
+                pedro,
+            ))
+            .unwrap();
+        let toby2 = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2023),
+                "Perro".to_string(),
+                pedro,
+            ))
+            .unwrap();
+        let michi = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Michi".to_string(),
+                Fecha::new(1, 1, 2019),
+                "Gato".to_string(),
+                ana,
+            ))
+            .unwrap();
+        assert_ne!(toby, toby2);
+        assert_eq!(
+            veterinaria
+                .get_mascota(toby2)
+                .unwrap()
+                .edad(&Fecha::new(1, 6, 2024)),
+            Some(1)
+        );
+        assert_eq!(veterinaria.mascotas_duenio(pedro).len(), 2);
+        assert!(veterinaria.get_mascota(9).is_none());
+
+        let e = veterinaria
+            .registrar_mascota(Mascota::new(
+                "Rex".to_string(),
+                Fecha::new(1, 1, 2022),
+                "Perro".to_string(),
+                9,
+            ))
+            .unwrap_err();
+        assert_eq!(e, ErrorVeterinaria::Duenio);
+        println!("{}", e);
+
+        // Busqueda parcial por mascota o por duenio
+
+        let ids = |mascotas: Vec<&Mascota>| -> Vec<u32> { mascotas.iter().map(|m| m.id).collect() };
+        assert_eq!(ids(veterinaria.buscar_mascotas("tob")), vec![toby, toby2]);
+        assert_eq!(ids(veterinaria.buscar_mascotas("PEREZ")), vec![toby, toby2]);
+        assert_eq!(ids(veterinaria.buscar_mascotas("ana")), vec![michi]);
+        assert!(veterinaria.buscar_mascotas("Firulais").is_empty());
+        assert_eq!(veterinaria.buscar_duenios("an")[0].id, ana);
+
+        // Al cambiar el contacto del duenio, las atenciones siguen asociadas a la mascota
+
+        let id_atencion = veterinaria
+            .registrar_atencion(Atencion::new(
+                toby,
+                "Otitis".to_string(),
+                "Gotas".to_string(),
+                None,
+            ))
+            .unwrap();
+
+        assert!(veterinaria
+            .modificar_contacto(pedro, "7 y 32".to_string(), "2215550000".to_string())
+            .is_ok());
+        assert_eq!(
+            veterinaria.get_duenio(pedro).unwrap().telefono,
+            "2215550000"
+        );
+        assert_eq!(
+            veterinaria.get_atencion(id_atencion).unwrap().id_mascota,
+            toby
+        );
+        assert_eq!(veterinaria.atenciones_mascota(toby).len(), 1);
+        assert!(veterinaria.atenciones_mascota(toby2).is_empty());
+
+        assert_eq!(
+            veterinaria.modificar_contacto(9, "".to_string(), "".to_string()),
+            Err(ErrorVeterinaria::Duenio)
+        );
+    }
+
+    #[test]
+    fn test_pacientes_archivo() {
+        let mut veterinaria = crear_veterinaria(2);
+
+        let pedro = veterinaria
+            .registrar_duenio(Duenio::new(
+                "Pedro".to_string(),
+                "155".to_string(),
+                "2217485463".to_string(),
+            ))
+            .unwrap();
+        veterinaria
+            .registrar_mascota(Mascota::new(
+                "Toby".to_string(),
+                Fecha::new(1, 1, 2021),
+                "Perro".to_string(),
+                pedro,
+            ))
+            .unwrap();
+
+        let mut reiniciada = crear_veterinaria(2);
+        assert!(reiniciada.recuperar_registros().is_ok());
+        assert_eq!(reiniciada.pacientes, veterinaria.pacientes);
+
+        // Si el registro no puede guardarse, no se modifica
+
+        reiniciada.path_pacientes = "/".to_string();
+        assert_eq!(
+            reiniciada.registrar_mascota(Mascota::new(
+                "Rex".to_string(),
+                Fecha::new(1, 1, 2022),
+                "Perro".to_string(),
+                pedro
+            )),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(
+            reiniciada.modificar_contacto(pedro, "".to_string(), "".to_string()),
+            Err(ErrorVeterinaria::Archivo)
+        );
+        assert_eq!(reiniciada.pacientes, veterinaria.pacientes);
+
+        // Un archivo con formato no valido
+
+        File::create("test_files/pacientes_invalido.json")
+            .unwrap()
+            .write_all(b"{\"mascotas\": 1}")
+            .unwrap();
+        reiniciada.path_pacientes = "test_files/pacientes_invalido.json".to_string();
+        assert_eq!(
+            reiniciada.recuperar_pacientes(),
+            Err(ErrorVeterinaria::Archivo)
+        );
+    }
+
+    #[test]
+    fn test_migrar_atenciones() {
+        let mut veterinaria = crear_veterinaria(3);
+
+        // Atenciones con el formato previo: la mascota y su duenio se guardaban en cada una
+
+        let previo = r#"[
+            {
+                "mascota": {"nombre": "Toby", "edad": 3, "tipo": "Perro",
+                    "duenio": {"nombre": "Pedro", "direccion": "155", "telefono": "2217485463"}},
+                "diagnostico": "Herida",
+                "tratamiento": "Gasa",
+                "prox_visita": null
+            },
+            {
+                "mascota": {"nombre": "Toby", "edad": 4, "tipo": "Perro",
+                    "duenio": {"nombre": "Pedro", "direccion": "7 y 32", "telefono": "2217485463"}},
+                "diagnostico": "Control",
+                "tratamiento": "Ninguno",
+                "prox_visita": {"dia": 5, "mes": 6, "anio": 2024}
+            },
+            {
+                "mascota": {"nombre": "Michi", "edad": 5, "tipo": "Gato",
+                    "duenio": {"nombre": "Ana", "direccion": "7 y 50", "telefono": "2214567890"}},
+                "diagnostico": "Control",
+                "tratamiento": "Ninguno",
+                "prox_visita": null
+            }
+        ]"#;
+        File::create(&veterinaria.file_path)
+            .unwrap()
+            .write_all(previo.as_bytes())
+            .unwrap();
+
+        assert!(veterinaria.recuperar_registros().is_ok());
+        assert_eq!(veterinaria.pacientes.duenios.len(), 2);
+        assert_eq!(veterinaria.pacientes.mascotas.len(), 2);
+
+        let ids: Vec<(u32, u32)> = veterinaria
+            .registro_atencion
+            .iter()
+            .map(|a| (a.id, a.id_mascota))
+            .collect();
+        assert_eq!(ids, vec![(1, 1), (2, 1), (3, 2)]);
+        assert!(veterinaria.registro_atencion[0].vacunas.is_empty());
+
+        // La fecha de nacimiento se estima con la edad de la primera atencion y la fecha
+        //del archivo
+
+        let hoy = fecha_archivo(&veterinaria.file_path).unwrap();
+        assert_eq!(veterinaria.get_mascota(1).unwrap().edad(&hoy), Some(3));
+        assert_eq!(veterinaria.get_mascota(2).unwrap().edad(&hoy), Some(5));
+
+        // Los archivos se reescriben con el formato nuevo
+
+        let mut reiniciada = crear_veterinaria(3);
+        assert!(reiniciada.recuperar_registros().is_ok());
+        assert_eq!(reiniciada.pacientes, veterinaria.pacientes);
+        assert_eq!(reiniciada.registro_atencion, veterinaria.registro_atencion);
+
+        // Una mascota con el formato previo incompleto
+
+        File::create(&veterinaria.file_path)
+            .unwrap()
+            .write_all(b"[{\"mascota\": {\"nombre\": \"Toby\"}}]")
+            .unwrap();
+        assert_eq!(
+            veterinaria.recuperar_registros(),
+            Err(ErrorVeterinaria::Archivo)
+        );
+    }
+
+    #[test]
+    fn test_migrar_pacientes() {
+        let mut veterinaria = crear_veterinaria(4);
+
+        // Registro con el formato previo, que guardaba la edad y el tipo de la mascota
+
+        let previo = r#"{
+            "duenios": [],
+            "mascotas": [
+                {"id": 1, "nombre": "Toby", "edad": 3, "tipo": "Perro", "id_duenio": 1},
+                {"id": 2, "nombre": "Michi", "fecha_nacimiento": null, "especie": "Gato",
+                    "id_duenio": 1}
+            ]
+        }"#;
+        File::create(&veterinaria.path_pacientes)
+            .unwrap()
+            .write_all(previo.as_bytes())
+            .unwrap();
+
+        assert!(veterinaria.recuperar_pacientes().is_ok());
+        let hoy = fecha_archivo(&veterinaria.path_pacientes).unwrap();
+        assert_eq!(veterinaria.get_mascota(1).unwrap().edad(&hoy), Some(3));
+        assert!(veterinaria.get_mascota(2).unwrap().edad(&hoy).is_none());
+    }
+
+    #[test]
+    fn test_estimar_nacimiento() {
+        assert_eq!(
+            estimar_nacimiento(3, &Fecha::new(10, 5, 2024)),
+            Fecha::new(10, 5, 2021)
+        );
+        assert_eq!(
+            estimar_nacimiento(1, &Fecha::new(29, 2, 2024)),
+            Fecha::new(28, 2, 2023)
+        );
+        assert_eq!(
+            estimar_nacimiento(4, &Fecha::new(29, 2, 2024)),
+            Fecha::new(29, 2, 2020)
+        );
+    }
+}
@@ -1,22 +1,49 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::{fmt::Display, fs::File, io::prelude::*, path::Path};
 
 use crate::tp3::ej03::Fecha;
+use crate::tp3::ej10::Isbn;
+
+mod intercambio;
+mod reportes;
+
+const MULTA_DIARIA: f64 = 50.0;
+const DIAS_RETIRO_RESERVA: u32 = 3;
+const COSTO_REPOSICION: f64 = 15000.0;
+
+// Peso de cada campo en el ranking de busqueda
+const PESO_TITULO: u32 = 3;
+const PESO_AUTOR: u32 = 2;
+const PESO_GENERO: u32 = 1;
 
 #[derive(Debug)]
 struct Biblioteca {
     nombre: String,
     direccion: String,
-    copias: HashMap<Libro, u32>,
+    copias: HashMap<Libro, Vec<Ejemplar>>,
     prestamos: Vec<Prestamo>,
+    reservas: HashMap<Libro, VecDeque<Reserva>>,
+    politicas_categoria: HashMap<CategoriaCliente, PoliticaPrestamo>,
+    politicas_genero: HashMap<Generos, PoliticaPrestamo>,
+    indice: IndiceCatalogo,
+    clientes: Vec<Cliente>,
     path_copias: String,
     path_prestamos: String,
+    path_clientes: String,
+    path_indice: String,
+    path_reservas: String,
+}
+
+// Indice invertido: cada termino normalizado se asocia a los isbn que lo contienen y su peso
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndiceCatalogo {
+    terminos: HashMap<String, HashMap<Isbn, u32>>,
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize, Eq, Hash)]
 struct Libro {
-    isbn: u32,
+    isbn: Isbn,
     titulo: String,
     autor: String,
     paginas: u32,
@@ -31,20 +58,83 @@ enum Generos {
     Otros,
 }
 
+// Copia fisica de un libro, identificada por su codigo de barras
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Ejemplar {
+    codigo: String,
+    estado: EstadoEjemplar,
+    ubicacion: String,
+    costo_reposicion: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum EstadoEjemplar {
+    Disponible,
+    Prestado,
+    Reservado,
+    Danado,
+    Perdido,
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Prestamo {
     libro: Libro,
-    cliente: Cliente,
+    #[serde(default)]
+    codigo_ejemplar: String,
+    id_cliente: u32,
+    #[serde(default)]
+    fecha_prestamo: Option<Fecha>,
     fecha_vencimiento: Fecha,
     fecha_devolucion: Option<Fecha>,
     fue_devuelto: bool,
+    #[serde(default)]
+    renovaciones: u32,
+    #[serde(default)]
+    multa: f64,
+    // Reposicion de un ejemplar perdido o devuelto con danios, se abona junto con la multa
+    #[serde(default)]
+    cargo_danio: f64,
+    #[serde(default)]
+    multa_pagada: bool,
+}
+
+// Una reserva tiene ejemplar y fecha limite una vez que se le asigna una copia devuelta
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Reserva {
+    id_cliente: u32,
+    codigo_ejemplar: Option<String>,
+    fecha_limite: Option<Fecha>,
 }
 
+// El id se asigna al registrar el cliente en la biblioteca. Sin vencimiento, la membresia no caduca
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Cliente {
+    #[serde(default)]
+    id: u32,
     nombre: String,
     telefono: String,
     email: String,
+    #[serde(default)]
+    categoria: CategoriaCliente,
+    #[serde(default)]
+    vencimiento_membresia: Option<Fecha>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Eq, Hash)]
+enum CategoriaCliente {
+    #[default]
+    General,
+    Estudiante,
+    Docente,
+}
+
+// Una politica por genero restringe a la de la categoria del cliente: se toma el menor valor
+//de cada campo y max_prestamos limita los prestamos simultaneos de ese genero
+#[derive(Debug, Clone, PartialEq)]
+struct PoliticaPrestamo {
+    dias_prestamo: u32,
+    max_prestamos: u32,
+    max_renovaciones: u32,
 }
 
 impl Biblioteca {
@@ -53,14 +143,32 @@ impl Biblioteca {
         direccion: String,
         path_copias: String,
         path_prestamos: String,
+        path_clientes: String,
+        path_indice: String,
+        path_reservas: String,
     ) -> Biblioteca {
         Biblioteca {
             nombre,
             direccion,
             copias: HashMap::new(),
             prestamos: Vec::new(),
+            reservas: HashMap::new(),
+            politicas_categoria: HashMap::from([
+                (CategoriaCliente::General, PoliticaPrestamo::new(14, 5, 2)),
+                (
+                    CategoriaCliente::Estudiante,
+                    PoliticaPrestamo::new(21, 5, 3),
+                ),
+                (CategoriaCliente::Docente, PoliticaPrestamo::new(30, 10, 3)),
+            ]),
+            politicas_genero: HashMap::new(),
+            indice: IndiceCatalogo::default(),
+            clientes: Vec::new(),
             path_copias,
             path_prestamos,
+            path_clientes,
+            path_indice,
+            path_reservas,
         }
     }
 
@@ -83,72 +191,477 @@ impl Biblioteca {
         Err(ErrorBiblioteca::Archivo)
     }
 
-    fn agregar_copia(&mut self, libro: Libro) -> Result<(), ErrorBiblioteca> {
-        self.copias.insert(libro, 0);
-
-        // serde_json::to_string falla para maps con keys que no son strings
+    // serde_json::to_string falla para maps con keys que no son strings
+    fn actualizar_archivo_copias(&self) -> Result<(), ErrorBiblioteca> {
         Self::actualizar_archivo(
             &self.path_copias,
             &self
                 .copias
                 .clone()
                 .into_iter()
-                .collect::<Vec<(Libro, u32)>>(),
+                .collect::<Vec<(Libro, Vec<Ejemplar>)>>(),
+        )
+    }
+
+    fn actualizar_archivo_reservas(&self) -> Result<(), ErrorBiblioteca> {
+        Self::actualizar_archivo(
+            &self.path_reservas,
+            &self
+                .reservas
+                .clone()
+                .into_iter()
+                .collect::<Vec<(Libro, VecDeque<Reserva>)>>(),
         )
     }
 
+    // Agrega el libro al catalogo sin descartar sus ejemplares, si ya existia
+    fn agregar_copia(&mut self, libro: Libro) -> Result<(), ErrorBiblioteca> {
+        self.indice.indexar(&libro);
+        self.copias.entry(libro).or_default();
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(Self::actualizar_archivo(&self.path_indice, &self.indice))
+    }
+
+    // Carga indice, copias, clientes, prestamos y reservas desde sus archivos. Los formatos previos
+    //(cantidad de copias por libro, isbn numericos, prestamos sin ejemplar y clientes embebidos en
+    //cada prestamo) se convierten y se vuelven a guardar. Los libros cargados se indexan igual,
+    //por si el indice no se guardo o es de un archivo previo. Los registros con un isbn que no
+    //puede migrarse se apartan sin interrumpir la carga
+    fn recuperar_archivos(&mut self) -> Result<(), ErrorBiblioteca> {
+        if let Some(buf) = Self::leer_archivo(&self.path_indice) {
+            let Ok(indice) = serde_json::from_str::<IndiceCatalogo>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            self.indice = indice;
+        }
+
+        if let Some(buf) = Self::leer_archivo(&self.path_copias) {
+            let Ok(registros) = serde_json::from_str::<Vec<serde_json::Value>>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            let mut rechazados = Vec::new();
+
+            for registro in registros {
+                if let Ok((libro, ejemplares)) =
+                    serde_json::from_value::<(Libro, Vec<Ejemplar>)>(registro.clone())
+                {
+                    self.indice.indexar(&libro);
+                    self.copias.insert(libro, ejemplares);
+                } else if let Ok((libro, cantidad)) =
+                    serde_json::from_value::<(Libro, u32)>(registro.clone())
+                {
+                    for _i in 0..cantidad {
+                        self.registrar_ejemplar(&libro, EstadoEjemplar::Disponible);
+                    }
+                    self.indice.indexar(&libro);
+                    self.copias.entry(libro).or_default();
+                } else if Self::isbn_invalido(registro.get(0)) {
+                    rechazados.push(registro);
+                } else {
+                    return Err(ErrorBiblioteca::Archivo);
+                }
+            }
+
+            Self::guardar_rechazados(&self.path_copias, rechazados)?;
+        }
+
+        if let Some(buf) = Self::leer_archivo(&self.path_clientes) {
+            let Ok(clientes) = serde_json::from_str::<Vec<Cliente>>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            self.clientes = clientes;
+        }
+
+        if let Some(buf) = Self::leer_archivo(&self.path_prestamos) {
+            let Ok(valores) = serde_json::from_str::<Vec<serde_json::Value>>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            let mut prestamos = Vec::new();
+            let mut rechazados = Vec::new();
+
+            for mut valor in valores {
+                if Self::isbn_invalido(valor.get("libro")) {
+                    rechazados.push(valor);
+                    continue;
+                }
+
+                // El cliente embebido se registra (o se reutiliza) y se reemplaza por su id
+                if let Some(cliente) = valor.as_object_mut().and_then(|p| p.remove("cliente")) {
+                    let Ok(cliente) = serde_json::from_value::<Cliente>(cliente) else {
+                        return Err(ErrorBiblioteca::Archivo);
+                    };
+
+                    valor["id_cliente"] = self.migrar_cliente(cliente).into();
+                }
+
+                let Ok(prestamo) = serde_json::from_value::<Prestamo>(valor) else {
+                    return Err(ErrorBiblioteca::Archivo);
+                };
+
+                prestamos.push(prestamo);
+            }
+
+            Self::guardar_rechazados(&self.path_prestamos, rechazados)?;
+
+            // Los prestamos activos se asocian a un nuevo ejemplar prestado
+            for p in prestamos.iter_mut() {
+                if p.codigo_ejemplar.is_empty() && !p.fue_devuelto {
+                    p.codigo_ejemplar = self.registrar_ejemplar(&p.libro, EstadoEjemplar::Prestado);
+                }
+            }
+
+            self.prestamos = prestamos;
+        }
+
+        if let Some(buf) = Self::leer_archivo(&self.path_reservas) {
+            let Ok(reservas) = serde_json::from_str::<Vec<(Libro, VecDeque<Reserva>)>>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            self.reservas = reservas.into_iter().collect();
+        }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        let resultado_prestamos = Self::actualizar_archivo(&self.path_prestamos, &self.prestamos);
+        let resultado_clientes = Self::actualizar_archivo(&self.path_clientes, &self.clientes);
+        let resultado_reservas = self.actualizar_archivo_reservas();
+
+        resultado_copias
+            .and(resultado_prestamos)
+            .and(resultado_clientes)
+            .and(resultado_reservas)
+            .and(Self::actualizar_archivo(&self.path_indice, &self.indice))
+    }
+
+    fn isbn_invalido(libro: Option<&serde_json::Value>) -> bool {
+        libro
+            .and_then(|l| l.get("isbn"))
+            .is_some_and(|isbn| serde_json::from_value::<Isbn>(isbn.clone()).is_err())
+    }
+
+    // Los registros que no pudieron migrarse se guardan sin modificar junto al archivo original,
+    //sumandose a los apartados en cargas anteriores
+    fn guardar_rechazados(
+        path: &String,
+        mut rechazados: Vec<serde_json::Value>,
+    ) -> Result<(), ErrorBiblioteca> {
+        if rechazados.is_empty() {
+            return Ok(());
+        }
+
+        let path = Self::path_rechazados(path);
+
+        if let Some(buf) = Self::leer_archivo(&path) {
+            let Ok(previos) = serde_json::from_str::<Vec<serde_json::Value>>(&buf) else {
+                return Err(ErrorBiblioteca::Archivo);
+            };
+
+            rechazados.splice(0..0, previos);
+        }
+
+        Self::actualizar_archivo(&path, &rechazados)
+    }
+
+    fn path_rechazados(path: &String) -> String {
+        Path::new(path)
+            .with_extension("rechazados.json")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    // Los clientes de archivos previos se identifican por todos sus datos y no se validan
+    fn migrar_cliente(&mut self, mut cliente: Cliente) -> u32 {
+        if let Some(c) = self.clientes.iter().find(|c| {
+            c.nombre == cliente.nombre && c.telefono == cliente.telefono && c.email == cliente.email
+        }) {
+            return c.id;
+        }
+
+        cliente.id = self.generar_id_cliente();
+        self.clientes.push(cliente);
+
+        self.clientes.last().unwrap().id
+    }
+
+    fn generar_id_cliente(&self) -> u32 {
+        self.clientes.iter().map(|c| c.id).max().unwrap_or(0) + 1
+    }
+
+    // Valida los datos de contacto y asigna un id. El email no puede repetirse
+    fn registrar_cliente(&mut self, mut cliente: Cliente) -> Result<u32, ErrorBiblioteca> {
+        self.validar_contacto(None, &cliente.telefono, &cliente.email)?;
+
+        cliente.id = self.generar_id_cliente();
+        let id = cliente.id;
+        self.clientes.push(cliente);
+
+        Self::actualizar_archivo(&self.path_clientes, &self.clientes)?;
+
+        Ok(id)
+    }
+
+    fn validar_contacto(
+        &self,
+        id: Option<u32>,
+        telefono: &str,
+        email: &str,
+    ) -> Result<(), ErrorBiblioteca> {
+        if !Cliente::es_email_valido(email) {
+            return Err(ErrorBiblioteca::Email);
+        }
+
+        if !Cliente::es_telefono_valido(telefono) {
+            return Err(ErrorBiblioteca::Telefono);
+        }
+
+        if self
+            .clientes
+            .iter()
+            .any(|c| Some(c.id) != id && c.email.eq_ignore_ascii_case(email.trim()))
+        {
+            return Err(ErrorBiblioteca::Cliente);
+        }
+
+        Ok(())
+    }
+
+    fn get_cliente(&self, id: u32) -> Option<&Cliente> {
+        self.clientes.iter().find(|c| c.id == id)
+    }
+
+    // Los prestamos referencian al cliente por id, por lo que conservan los datos actualizados
+    fn modificar_cliente(
+        &mut self,
+        id: u32,
+        nombre: String,
+        telefono: String,
+        email: String,
+    ) -> Result<(), ErrorBiblioteca> {
+        if self.get_cliente(id).is_none() {
+            return Err(ErrorBiblioteca::Cliente);
+        }
+
+        self.validar_contacto(Some(id), &telefono, &email)?;
+
+        let cliente = self.clientes.iter_mut().find(|c| c.id == id).unwrap();
+        cliente.nombre = nombre;
+        cliente.telefono = telefono.trim().to_string();
+        cliente.email = email.trim().to_string();
+
+        Self::actualizar_archivo(&self.path_clientes, &self.clientes)
+    }
+
+    fn renovar_membresia(&mut self, id: u32, vencimiento: Fecha) -> Result<(), ErrorBiblioteca> {
+        if !vencimiento.es_fecha_valida() {
+            panic!("Fecha no valida");
+        }
+
+        let Some(cliente) = self.clientes.iter_mut().find(|c| c.id == id) else {
+            return Err(ErrorBiblioteca::Cliente);
+        };
+
+        cliente.vencimiento_membresia = Some(vencimiento);
+
+        Self::actualizar_archivo(&self.path_clientes, &self.clientes)
+    }
+
+    // Devuelve los clientes cuya membresia vence dentro de los proximos dias (o ya vencio)
+    fn membresias_a_vencer(&self, dias: u32, fecha_actual: &Fecha) -> Vec<&Cliente> {
+        let mut fecha = fecha_actual.clone();
+        fecha.sumar_dias(dias + 1);
+
+        self.clientes
+            .iter()
+            .filter(|c| {
+                c.vencimiento_membresia
+                    .as_ref()
+                    .is_some_and(|v| fecha.es_mayor(v))
+            })
+            .collect()
+    }
+
+    // Un archivo inexistente se considera vacio
+    fn leer_archivo(path: &String) -> Option<String> {
+        let mut f = File::open(path).ok()?;
+        let mut buf = String::new();
+        f.read_to_string(&mut buf).ok()?;
+
+        Some(buf)
+    }
+
+    // Agrega un ejemplar con codigo generado sin actualizar los archivos
+    fn registrar_ejemplar(&mut self, libro: &Libro, estado: EstadoEjemplar) -> String {
+        let mut ejemplar = Ejemplar::new(
+            self.generar_codigo_ejemplar(libro),
+            "Deposito".to_string(),
+            COSTO_REPOSICION,
+        );
+        ejemplar.estado = estado;
+
+        let codigo = ejemplar.codigo.clone();
+        self.indice.indexar(libro);
+        self.copias.entry(libro.clone()).or_default().push(ejemplar);
+
+        codigo
+    }
+
+    // Devuelve la pagina solicitada (comenzando en 1) de los libros ordenados por relevancia,
+    //junto con la cantidad total de resultados
+    fn buscar_catalogo(
+        &self,
+        consulta: &str,
+        pagina: usize,
+        por_pagina: usize,
+    ) -> (Vec<&Libro>, usize) {
+        let mut resultados: Vec<(&Libro, u32)> = self
+            .indice
+            .buscar(consulta)
+            .into_iter()
+            .filter_map(|(isbn, puntaje)| {
+                self.copias
+                    .keys()
+                    .find(|l| l.isbn == isbn)
+                    .map(|l| (l, puntaje))
+            })
+            .collect();
+
+        resultados.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.titulo.cmp(&b.0.titulo)));
+
+        let total = resultados.len();
+        let libros = resultados
+            .into_iter()
+            .skip(pagina.saturating_sub(1) * por_pagina)
+            .take(if pagina == 0 { 0 } else { por_pagina })
+            .map(|(l, _)| l)
+            .collect();
+
+        (libros, total)
+    }
+
+    // Cantidad de ejemplares disponibles para prestar
     fn obtener_cantidad_copias(&self, libro: &Libro) -> u32 {
-        match self.copias.get(&libro) {
-            Some(cant) => *cant,
+        match self.copias.get(libro) {
+            Some(ejemplares) => ejemplares
+                .iter()
+                .filter(|e| e.estado == EstadoEjemplar::Disponible)
+                .count() as u32,
             None => 0,
         }
     }
 
+    // Da de baja un ejemplar disponible
     fn decrementar_cantidad_copias(&mut self, libro: &Libro) -> Result<(), ErrorBiblioteca> {
-        match self.copias.get_mut(&libro) {
-            Some(cant) => {
-                if *cant > 0 {
-                    *cant -= 1;
-
-                    return Self::actualizar_archivo(
-                        &self.path_copias,
-                        &self
-                            .copias
-                            .clone()
-                            .into_iter()
-                            .collect::<Vec<(Libro, u32)>>(),
-                    );
-                }
+        if let Some(ejemplares) = self.copias.get_mut(libro) {
+            if let Some(index) = ejemplares
+                .iter()
+                .position(|e| e.estado == EstadoEjemplar::Disponible)
+            {
+                ejemplares.remove(index);
+
+                return self.actualizar_archivo_copias();
             }
-            None => (),
         }
 
         Err(ErrorBiblioteca::ModificarCantidadCopia)
     }
 
-    fn incrementar_cantidad_copias(&mut self, libro: &Libro) -> Result<(), ErrorBiblioteca> {
-        match self.copias.get_mut(&libro) {
-            Some(cant) => {
-                *cant += 1;
+    // Agrega un ejemplar con codigo generado a partir del isbn
+    fn incrementar_cantidad_copias(
+        &mut self,
+        libro: &Libro,
+        fecha_actual: &Fecha,
+    ) -> Result<(), ErrorBiblioteca> {
+        if !self.copias.contains_key(libro) {
+            return Err(ErrorBiblioteca::ModificarCantidadCopia);
+        }
 
-                return Self::actualizar_archivo(
-                    &self.path_copias,
-                    &self
-                        .copias
-                        .clone()
-                        .into_iter()
-                        .collect::<Vec<(Libro, u32)>>(),
-                );
-            }
-            None => Err(ErrorBiblioteca::ModificarCantidadCopia),
+        let ejemplar = Ejemplar::new(
+            self.generar_codigo_ejemplar(libro),
+            "Deposito".to_string(),
+            COSTO_REPOSICION,
+        );
+
+        self.agregar_ejemplar(libro, ejemplar, fecha_actual)
+    }
+
+    fn generar_codigo_ejemplar(&self, libro: &Libro) -> String {
+        let mut numero = self.copias.get(libro).map_or(0, |e| e.len()) + 1;
+
+        while self
+            .get_ejemplar(&format!("{}-{}", libro.isbn, numero))
+            .is_some()
+        {
+            numero += 1;
+        }
+
+        format!("{}-{}", libro.isbn, numero)
+    }
+
+    // Un ejemplar nuevo disponible pasa primero por las reservas pendientes del libro
+    fn agregar_ejemplar(
+        &mut self,
+        libro: &Libro,
+        ejemplar: Ejemplar,
+        fecha_actual: &Fecha,
+    ) -> Result<(), ErrorBiblioteca> {
+        if self.get_ejemplar(&ejemplar.codigo).is_some() {
+            return Err(ErrorBiblioteca::Ejemplar);
+        }
+
+        let Some(ejemplares) = self.copias.get_mut(libro) else {
+            return Err(ErrorBiblioteca::ModificarCantidadCopia);
+        };
+
+        let codigo = ejemplar.codigo.clone();
+        let disponible = ejemplar.estado == EstadoEjemplar::Disponible;
+        ejemplares.push(ejemplar);
+
+        if disponible {
+            self.liberar_ejemplar(libro, &codigo, fecha_actual);
         }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(self.actualizar_archivo_reservas())
+    }
+
+    fn get_ejemplar(&self, codigo: &str) -> Option<&Ejemplar> {
+        self.copias.values().flatten().find(|e| e.codigo == codigo)
+    }
+
+    fn get_ejemplar_mut(&mut self, codigo: &str) -> Option<&mut Ejemplar> {
+        self.copias
+            .values_mut()
+            .flatten()
+            .find(|e| e.codigo == codigo)
+    }
+
+    fn get_libro_ejemplar(&self, codigo: &str) -> Option<&Libro> {
+        self.copias
+            .iter()
+            .find(|(_, ejemplares)| ejemplares.iter().any(|e| e.codigo == codigo))
+            .map(|(libro, _)| libro)
     }
 
-    fn contar_prestamos_cliente(&self, cliente: &Cliente) -> u32 {
+    fn mover_ejemplar(&mut self, codigo: &str, ubicacion: String) -> Result<(), ErrorBiblioteca> {
+        let Some(ejemplar) = self.get_ejemplar_mut(codigo) else {
+            return Err(ErrorBiblioteca::Ejemplar);
+        };
+
+        ejemplar.ubicacion = ubicacion;
+
+        self.actualizar_archivo_copias()
+    }
+
+    fn contar_prestamos_cliente(&self, id_cliente: u32) -> u32 {
         let mut cantidad_prestamos = 0;
 
         for p in &self.prestamos {
-            if p.get_cliente().eq(cliente) && !p.fue_devuelto {
+            if p.get_id_cliente() == id_cliente && !p.fue_devuelto {
                 cantidad_prestamos += 1;
             }
         }
@@ -156,33 +669,121 @@ impl Biblioteca {
         cantidad_prestamos
     }
 
+    fn set_politica_categoria(&mut self, categoria: CategoriaCliente, politica: PoliticaPrestamo) {
+        self.politicas_categoria.insert(categoria, politica);
+    }
+
+    fn set_politica_genero(&mut self, genero: Generos, politica: PoliticaPrestamo) {
+        self.politicas_genero.insert(genero, politica);
+    }
+
+    fn get_politica(&self, libro: &Libro, cliente: &Cliente) -> PoliticaPrestamo {
+        let politica = self.politicas_categoria[&cliente.categoria].clone();
+
+        match self.politicas_genero.get(&libro.genero) {
+            Some(p) => PoliticaPrestamo::new(
+                politica.dias_prestamo.min(p.dias_prestamo),
+                politica.max_prestamos.min(p.max_prestamos),
+                politica.max_renovaciones.min(p.max_renovaciones),
+            ),
+            None => politica,
+        }
+    }
+
+    fn supera_limite_prestamos(&self, libro: &Libro, cliente: &Cliente) -> bool {
+        let max_categoria = self.politicas_categoria[&cliente.categoria].max_prestamos;
+
+        if self.contar_prestamos_cliente(cliente.id) >= max_categoria {
+            return true;
+        }
+
+        match self.politicas_genero.get(&libro.genero) {
+            Some(p) => {
+                let prestamos_genero = self
+                    .prestamos
+                    .iter()
+                    .filter(|pr| {
+                        pr.id_cliente == cliente.id
+                            && !pr.fue_devuelto
+                            && pr.libro.genero == libro.genero
+                    })
+                    .count() as u32;
+
+                prestamos_genero >= p.max_prestamos
+            }
+            None => false,
+        }
+    }
+
+    // La fecha de vencimiento se calcula a partir de la politica del cliente y del genero del libro
     fn realizar_prestamo(
         &mut self,
         libro: Libro,
-        cliente: Cliente,
-        fecha_vencimiento: Fecha,
+        id_cliente: u32,
+        fecha_actual: Fecha,
     ) -> Result<(), ErrorBiblioteca> {
-        if !fecha_vencimiento.es_fecha_valida() {
+        if !fecha_actual.es_fecha_valida() {
             panic!("Fecha no valida");
         }
 
-        if self.contar_prestamos_cliente(&cliente) <= 5 && self.obtener_cantidad_copias(&libro) >= 1
-        {
-            match self.decrementar_cantidad_copias(&libro) {
-                Ok(_) => (),
-                Err(e) => {
-                    if e == ErrorBiblioteca::ModificarCantidadCopia {
-                        panic!("{}", e)
-                    }
-                }
-            }
-            self.prestamos
-                .push(Prestamo::new(libro, cliente, fecha_vencimiento));
+        let Some(cliente) = self.get_cliente(id_cliente) else {
+            return Err(ErrorBiblioteca::Cliente);
+        };
+
+        if !cliente.membresia_vigente(&fecha_actual) {
+            return Err(ErrorBiblioteca::MembresiaVencida);
+        }
 
-            return Self::actualizar_archivo(&self.path_prestamos, &self.prestamos);
+        if self.multas_pendientes(id_cliente) > 0.0 {
+            return Err(ErrorBiblioteca::MultasPendientes);
         }
 
-        Err(ErrorBiblioteca::RealizarPrestamo)
+        if self.supera_limite_prestamos(&libro, cliente) {
+            return Err(ErrorBiblioteca::RealizarPrestamo);
+        }
+
+        let politica = self.get_politica(&libro, cliente);
+
+        // Si el cliente tiene un ejemplar asignado por reserva, retira ese mismo ejemplar
+        let codigo = match self.retirar_reserva(&libro, id_cliente) {
+            Some(codigo) => codigo,
+            None => {
+                let Some(ejemplar) = self.copias.get(&libro).and_then(|ejemplares| {
+                    ejemplares
+                        .iter()
+                        .find(|e| e.estado == EstadoEjemplar::Disponible)
+                }) else {
+                    return Err(ErrorBiblioteca::RealizarPrestamo);
+                };
+
+                ejemplar.codigo.clone()
+            }
+        };
+
+        let mut fecha_vencimiento = fecha_actual.clone();
+        fecha_vencimiento.sumar_dias(politica.dias_prestamo);
+
+        let mut prestamo = Prestamo::new(libro, codigo, id_cliente, fecha_vencimiento);
+        prestamo.fecha_prestamo = Some(fecha_actual);
+
+        self.cambiar_estado_ejemplar(&prestamo.codigo_ejemplar, EstadoEjemplar::Prestado);
+        self.prestamos.push(prestamo);
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        let resultado_reservas = self.actualizar_archivo_reservas();
+        resultado_copias
+            .and(resultado_reservas)
+            .and(Self::actualizar_archivo(
+                &self.path_prestamos,
+                &self.prestamos,
+            ))
+    }
+
+    fn cambiar_estado_ejemplar(&mut self, codigo: &str, estado: EstadoEjemplar) {
+        match self.get_ejemplar_mut(codigo) {
+            Some(ejemplar) => ejemplar.estado = estado,
+            None => panic!("{}", ErrorBiblioteca::Ejemplar),
+        }
     }
 
     fn prestamos_a_vencer(&self, dias: u32, fecha_actual: &Fecha) -> Vec<&Prestamo> {
@@ -191,7 +792,7 @@ impl Biblioteca {
         fecha.sumar_dias(dias + 1);
 
         for p in &self.prestamos {
-            if fecha.es_mayor(&p.fecha_vencimiento) {
+            if fecha.es_mayor(&p.fecha_vencimiento) && !p.fue_devuelto {
                 vec.push(p);
             }
         }
@@ -203,7 +804,7 @@ impl Biblioteca {
         let mut vec = Vec::new();
 
         for p in &self.prestamos {
-            if fecha_actual.es_mayor(&p.fecha_vencimiento) {
+            if fecha_actual.es_mayor(&p.fecha_vencimiento) && !p.fue_devuelto {
                 vec.push(p)
             }
         }
@@ -211,9 +812,9 @@ impl Biblioteca {
         vec
     }
 
-    fn buscar_prestamo(&self, libro: &Libro, cliente: &Cliente) -> Option<&Prestamo> {
+    fn buscar_prestamo(&self, libro: &Libro, id_cliente: u32) -> Option<&Prestamo> {
         for p in &self.prestamos {
-            if p.libro.isbn == libro.isbn && p.cliente.eq(cliente) {
+            if p.libro.isbn == libro.isbn && p.id_cliente == id_cliente {
                 return Some(p);
             }
         }
@@ -224,76 +825,481 @@ impl Biblioteca {
     fn devolver_libro(
         &mut self,
         libro: &Libro,
-        cliente: &Cliente,
+        id_cliente: u32,
         fecha_actual: Fecha,
     ) -> Result<(), ErrorBiblioteca> {
         if !fecha_actual.es_fecha_valida() {
             panic!("Fecha invalida");
         }
 
-        for p in &mut self.prestamos {
-            if p.cliente.eq(cliente) && p.libro.isbn == libro.isbn {
-                p.fecha_devolucion = Some(fecha_actual);
-                p.fue_devuelto = true;
+        let Some(p) = self.get_prestamo_activo_mut(libro, id_cliente) else {
+            return Err(ErrorBiblioteca::ModificarPrestamo);
+        };
 
-                match self.incrementar_cantidad_copias(libro) {
-                    Ok(_) => (),
-                    Err(e) => {
-                        if e == ErrorBiblioteca::ModificarCantidadCopia {
-                            panic!("{}", e);
-                        }
-                    }
-                }
+        p.registrar_devolucion(fecha_actual.clone());
+        let codigo = p.codigo_ejemplar.clone();
 
-                return Self::actualizar_archivo(&self.path_prestamos, &self.prestamos);
-            }
-        }
+        // El ejemplar devuelto se asigna al primer cliente en espera, si lo hay
+        self.liberar_ejemplar(libro, &codigo, &fecha_actual);
 
-        Err(ErrorBiblioteca::ModificarPrestamo)
+        let resultado_copias = self.actualizar_archivo_copias();
+        let resultado_reservas = self.actualizar_archivo_reservas();
+        resultado_copias
+            .and(resultado_reservas)
+            .and(Self::actualizar_archivo(
+                &self.path_prestamos,
+                &self.prestamos,
+            ))
     }
-}
 
-impl Clone for Libro {
-    fn clone(&self) -> Self {
-        Libro::new(
-            self.isbn,
-            self.titulo.clone(),
-            self.autor.clone(),
-            self.paginas,
-            self.genero.clone(),
-        )
+    // Ultimo prestamo del ejemplar
+    fn get_prestamo_mut(&mut self, codigo: &str) -> Option<&mut Prestamo> {
+        self.prestamos
+            .iter_mut()
+            .rev()
+            .find(|p| p.codigo_ejemplar == codigo)
     }
-}
 
-impl Libro {
-    fn new(isbn: u32, titulo: String, autor: String, paginas: u32, genero: Generos) -> Libro {
-        Libro {
-            isbn,
-            titulo,
-            autor,
-            paginas,
-            genero,
-        }
+    fn get_prestamo_activo_mut(&mut self, libro: &Libro, id_cliente: u32) -> Option<&mut Prestamo> {
+        self.prestamos
+            .iter_mut()
+            .find(|p| p.id_cliente == id_cliente && p.libro.isbn == libro.isbn && !p.fue_devuelto)
     }
-}
 
-impl Prestamo {
-    fn new(libro: Libro, cliente: Cliente, fecha_vencimiento: Fecha) -> Prestamo {
-        if !fecha_vencimiento.es_fecha_valida() {
-            panic!("Fecha no valida")
-        }
+    // No se renuevan prestamos vencidos, ni libros con clientes en espera, ni prestamos de
+    //clientes con la membresia vencida
+    fn renovar_prestamo(
+        &mut self,
+        libro: &Libro,
+        id_cliente: u32,
+        fecha_actual: &Fecha,
+    ) -> Result<(), ErrorBiblioteca> {
+        let Some(cliente) = self.get_cliente(id_cliente) else {
+            return Err(ErrorBiblioteca::Cliente);
+        };
 
-        Prestamo {
-            libro,
-            cliente,
-            fecha_vencimiento,
-            fecha_devolucion: None,
-            fue_devuelto: false,
+        if !cliente.membresia_vigente(fecha_actual) {
+            return Err(ErrorBiblioteca::MembresiaVencida);
+        }
+
+        let politica = self.get_politica(libro, cliente);
+
+        if self
+            .reservas
+            .get(libro)
+            .is_some_and(|cola| !cola.is_empty())
+        {
+            return Err(ErrorBiblioteca::Renovacion);
+        }
+
+        let Some(p) = self.get_prestamo_activo_mut(libro, id_cliente) else {
+            return Err(ErrorBiblioteca::ModificarPrestamo);
+        };
+
+        if p.renovaciones >= politica.max_renovaciones
+            || fecha_actual.es_mayor(&p.fecha_vencimiento)
+        {
+            return Err(ErrorBiblioteca::Renovacion);
+        }
+
+        p.fecha_vencimiento.sumar_dias(politica.dias_prestamo);
+        p.renovaciones += 1;
+
+        Self::actualizar_archivo(&self.path_prestamos, &self.prestamos)
+    }
+
+    fn liberar_ejemplar(&mut self, libro: &Libro, codigo: &str, fecha_actual: &Fecha) {
+        if self.asignar_reserva(libro, codigo, fecha_actual) {
+            self.cambiar_estado_ejemplar(codigo, EstadoEjemplar::Reservado);
+        } else {
+            self.cambiar_estado_ejemplar(codigo, EstadoEjemplar::Disponible);
+        }
+    }
+
+    // Cierra el prestamo y cobra la reposicion del ejemplar, ademas de la multa por atraso
+    fn reportar_perdida(
+        &mut self,
+        libro: &Libro,
+        id_cliente: u32,
+        fecha_actual: Fecha,
+    ) -> Result<f64, ErrorBiblioteca> {
+        let Some(p) = self.get_prestamo_activo_mut(libro, id_cliente) else {
+            return Err(ErrorBiblioteca::ModificarPrestamo);
+        };
+
+        p.registrar_devolucion(fecha_actual);
+        let codigo = p.codigo_ejemplar.clone();
+        let multa = p.multa;
+
+        let costo = self.get_ejemplar(&codigo).unwrap().costo_reposicion;
+        self.cambiar_estado_ejemplar(&codigo, EstadoEjemplar::Perdido);
+
+        if let Some(p) = self.get_prestamo_mut(&codigo) {
+            p.cargo_danio = costo;
+        }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(Self::actualizar_archivo(
+            &self.path_prestamos,
+            &self.prestamos,
+        ))?;
+
+        Ok(multa + costo)
+    }
+
+    // Cierra el prestamo de un ejemplar devuelto con danios y lo retira de circulacion. Ademas de
+    //la multa por atraso, se cobra la reposicion. Devuelve el monto de la reposicion
+    fn devolver_libro_danado(
+        &mut self,
+        libro: &Libro,
+        id_cliente: u32,
+        fecha_actual: Fecha,
+    ) -> Result<f64, ErrorBiblioteca> {
+        let Some(p) = self.get_prestamo_activo_mut(libro, id_cliente) else {
+            return Err(ErrorBiblioteca::ModificarPrestamo);
+        };
+
+        p.registrar_devolucion(fecha_actual);
+        let codigo = p.codigo_ejemplar.clone();
+
+        let costo = self.get_ejemplar(&codigo).unwrap().costo_reposicion;
+        self.cambiar_estado_ejemplar(&codigo, EstadoEjemplar::Danado);
+
+        if let Some(p) = self.get_prestamo_mut(&codigo) {
+            p.cargo_danio = costo;
+        }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(Self::actualizar_archivo(
+            &self.path_prestamos,
+            &self.prestamos,
+        ))?;
+
+        Ok(costo)
+    }
+
+    // Un ejemplar disponible se retira de circulacion. Como no puede saberse quien lo danio, no se
+    //cobra a ningun cliente
+    fn reportar_danio(&mut self, codigo: &str) -> Result<(), ErrorBiblioteca> {
+        match self.get_ejemplar(codigo) {
+            Some(e) if e.estado == EstadoEjemplar::Disponible => (),
+            _ => return Err(ErrorBiblioteca::Ejemplar),
+        }
+
+        self.cambiar_estado_ejemplar(codigo, EstadoEjemplar::Danado);
+
+        self.actualizar_archivo_copias()
+    }
+
+    fn reparar_ejemplar(
+        &mut self,
+        codigo: &str,
+        fecha_actual: &Fecha,
+    ) -> Result<(), ErrorBiblioteca> {
+        match self.get_ejemplar(codigo) {
+            Some(e) if e.estado == EstadoEjemplar::Danado => (),
+            _ => return Err(ErrorBiblioteca::Ejemplar),
+        }
+
+        let libro = self.get_libro_ejemplar(codigo).unwrap().clone();
+        self.liberar_ejemplar(&libro, codigo, fecha_actual);
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(self.actualizar_archivo_reservas())
+    }
+
+    fn multas_pendientes(&self, id_cliente: u32) -> f64 {
+        self.prestamos
+            .iter()
+            .filter(|p| p.id_cliente == id_cliente && !p.multa_pagada)
+            .map(|p| p.multa + p.cargo_danio)
+            .sum()
+    }
+
+    // Devuelve el monto abonado
+    fn pagar_multas(&mut self, id_cliente: u32) -> Result<f64, ErrorBiblioteca> {
+        let monto = self.multas_pendientes(id_cliente);
+
+        if monto == 0.0 {
+            return Ok(0.0);
+        }
+
+        self.prestamos
+            .iter_mut()
+            .filter(|p| p.id_cliente == id_cliente && p.multa + p.cargo_danio > 0.0)
+            .for_each(|p| p.multa_pagada = true);
+
+        Self::actualizar_archivo(&self.path_prestamos, &self.prestamos)?;
+
+        Ok(monto)
+    }
+
+    // Solo pueden reservarse libros del catalogo sin copias disponibles
+    fn reservar_libro(&mut self, libro: &Libro, id_cliente: u32) -> Result<(), ErrorBiblioteca> {
+        if self.get_cliente(id_cliente).is_none() {
+            return Err(ErrorBiblioteca::Cliente);
+        }
+
+        if self.multas_pendientes(id_cliente) > 0.0 {
+            return Err(ErrorBiblioteca::MultasPendientes);
+        }
+
+        if !self.copias.contains_key(libro)
+            || self.obtener_cantidad_copias(libro) > 0
+            || self.get_reserva(libro, id_cliente).is_some()
+        {
+            return Err(ErrorBiblioteca::Reserva);
+        }
+
+        self.reservas
+            .entry(libro.clone())
+            .or_default()
+            .push_back(Reserva::new(id_cliente));
+
+        self.actualizar_archivo_reservas()
+    }
+
+    fn get_reserva(&self, libro: &Libro, id_cliente: u32) -> Option<&Reserva> {
+        self.reservas
+            .get(libro)?
+            .iter()
+            .find(|r| r.id_cliente == id_cliente)
+    }
+
+    // Asigna el ejemplar al primer cliente en espera. Devuelve false si no habia ninguno
+    fn asignar_reserva(&mut self, libro: &Libro, codigo: &str, fecha_actual: &Fecha) -> bool {
+        let Some(cola) = self.reservas.get_mut(libro) else {
+            return false;
+        };
+
+        match cola.iter_mut().find(|r| r.fecha_limite.is_none()) {
+            Some(r) => {
+                let mut fecha_limite = fecha_actual.clone();
+                fecha_limite.sumar_dias(DIAS_RETIRO_RESERVA);
+                r.codigo_ejemplar = Some(codigo.to_string());
+                r.fecha_limite = Some(fecha_limite);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Devuelve el codigo del ejemplar asignado a la reserva retirada
+    fn retirar_reserva(&mut self, libro: &Libro, id_cliente: u32) -> Option<String> {
+        let cola = self.reservas.get_mut(libro)?;
+
+        let index = cola
+            .iter()
+            .position(|r| r.id_cliente == id_cliente && r.fecha_limite.is_some())?;
+
+        cola.remove(index)?.codigo_ejemplar
+    }
+
+    // Cancela las reservas asignadas que no se retiraron a tiempo y reasigna sus copias
+    fn vencer_reservas(&mut self, fecha_actual: &Fecha) -> Result<(), ErrorBiblioteca> {
+        let mut liberadas = Vec::new();
+
+        for (libro, cola) in self.reservas.iter_mut() {
+            cola.retain(|r| match (&r.fecha_limite, &r.codigo_ejemplar) {
+                (Some(limite), Some(codigo)) if fecha_actual.es_mayor(limite) => {
+                    liberadas.push((libro.clone(), codigo.clone()));
+                    false
+                }
+                _ => true,
+            });
+        }
+
+        if liberadas.is_empty() {
+            return Ok(());
+        }
+
+        for (libro, codigo) in liberadas {
+            self.liberar_ejemplar(&libro, &codigo, fecha_actual);
+        }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(self.actualizar_archivo_reservas())
+    }
+}
+
+impl Clone for Libro {
+    fn clone(&self) -> Self {
+        Libro::new(
+            self.isbn.clone(),
+            self.titulo.clone(),
+            self.autor.clone(),
+            self.paginas,
+            self.genero.clone(),
+        )
+    }
+}
+
+impl Libro {
+    fn new(isbn: Isbn, titulo: String, autor: String, paginas: u32, genero: Generos) -> Libro {
+        Libro {
+            isbn,
+            titulo,
+            autor,
+            paginas,
+            genero,
+        }
+    }
+}
+
+impl IndiceCatalogo {
+    // Pasa a minusculas, quita acentos y separa en palabras
+    fn normalizar(texto: &str) -> Vec<String> {
+        texto
+            .to_lowercase()
+            .chars()
+            .map(|c| match c {
+                'á' | 'à' | 'ä' | 'â' => 'a',
+                'é' | 'è' | 'ë' | 'ê' => 'e',
+                'í' | 'ì' | 'ï' | 'î' => 'i',
+                'ó' | 'ò' | 'ö' | 'ô' => 'o',
+                'ú' | 'ù' | 'ü' | 'û' => 'u',
+                'ñ' => 'n',
+                c if c.is_alphanumeric() => c,
+                _ => ' ',
+            })
+            .collect::<String>()
+            .split_whitespace()
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    fn indexar(&mut self, libro: &Libro) {
+        let campos = [
+            (libro.titulo.clone(), PESO_TITULO),
+            (libro.autor.clone(), PESO_AUTOR),
+            (format!("{:?}", libro.genero), PESO_GENERO),
+        ];
+
+        for (texto, peso) in campos {
+            for termino in Self::normalizar(&texto) {
+                let pesos = self.terminos.entry(termino).or_default();
+                let actual = pesos.entry(libro.isbn.clone()).or_insert(0);
+                *actual = (*actual).max(peso);
+            }
+        }
+    }
+
+    // Una palabra de la consulta coincide con un termino completo o con su prefijo (a mitad de
+    //puntaje). Los numeros, con o sin guiones, se comparan ademas como prefijo del isbn
+    fn buscar(&self, consulta: &str) -> HashMap<Isbn, u32> {
+        let mut puntajes: HashMap<Isbn, u32> = HashMap::new();
+
+        for palabra in Self::normalizar(consulta) {
+            let mut coincidencias: HashMap<Isbn, u32> = HashMap::new();
+
+            for (termino, pesos) in &self.terminos {
+                let factor = match termino {
+                    t if *t == palabra => 2,
+                    t if t.starts_with(&palabra) => 1,
+                    _ => continue,
+                };
+
+                for (isbn, peso) in pesos {
+                    let puntaje = coincidencias.entry(isbn.clone()).or_insert(0);
+                    *puntaje = (*puntaje).max(peso * factor);
+                }
+            }
+
+            Self::sumar_puntajes(&mut puntajes, coincidencias);
+        }
+
+        for palabra in consulta.split_whitespace() {
+            let prefijo = palabra.replace('-', "").to_uppercase();
+
+            if prefijo.is_empty() || !prefijo.chars().all(|c| c.is_ascii_digit() || c == 'X') {
+                continue;
+            }
+
+            let coincidencias: HashMap<Isbn, u32> = self
+                .terminos
+                .values()
+                .flat_map(|p| p.keys())
+                .filter(|isbn| {
+                    isbn.get_isbn13().starts_with(&prefijo)
+                        || isbn.get_isbn10().is_some_and(|i| i.starts_with(&prefijo))
+                })
+                .map(|isbn| (isbn.clone(), PESO_TITULO * 2))
+                .collect();
+
+            Self::sumar_puntajes(&mut puntajes, coincidencias);
+        }
+
+        puntajes
+    }
+
+    fn sumar_puntajes(puntajes: &mut HashMap<Isbn, u32>, coincidencias: HashMap<Isbn, u32>) {
+        for (isbn, puntaje) in coincidencias {
+            *puntajes.entry(isbn).or_insert(0) += puntaje;
+        }
+    }
+}
+
+impl Ejemplar {
+    fn new(codigo: String, ubicacion: String, costo_reposicion: f64) -> Ejemplar {
+        Ejemplar {
+            codigo,
+            estado: EstadoEjemplar::Disponible,
+            ubicacion,
+            costo_reposicion,
+        }
+    }
+}
+
+impl Prestamo {
+    fn new(
+        libro: Libro,
+        codigo_ejemplar: String,
+        id_cliente: u32,
+        fecha_vencimiento: Fecha,
+    ) -> Prestamo {
+        if !fecha_vencimiento.es_fecha_valida() {
+            panic!("Fecha no valida")
+        }
+
+        Prestamo {
+            libro,
+            codigo_ejemplar,
+            id_cliente,
+            fecha_prestamo: None,
+            fecha_vencimiento,
+            fecha_devolucion: None,
+            fue_devuelto: false,
+            renovaciones: 0,
+            multa: 0.0,
+            cargo_danio: 0.0,
+            multa_pagada: false,
         }
     }
 
-    fn get_cliente(&self) -> &Cliente {
-        &self.cliente
+    fn get_id_cliente(&self) -> u32 {
+        self.id_cliente
+    }
+
+    // Se cobra una multa por cada dia de atraso respecto a la fecha de vencimiento
+    fn registrar_devolucion(&mut self, fecha_devolucion: Fecha) {
+        let dias_atraso = self.fecha_vencimiento.dias_hasta(&fecha_devolucion);
+
+        if dias_atraso > 0 {
+            self.multa = dias_atraso as f64 * MULTA_DIARIA;
+        }
+
+        self.fecha_devolucion = Some(fecha_devolucion);
+        self.fue_devuelto = true;
+    }
+}
+
+impl Reserva {
+    fn new(id_cliente: u32) -> Reserva {
+        Reserva {
+            id_cliente,
+            codigo_ejemplar: None,
+            fecha_limite: None,
+        }
     }
 }
 
@@ -302,25 +1308,96 @@ trait ElementoBiblioteca {}
 
 impl ElementoBiblioteca for Prestamo {}
 impl ElementoBiblioteca for Libro {}
-impl ElementoBiblioteca for Vec<(Libro, u32)> {}
+impl ElementoBiblioteca for Vec<(Libro, Vec<Ejemplar>)> {}
 impl ElementoBiblioteca for Vec<Prestamo> {}
+impl ElementoBiblioteca for Vec<Cliente> {}
+impl ElementoBiblioteca for IndiceCatalogo {}
+impl ElementoBiblioteca for Vec<serde_json::Value> {}
+impl ElementoBiblioteca for Vec<(Libro, VecDeque<Reserva>)> {}
 
 impl Clone for Cliente {
     fn clone(&self) -> Self {
-        Cliente::new(
-            self.nombre.clone(),
-            self.telefono.clone(),
-            self.email.clone(),
-        )
+        Cliente {
+            id: self.id,
+            nombre: self.nombre.clone(),
+            telefono: self.telefono.clone(),
+            email: self.email.clone(),
+            categoria: self.categoria.clone(),
+            vencimiento_membresia: self.vencimiento_membresia.clone(),
+        }
     }
 }
 
 impl Cliente {
     fn new(nombre: String, telefono: String, email: String) -> Cliente {
         Cliente {
+            id: 0,
             nombre,
-            telefono,
-            email,
+            telefono: telefono.trim().to_string(),
+            email: email.trim().to_string(),
+            categoria: CategoriaCliente::General,
+            vencimiento_membresia: None,
+        }
+    }
+
+    fn set_categoria(&mut self, categoria: CategoriaCliente) {
+        self.categoria = categoria;
+    }
+
+    fn set_vencimiento_membresia(&mut self, vencimiento: Fecha) {
+        self.vencimiento_membresia = Some(vencimiento);
+    }
+
+    fn membresia_vigente(&self, fecha_actual: &Fecha) -> bool {
+        match &self.vencimiento_membresia {
+            Some(vencimiento) => !fecha_actual.es_mayor(vencimiento),
+            None => true,
+        }
+    }
+
+    // Una sola arroba, con usuario y un dominio con al menos un punto
+    fn es_email_valido(email: &str) -> bool {
+        let Some((usuario, dominio)) = email.trim().split_once('@') else {
+            return false;
+        };
+
+        let usuario_valido = !usuario.is_empty()
+            && usuario
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._%+-".contains(c));
+
+        let etiquetas: Vec<&str> = dominio.split('.').collect();
+        let dominio_valido = etiquetas.len() > 1
+            && etiquetas
+                .iter()
+                .all(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'));
+
+        usuario_valido && dominio_valido
+    }
+
+    // Admite un + inicial, espacios, guiones y parentesis. Debe tener entre 8 y 15 digitos
+    fn es_telefono_valido(telefono: &str) -> bool {
+        let telefono = telefono.trim();
+        let telefono = telefono.strip_prefix('+').unwrap_or(telefono);
+
+        if !telefono
+            .chars()
+            .all(|c| c.is_ascii_digit() || " -()".contains(c))
+        {
+            return false;
+        }
+
+        let digitos = telefono.chars().filter(|c| c.is_ascii_digit()).count();
+        (8..=15).contains(&digitos)
+    }
+}
+
+impl PoliticaPrestamo {
+    fn new(dias_prestamo: u32, max_prestamos: u32, max_renovaciones: u32) -> PoliticaPrestamo {
+        PoliticaPrestamo {
+            dias_prestamo,
+            max_prestamos,
+            max_renovaciones,
         }
     }
 }
@@ -331,6 +1408,14 @@ enum ErrorBiblioteca {
     ModificarCantidadCopia,
     RealizarPrestamo,
     ModificarPrestamo,
+    MultasPendientes,
+    Reserva,
+    Ejemplar,
+    Renovacion,
+    Cliente,
+    Email,
+    Telefono,
+    MembresiaVencida,
 }
 
 impl Display for ErrorBiblioteca {
@@ -347,6 +1432,18 @@ impl Display for ErrorBiblioteca {
             ErrorBiblioteca::ModificarPrestamo => {
                 write!(f, "Error al intentar actualizar el estado del prestamo")
             }
+            ErrorBiblioteca::MultasPendientes => {
+                write!(f, "El cliente posee multas sin abonar")
+            }
+            ErrorBiblioteca::Reserva => write!(f, "Error al intentar reservar el libro"),
+            ErrorBiblioteca::Ejemplar => write!(f, "Error al intentar operar con el ejemplar"),
+            ErrorBiblioteca::Renovacion => write!(f, "Error al intentar renovar el prestamo"),
+            ErrorBiblioteca::Cliente => write!(f, "Error al intentar operar con el cliente"),
+            ErrorBiblioteca::Email => write!(f, "El email del cliente no es valido"),
+            ErrorBiblioteca::Telefono => write!(f, "El telefono del cliente no es valido"),
+            ErrorBiblioteca::MembresiaVencida => {
+                write!(f, "La membresia del cliente se encuentra vencida")
+            }
         }
     }
 }
@@ -363,29 +1460,43 @@ mod test {
 
     use super::*;
 
+    // Los contextos sin archivos no pueden guardar el registro, pero el cliente queda agregado
+    fn registrar_cliente(biblioteca: &mut Biblioteca, cliente: Cliente) -> u32 {
+        match biblioteca.registrar_cliente(cliente) {
+            Ok(id) => id,
+            Err(e) => {
+                assert_eq!(e, ErrorBiblioteca::Archivo);
+                biblioteca.clientes.last().unwrap().id
+            }
+        }
+    }
+
     fn creacion_contexto() -> (Biblioteca, Vec<Libro>) {
         let mut biblioteca = Biblioteca::new(
             "Biblioteca UNLP".to_string(),
             "Direccion".to_string(),
             Default::default(),
             Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
         );
         let l1 = Libro::new(
-            123456,
+            Isbn::new("978-0-452-28423-4").unwrap(),
             "1984".to_string(),
             "Orwell".to_string(),
             320,
             Generos::Novela,
         );
         let l2 = Libro::new(
-            78910,
+            Isbn::new("1-4215-2772-3").unwrap(),
             "Battle Royale".to_string(),
             "Koushun Takami".to_string(),
             550,
             Generos::Otros,
         );
         let l3 = Libro::new(
-            111213,
+            Isbn::new("978-0-547-92822-7").unwrap(),
             "El Hobbit".to_string(),
             "Tolkien".to_string(),
             255,
@@ -398,13 +1509,20 @@ mod test {
         biblioteca.agregar_copia(l3.clone()).expect_err("");
 
         // Incremento su stock. l1 = 10, l2 = 5, l3 = 3
+        let fecha_alta = Fecha::new(1, 12, 2023);
         for i in 1..11 {
-            biblioteca.incrementar_cantidad_copias(&l1).expect_err("");
+            biblioteca
+                .incrementar_cantidad_copias(&l1, &fecha_alta)
+                .expect_err("");
             if i % 2 == 0 {
-                biblioteca.incrementar_cantidad_copias(&l2).expect_err("");
+                biblioteca
+                    .incrementar_cantidad_copias(&l2, &fecha_alta)
+                    .expect_err("");
             }
             if i % 3 == 0 {
-                biblioteca.incrementar_cantidad_copias(&l3).expect_err("");
+                biblioteca
+                    .incrementar_cantidad_copias(&l3, &fecha_alta)
+                    .expect_err("");
             }
         }
 
@@ -418,18 +1536,24 @@ mod test {
             "Direccion".to_string(),
             Default::default(),
             Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
         );
         let l1 = Libro::new(
-            123456,
+            Isbn::new("978-0-452-28423-4").unwrap(),
             "1984".to_string(),
             "Orwell".to_string(),
             320,
             Generos::Novela,
         );
-        let c1 = Cliente::new(
-            "Juan".to_string(),
-            "01164829421".to_string(),
-            "example@gmail.com".to_string(),
+        let c1 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Juan".to_string(),
+                "01164829421".to_string(),
+                "example@gmail.com".to_string(),
+            ),
         );
         let fecha_actual = Fecha::new(5, 5, 2024);
 
@@ -441,19 +1565,19 @@ mod test {
         );
 
         assert_eq!(
-            biblioteca.incrementar_cantidad_copias(&l1),
+            biblioteca.incrementar_cantidad_copias(&l1, &fecha_actual),
             Err(ErrorBiblioteca::ModificarCantidadCopia)
         );
-        assert_eq!(biblioteca.contar_prestamos_cliente(&c1), 0);
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 0);
 
         assert_eq!(biblioteca.prestamos_a_vencer(10, &fecha_actual).len(), 0);
 
         assert_eq!(biblioteca.prestamos_vencidos(&fecha_actual).len(), 0);
 
-        assert!(biblioteca.buscar_prestamo(&l1, &c1).is_none());
+        assert!(biblioteca.buscar_prestamo(&l1, c1).is_none());
 
         assert_eq!(
-            biblioteca.devolver_libro(&l1, &c1, fecha_actual),
+            biblioteca.devolver_libro(&l1, c1, fecha_actual),
             Err(ErrorBiblioteca::ModificarPrestamo)
         );
     }
@@ -465,33 +1589,39 @@ mod test {
             "Direccion".to_string(),
             Default::default(),
             Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
         );
         let l1 = Libro::new(
-            123456,
+            Isbn::new("978-0-452-28423-4").unwrap(),
             "1984".to_string(),
             "Orwell".to_string(),
             320,
             Generos::Novela,
         );
         let l2 = Libro::new(
-            78910,
+            Isbn::new("1-4215-2772-3").unwrap(),
             "Battle Royale".to_string(),
             "Koushun Takami".to_string(),
             550,
             Generos::Otros,
         );
         let l3 = Libro::new(
-            111213,
+            Isbn::new("978-0-547-92822-7").unwrap(),
             "El Hobbit".to_string(),
             "Tolkien".to_string(),
             255,
             Generos::Otros,
         );
 
-        let c1 = Cliente::new(
-            "Juan".to_string(),
-            "01164829421".to_string(),
-            "example@gmail.com".to_string(),
+        let c1 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Juan".to_string(),
+                "01164829421".to_string(),
+                "example@gmail.com".to_string(),
+            ),
         );
         let fecha_actual = Fecha::new(5, 5, 2024);
 
@@ -502,26 +1632,32 @@ mod test {
 
         // Incremento su stock. l1 = 10, l2 = 5, l3 = 3
         for i in 1..11 {
-            biblioteca.incrementar_cantidad_copias(&l1).expect_err("");
+            biblioteca
+                .incrementar_cantidad_copias(&l1, &fecha_actual)
+                .expect_err("");
             if i % 2 == 0 {
-                biblioteca.incrementar_cantidad_copias(&l2).expect_err("");
+                biblioteca
+                    .incrementar_cantidad_copias(&l2, &fecha_actual)
+                    .expect_err("");
             }
             if i % 3 == 0 {
-                biblioteca.incrementar_cantidad_copias(&l3).expect_err("");
+                biblioteca
+                    .incrementar_cantidad_copias(&l3, &fecha_actual)
+                    .expect_err("");
             }
         }
 
         // Chequeo que la cantidad se haya asignado correctamente
-        assert_eq!(*biblioteca.copias.get(&l1).unwrap(), 10);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 10);
 
         // Realiza un prestamo y chequea que la cantidad de copias hayan disminuido
         biblioteca
-            .realizar_prestamo(l3.clone(), c1.clone(), Fecha::new(8, 5, 2024))
+            .realizar_prestamo(l3.clone(), c1, Fecha::new(24, 4, 2024))
             .expect_err("");
-        assert_eq!(*biblioteca.copias.get(&l3).unwrap(), 2);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 2);
 
         // Cuento cantidad de prestamos de cliente c1
-        assert_eq!(biblioteca.contar_prestamos_cliente(&c1), 1);
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 1);
 
         // Chequeo si el prestamo esta proximo a vencer (con un lapso de 3 dias y luego de 1)
         let lista_prestamos_a_vencer = biblioteca.prestamos_a_vencer(3, &fecha_actual);
@@ -538,34 +1674,32 @@ mod test {
                 .prestamos_vencidos(&Fecha::new(9, 5, 2024))
                 .first()
                 .unwrap()
-                .cliente
-                .nombre,
-            "Juan"
+                .id_cliente,
+            c1
         );
 
         biblioteca
-            .realizar_prestamo(l1.clone(), c1.clone(), Fecha::new(19, 6, 2024))
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(5, 6, 2024))
             .expect_err("");
 
         // Busco prestamo. Primero uno existente, despues un libro no prestado, luego un libro prestado pero con cliente incorrecto
         assert_eq!(
-            biblioteca.buscar_prestamo(&l1, &c1).unwrap().libro.titulo,
+            biblioteca.buscar_prestamo(&l1, c1).unwrap().libro.titulo,
             "1984"
         );
-        assert!((biblioteca.buscar_prestamo(&l2, &c1)).is_none());
-        assert!(biblioteca
-            .buscar_prestamo(
-                &l1,
-                &Cliente::new(
-                    "Pedro".to_string(),
-                    "221843732".to_string(),
-                    "text@yahoo.com".to_string()
-                )
-            )
-            .is_none());
+        assert!((biblioteca.buscar_prestamo(&l2, c1)).is_none());
+        let c2 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Pedro".to_string(),
+                "221843732".to_string(),
+                "text@yahoo.com".to_string(),
+            ),
+        );
+        assert!(biblioteca.buscar_prestamo(&l1, c2).is_none());
 
         // Devuelvo libro y verifico que haya actualizado vector de prestamos
-        match biblioteca.devolver_libro(&l1, &c1, fecha_actual) {
+        match biblioteca.devolver_libro(&l1, c1, fecha_actual) {
             Ok(_) => (),
             Err(e) => println!("{}", e),
         }
@@ -591,7 +1725,7 @@ mod test {
     #[test]
     fn test_estructuras_secundarias() {
         let libro1 = Libro {
-            isbn: 12345,
+            isbn: Isbn::new("0-8044-2957-X").unwrap(),
             titulo: "example".to_string(),
             autor: "autor".to_string(),
             paginas: 77,
@@ -603,9 +1737,16 @@ mod test {
         assert_eq!(libro1, libro2);
 
         let cliente = Cliente::new("Name".to_string(), "Phone".to_string(), "Email".to_string());
-        let prestamo = Prestamo::new(libro1, cliente, Fecha::new(1, 1, 2020));
+        assert_eq!(cliente.clone(), cliente);
+
+        let prestamo = Prestamo::new(
+            libro1,
+            "9780804429573-1".to_string(),
+            7,
+            Fecha::new(1, 1, 2020),
+        );
 
-        assert_eq!(prestamo.get_cliente().nombre, "Name");
+        assert_eq!(prestamo.get_id_cliente(), 7);
     }
 
     fn abrir_archivo<'de, T>(path: &String) -> Result<T, ErrorBiblioteca>
@@ -638,44 +1779,55 @@ mod test {
         let l3 = libros.last().unwrap().clone();
 
         biblioteca.path_copias = "test_files/copias1.json".to_string();
+        biblioteca.path_indice = "test_files/indice1.json".to_string();
+        biblioteca.path_reservas = "test_files/reservas3.json".to_string();
 
         // Corroboro archivo previo
 
-        if let Ok(copias) = abrir_archivo::<Vec<(Libro, u32)>>(&biblioteca.path_copias) {
-            let suma_copias = copias.iter().map(|l| l.1).sum::<u32>();
-            assert_eq!(suma_copias, 15);
+        if let Ok(copias) = abrir_archivo::<Vec<(Libro, Vec<Ejemplar>)>>(&biblioteca.path_copias) {
+            let suma_copias = copias
+                .iter()
+                .flat_map(|l| l.1.iter())
+                .filter(|e| e.estado == EstadoEjemplar::Disponible)
+                .count();
+            assert_eq!(suma_copias, 33);
         }
 
-        // Agrego 3 libros
+        // Agrego 3 libros (no se descartan los ejemplares existentes)
 
         assert!(biblioteca.agregar_copia(l1.clone()).is_ok());
         assert!(biblioteca.agregar_copia(l2.clone()).is_ok());
         assert!(biblioteca.agregar_copia(l3.clone()).is_ok());
 
-        // Incremento su stock. l1 = 10, l2 = 5, l3 = 3
+        // Incremento su stock. l1 = 20, l2 = 10, l3 = 6
 
+        let fecha_alta = Fecha::new(1, 12, 2023);
         for i in 1..11 {
             biblioteca
-                .incrementar_cantidad_copias(&l1)
+                .incrementar_cantidad_copias(&l1, &fecha_alta)
                 .expect("No se pudo incrementar");
             if i % 2 == 0 {
                 biblioteca
-                    .incrementar_cantidad_copias(&l2)
+                    .incrementar_cantidad_copias(&l2, &fecha_alta)
                     .expect("No se pudo incrementar");
             }
             if i % 3 == 0 {
                 biblioteca
-                    .incrementar_cantidad_copias(&l3)
+                    .incrementar_cantidad_copias(&l3, &fecha_alta)
                     .expect("No se pudo incrementar");
             }
         }
 
         // Abro el archivo generado para corroborar informacion
 
-        if let Ok(copias) = abrir_archivo::<Vec<(Libro, u32)>>(&biblioteca.path_copias) {
+        if let Ok(copias) = abrir_archivo::<Vec<(Libro, Vec<Ejemplar>)>>(&biblioteca.path_copias) {
             assert_eq!(copias.len(), 3); // Chequeo cantidad de libros
-            let suma_copias = copias.iter().map(|l| l.1).sum::<u32>();
-            assert_eq!(suma_copias, 18);
+            let suma_copias = copias
+                .iter()
+                .flat_map(|l| l.1.iter())
+                .filter(|e| e.estado == EstadoEjemplar::Disponible)
+                .count();
+            assert_eq!(suma_copias, 36);
         }
 
         // Decremento cantidad de copias
@@ -695,19 +1847,27 @@ mod test {
         let l2 = libros.get(1).unwrap().clone();
         let l3 = libros.last().unwrap().clone();
 
-        let c1 = Cliente::new(
-            "Nahuel".to_string(),
-            "2218570392".to_string(),
-            "example@gmail.com".to_string(),
+        let c1 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Nahuel".to_string(),
+                "2218570392".to_string(),
+                "example@gmail.com".to_string(),
+            ),
         );
-        let c2 = Cliente::new(
-            "Pedro".to_string(),
-            "2212604821".to_string(),
-            "test@hotmail.com".to_string(),
+        let c2 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Pedro".to_string(),
+                "2212604821".to_string(),
+                "test@hotmail.com".to_string(),
+            ),
         );
 
         biblioteca.path_copias = "test_files/copias2.json".to_string();
+        biblioteca.path_indice = "test_files/indice2.json".to_string();
         biblioteca.path_prestamos = "test_files/prestamos1.json".to_string();
+        biblioteca.path_reservas = "test_files/reservas2.json".to_string();
 
         // Corroboro archivos previo
 
@@ -716,9 +1876,13 @@ mod test {
             assert!(prestamos.get(1).unwrap().fecha_devolucion.is_some());
         }
 
-        if let Ok(copias) = abrir_archivo::<Vec<(Libro, u32)>>(&biblioteca.path_copias) {
-            let suma_copias = copias.iter().map(|l| l.1).sum::<u32>();
-            assert_eq!(suma_copias, 16); // l3 se presto 2 veces sin devolverse al final
+        if let Ok(copias) = abrir_archivo::<Vec<(Libro, Vec<Ejemplar>)>>(&biblioteca.path_copias) {
+            let suma_copias = copias
+                .iter()
+                .flat_map(|l| l.1.iter())
+                .filter(|e| e.estado == EstadoEjemplar::Disponible)
+                .count();
+            assert_eq!(suma_copias, 34); // l3 se presto 2 veces sin devolverse al final
         }
 
         // Agrego 3 libros
@@ -727,20 +1891,21 @@ mod test {
         biblioteca.agregar_copia(l2.clone()).expect("");
         biblioteca.agregar_copia(l3.clone()).expect("");
 
-        // Incremento su stock. l1 = 10, l2 = 5, l3 = 3
+        // Incremento su stock. l1 = 20, l2 = 10, l3 = 6
 
+        let fecha_alta = Fecha::new(1, 12, 2023);
         for i in 1..11 {
             biblioteca
-                .incrementar_cantidad_copias(&l1)
+                .incrementar_cantidad_copias(&l1, &fecha_alta)
                 .expect("No se pudo incrementar");
             if i % 2 == 0 {
                 biblioteca
-                    .incrementar_cantidad_copias(&l2)
+                    .incrementar_cantidad_copias(&l2, &fecha_alta)
                     .expect("No se pudo incrementar");
             }
             if i % 3 == 0 {
                 biblioteca
-                    .incrementar_cantidad_copias(&l3)
+                    .incrementar_cantidad_copias(&l3, &fecha_alta)
                     .expect("No se pudo incrementar");
             }
         }
@@ -748,28 +1913,28 @@ mod test {
         // Se realizan 3 prestamos
 
         assert!(biblioteca
-            .realizar_prestamo(l3.clone(), c1.clone(), Fecha::new(10, 1, 2024))
+            .realizar_prestamo(l3.clone(), c1, Fecha::new(27, 12, 2023))
             .is_ok());
 
         assert!(biblioteca
-            .realizar_prestamo(l2.clone(), c1.clone(), Fecha::new(15, 1, 2024))
+            .realizar_prestamo(l2.clone(), c1, Fecha::new(1, 1, 2024))
             .is_ok());
 
         assert!(biblioteca
-            .realizar_prestamo(l3.clone(), c2.clone(), Fecha::new(25, 1, 2024))
+            .realizar_prestamo(l3.clone(), c2, Fecha::new(11, 1, 2024))
             .is_ok());
 
         // Corrobora cantidad de prestamos en el archivo
 
         if let Ok(prestamos) = abrir_archivo::<Vec<Prestamo>>(&biblioteca.path_copias) {
             assert_eq!(prestamos.len(), 3);
-            assert_eq!(prestamos.first().unwrap().cliente.nombre, "Nahuel");
+            assert_eq!(prestamos.first().unwrap().id_cliente, c1);
         }
 
         // Devuelve un libro
 
         assert!(biblioteca
-            .devolver_libro(&l2, &c1, Fecha::new(30, 5, 2024))
+            .devolver_libro(&l2, c1, Fecha::new(30, 5, 2024))
             .is_ok());
     }
 
@@ -780,32 +1945,40 @@ mod test {
             "520".to_string(),
             "/".to_string(),
             "/".to_string(),
+            "/".to_string(),
+            "/".to_string(),
+            "/".to_string(),
         );
 
         let l = Libro::new(
-            111213,
+            Isbn::new("978-0-547-92822-7").unwrap(),
             "El Hobbit".to_string(),
             "Tolkien".to_string(),
             255,
             Generos::Otros,
         );
 
-        let c = Cliente::new(
-            "Nahuel".to_string(),
-            "2218570392".to_string(),
-            "example@gmail.com".to_string(),
+        let c = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Nahuel".to_string(),
+                "2218570392".to_string(),
+                "example@gmail.com".to_string(),
+            ),
         );
 
         // Fuerzo error de incapacidad para modificar cantidad de copias
 
-        let e = biblioteca.incrementar_cantidad_copias(&l).unwrap_err();
+        let e = biblioteca
+            .incrementar_cantidad_copias(&l, &Fecha::new(1, 12, 2023))
+            .unwrap_err();
         assert_eq!(e, ErrorBiblioteca::ModificarCantidadCopia);
         println!("{}", e);
 
         // Fuerzo error de incapacidad de realizar prestamo
 
         let e = biblioteca
-            .realizar_prestamo(l.clone(), c.clone(), Fecha::new(1, 1, 2024))
+            .realizar_prestamo(l.clone(), c, Fecha::new(18, 12, 2023))
             .unwrap_err();
         assert_eq!(e, ErrorBiblioteca::RealizarPrestamo);
         println!("{}", e);
@@ -813,9 +1986,1060 @@ mod test {
         // Fuerzo error de incapacidad de actualizar prestamo
 
         let e = biblioteca
-            .devolver_libro(&l, &c, Fecha::new(31, 5, 2024))
+            .devolver_libro(&l, c, Fecha::new(31, 5, 2024))
             .unwrap_err();
         assert_eq!(e, ErrorBiblioteca::ModificarPrestamo);
         println!("{}", e);
     }
+
+    #[test]
+    fn test_multas() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l1 = libros.first().unwrap().clone();
+        let l2 = libros.get(1).unwrap().clone();
+
+        let c1 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Nahuel".to_string(),
+                "2218570392".to_string(),
+                "example@gmail.com".to_string(),
+            ),
+        );
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(26, 4, 2024))
+            .expect_err("");
+        biblioteca
+            .realizar_prestamo(l2.clone(), c1, Fecha::new(26, 4, 2024))
+            .expect_err("");
+
+        // Devolucion en termino: no genera multa
+
+        biblioteca
+            .devolver_libro(&l2, c1, Fecha::new(10, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.multas_pendientes(c1), 0.0);
+
+        // Una devolucion repetida no modifica el prestamo ya devuelto
+
+        assert_eq!(
+            biblioteca.devolver_libro(&l2, c1, Fecha::new(20, 5, 2024)),
+            Err(ErrorBiblioteca::ModificarPrestamo)
+        );
+
+        // Devolucion con 4 dias de atraso
+
+        biblioteca
+            .devolver_libro(&l1, c1, Fecha::new(14, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.multas_pendientes(c1), 4.0 * MULTA_DIARIA);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 10);
+
+        // No puede realizar prestamos ni reservas hasta abonar
+
+        let e = biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(16, 5, 2024))
+            .unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::MultasPendientes);
+        println!("{}", e);
+
+        assert_eq!(
+            biblioteca.reservar_libro(&l1, c1),
+            Err(ErrorBiblioteca::MultasPendientes)
+        );
+
+        assert_eq!(biblioteca.pagar_multas(c1), Err(ErrorBiblioteca::Archivo));
+        assert_eq!(biblioteca.multas_pendientes(c1), 0.0);
+        assert_eq!(biblioteca.pagar_multas(c1), Ok(0.0));
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(16, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 1);
+    }
+
+    #[test]
+    fn test_reservas() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l3 = libros.last().unwrap().clone();
+
+        let clientes: Vec<u32> = (1..6)
+            .map(|i| {
+                registrar_cliente(
+                    &mut biblioteca,
+                    Cliente::new(
+                        format!("Cliente{}", i),
+                        format!("221000000{}", i),
+                        format!("cliente{}@gmail.com", i),
+                    ),
+                )
+            })
+            .collect();
+
+        // Con copias disponibles no es posible reservar
+
+        let e = biblioteca.reservar_libro(&l3, clientes[3]).unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::Reserva);
+        println!("{}", e);
+
+        // Se prestan las 3 copias de l3
+
+        for c in &clientes[..3] {
+            biblioteca
+                .realizar_prestamo(l3.clone(), *c, Fecha::new(26, 4, 2024))
+                .expect_err("");
+        }
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 0);
+        assert_eq!(
+            biblioteca.realizar_prestamo(l3.clone(), clientes[3], Fecha::new(26, 4, 2024)),
+            Err(ErrorBiblioteca::RealizarPrestamo)
+        );
+
+        // Dos clientes quedan en espera
+
+        biblioteca.reservar_libro(&l3, clientes[3]).expect_err("");
+        biblioteca.reservar_libro(&l3, clientes[4]).expect_err("");
+        assert_eq!(
+            biblioteca.reservar_libro(&l3, clientes[4]),
+            Err(ErrorBiblioteca::Reserva)
+        );
+
+        // La copia devuelta se asigna al primero de la cola y no vuelve al stock
+
+        biblioteca
+            .devolver_libro(&l3, clientes[0], Fecha::new(8, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 0);
+        assert!(biblioteca
+            .get_reserva(&l3, clientes[3])
+            .unwrap()
+            .fecha_limite
+            .as_ref()
+            .is_some_and(|f| f.eq(&Fecha::new(11, 5, 2024))));
+        assert!(biblioteca
+            .get_reserva(&l3, clientes[4])
+            .unwrap()
+            .fecha_limite
+            .is_none());
+
+        // El segundo en espera no puede retirar la copia asignada al primero
+
+        assert_eq!(
+            biblioteca.realizar_prestamo(l3.clone(), clientes[4], Fecha::new(6, 5, 2024)),
+            Err(ErrorBiblioteca::RealizarPrestamo)
+        );
+
+        // La reserva no se retira a tiempo y pasa al siguiente cliente
+
+        assert!(biblioteca.vencer_reservas(&Fecha::new(11, 5, 2024)).is_ok());
+        assert!(biblioteca.get_reserva(&l3, clientes[3]).is_some());
+
+        biblioteca
+            .vencer_reservas(&Fecha::new(12, 5, 2024))
+            .expect_err("");
+        assert!(biblioteca.get_reserva(&l3, clientes[3]).is_none());
+        assert!(biblioteca
+            .get_reserva(&l3, clientes[4])
+            .unwrap()
+            .fecha_limite
+            .as_ref()
+            .is_some_and(|f| f.eq(&Fecha::new(15, 5, 2024))));
+
+        biblioteca
+            .realizar_prestamo(l3.clone(), clientes[4], Fecha::new(6, 5, 2024))
+            .expect_err("");
+        assert!(biblioteca.get_reserva(&l3, clientes[4]).is_none());
+        assert_eq!(biblioteca.contar_prestamos_cliente(clientes[4]), 1);
+
+        // Sin clientes en espera, las copias devueltas vuelven al stock
+
+        biblioteca
+            .devolver_libro(&l3, clientes[1], Fecha::new(8, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 1);
+
+        // Una copia nueva se asigna primero a la reserva pendiente
+
+        biblioteca
+            .realizar_prestamo(l3.clone(), clientes[0], Fecha::new(9, 5, 2024))
+            .expect_err("");
+        biblioteca.reservar_libro(&l3, clientes[1]).expect_err("");
+        biblioteca
+            .incrementar_cantidad_copias(&l3, &Fecha::new(9, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 0);
+        assert!(biblioteca
+            .get_ejemplar("9780547928227-4")
+            .is_some_and(|e| e.estado == EstadoEjemplar::Reservado));
+        assert!(biblioteca
+            .get_reserva(&l3, clientes[1])
+            .unwrap()
+            .fecha_limite
+            .as_ref()
+            .is_some_and(|f| f.eq(&Fecha::new(12, 5, 2024))));
+    }
+
+    #[test]
+    fn test_archivo_reservas() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l3 = libros.last().unwrap().clone();
+
+        biblioteca.path_copias = "test_files/copias_reservas.json".to_string();
+        biblioteca.path_prestamos = "test_files/prestamos_reservas.json".to_string();
+        biblioteca.path_clientes = "test_files/clientes_reservas.json".to_string();
+        biblioteca.path_indice = "test_files/indice_reservas.json".to_string();
+        biblioteca.path_reservas = "test_files/reservas1.json".to_string();
+
+        let clientes: Vec<u32> = (1..6)
+            .map(|i| {
+                biblioteca
+                    .registrar_cliente(Cliente::new(
+                        format!("Cliente{}", i),
+                        format!("221000000{}", i),
+                        format!("cliente{}@gmail.com", i),
+                    ))
+                    .unwrap()
+            })
+            .collect();
+
+        for c in &clientes[..3] {
+            assert!(biblioteca
+                .realizar_prestamo(l3.clone(), *c, Fecha::new(26, 4, 2024))
+                .is_ok());
+        }
+        assert!(biblioteca.reservar_libro(&l3, clientes[3]).is_ok());
+        assert!(biblioteca.reservar_libro(&l3, clientes[4]).is_ok());
+        assert!(biblioteca
+            .devolver_libro(&l3, clientes[0], Fecha::new(8, 5, 2024))
+            .is_ok());
+
+        // La cola de espera y la copia asignada se recuperan del archivo
+
+        let mut recuperada = Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            biblioteca.path_copias.clone(),
+            biblioteca.path_prestamos.clone(),
+            biblioteca.path_clientes.clone(),
+            biblioteca.path_indice.clone(),
+            biblioteca.path_reservas.clone(),
+        );
+        assert!(recuperada.recuperar_archivos().is_ok());
+        assert_eq!(recuperada.reservas, biblioteca.reservas);
+
+        assert!(recuperada
+            .get_reserva(&l3, clientes[3])
+            .unwrap()
+            .codigo_ejemplar
+            .is_some());
+        assert_eq!(
+            recuperada.renovar_prestamo(&l3, clientes[1], &Fecha::new(9, 5, 2024)),
+            Err(ErrorBiblioteca::Renovacion)
+        );
+        assert!(recuperada
+            .realizar_prestamo(l3.clone(), clientes[3], Fecha::new(9, 5, 2024))
+            .is_ok());
+
+        let mut buf = String::new();
+        File::open(&recuperada.path_reservas)
+            .unwrap()
+            .read_to_string(&mut buf)
+            .unwrap();
+        assert!(!buf.contains(&format!("\"id_cliente\": {}", clientes[3])));
+        assert!(buf.contains(&format!("\"id_cliente\": {}", clientes[4])));
+    }
+
+    #[test]
+    fn test_ejemplares() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l1 = libros.first().unwrap().clone();
+        let l3 = libros.last().unwrap().clone();
+
+        let c1 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Nahuel".to_string(),
+                "2218570392".to_string(),
+                "example@gmail.com".to_string(),
+            ),
+        );
+        let c2 = registrar_cliente(
+            &mut biblioteca,
+            Cliente::new(
+                "Pedro".to_string(),
+                "2212604821".to_string(),
+                "test@hotmail.com".to_string(),
+            ),
+        );
+
+        // Los ejemplares generados tienen codigo unico
+
+        assert_eq!(biblioteca.copias.get(&l3).unwrap().len(), 3);
+        assert!(biblioteca
+            .get_ejemplar("9780547928227-3")
+            .is_some_and(|e| e.estado == EstadoEjemplar::Disponible));
+        assert_eq!(
+            biblioteca
+                .get_libro_ejemplar("9780547928227-3")
+                .unwrap()
+                .titulo,
+            "El Hobbit"
+        );
+
+        let e = biblioteca
+            .agregar_ejemplar(
+                &l3,
+                Ejemplar::new("9780452284234-1".to_string(), "Sala".to_string(), 9000.0),
+                &Fecha::new(1, 3, 2024),
+            )
+            .unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::Ejemplar);
+        println!("{}", e);
+
+        biblioteca
+            .agregar_ejemplar(
+                &l3,
+                Ejemplar::new("HOB-01".to_string(), "Sala".to_string(), 9000.0),
+                &Fecha::new(1, 3, 2024),
+            )
+            .expect_err("");
+        biblioteca
+            .mover_ejemplar("HOB-01", "Estante 4".to_string())
+            .expect_err("");
+        assert_eq!(
+            biblioteca.get_ejemplar("HOB-01").unwrap().ubicacion,
+            "Estante 4"
+        );
+        assert_eq!(
+            biblioteca.mover_ejemplar("XXX", "Sala".to_string()),
+            Err(ErrorBiblioteca::Ejemplar)
+        );
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 4);
+
+        // El prestamo apunta a un ejemplar especifico
+
+        biblioteca
+            .realizar_prestamo(l3.clone(), c1, Fecha::new(26, 4, 2024))
+            .expect_err("");
+        let codigo = biblioteca.prestamos.last().unwrap().codigo_ejemplar.clone();
+        assert_eq!(codigo, "9780547928227-1");
+        assert_eq!(
+            biblioteca.get_ejemplar(&codigo).unwrap().estado,
+            EstadoEjemplar::Prestado
+        );
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 3);
+
+        // Perdida: se cierra el prestamo y se cobra la reposicion mas el atraso
+
+        assert_eq!(
+            biblioteca.reportar_perdida(&l3, c1, Fecha::new(12, 5, 2024)),
+            Err(ErrorBiblioteca::Archivo)
+        );
+        assert_eq!(
+            biblioteca.get_ejemplar(&codigo).unwrap().estado,
+            EstadoEjemplar::Perdido
+        );
+        assert_eq!(
+            biblioteca.multas_pendientes(c1),
+            COSTO_REPOSICION + 2.0 * MULTA_DIARIA
+        );
+        let prestamo = biblioteca
+            .prestamos
+            .iter()
+            .rev()
+            .find(|p| p.codigo_ejemplar == codigo)
+            .unwrap();
+        assert_eq!(prestamo.multa, 2.0 * MULTA_DIARIA);
+        assert_eq!(prestamo.cargo_danio, COSTO_REPOSICION);
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 0);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 3);
+        assert_eq!(
+            biblioteca.reportar_perdida(&l3, c1, Fecha::new(12, 5, 2024)),
+            Err(ErrorBiblioteca::ModificarPrestamo)
+        );
+
+        // Danio: se cobra la reposicion solo si se informa al devolver el ejemplar
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c2, Fecha::new(26, 4, 2024))
+            .expect_err("");
+        let codigo = biblioteca.prestamos.last().unwrap().codigo_ejemplar.clone();
+
+        assert_eq!(
+            biblioteca.reportar_danio(&codigo),
+            Err(ErrorBiblioteca::Ejemplar)
+        ); // Todavia prestado
+
+        assert_eq!(
+            biblioteca.devolver_libro_danado(&l1, c2, Fecha::new(12, 5, 2024)),
+            Err(ErrorBiblioteca::Archivo)
+        );
+        let prestamo = biblioteca.prestamos.last().unwrap();
+        assert_eq!(prestamo.multa, 2.0 * MULTA_DIARIA);
+        assert_eq!(prestamo.cargo_danio, COSTO_REPOSICION);
+        assert_eq!(
+            biblioteca.get_ejemplar(&codigo).unwrap().estado,
+            EstadoEjemplar::Danado
+        );
+        assert_eq!(
+            biblioteca.multas_pendientes(c2),
+            COSTO_REPOSICION + 2.0 * MULTA_DIARIA
+        );
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 9);
+        assert_eq!(
+            biblioteca.devolver_libro_danado(&l1, c2, Fecha::new(12, 5, 2024)),
+            Err(ErrorBiblioteca::ModificarPrestamo)
+        );
+
+        biblioteca.pagar_multas(c2).expect_err("");
+        assert_eq!(biblioteca.multas_pendientes(c2), 0.0);
+
+        // Un danio detectado luego de una devolucion normal no se cobra al ultimo cliente, ni
+        //reabre su prestamo ya saldado
+
+        biblioteca
+            .reparar_ejemplar(&codigo, &Fecha::new(13, 5, 2024))
+            .expect_err("");
+        assert_eq!(
+            biblioteca.reportar_danio(&codigo),
+            Err(ErrorBiblioteca::Archivo)
+        );
+        assert!(biblioteca.prestamos.last().unwrap().multa_pagada);
+        assert_eq!(biblioteca.multas_pendientes(c2), 0.0);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 9);
+
+        // Un ejemplar sin prestamos se retira sin cobro
+
+        biblioteca.reportar_danio("HOB-01").expect_err("");
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 2);
+
+        // Al repararse vuelve a estar disponible
+
+        assert_eq!(
+            biblioteca.reparar_ejemplar("9780547928227-2", &Fecha::new(9, 5, 2024)),
+            Err(ErrorBiblioteca::Ejemplar)
+        );
+        biblioteca
+            .reparar_ejemplar("HOB-01", &Fecha::new(9, 5, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 3);
+
+        // La baja de copias solo quita ejemplares disponibles
+
+        for _i in 0..3 {
+            biblioteca.decrementar_cantidad_copias(&l3).expect_err("");
+        }
+        assert_eq!(
+            biblioteca.decrementar_cantidad_copias(&l3),
+            Err(ErrorBiblioteca::ModificarCantidadCopia)
+        );
+        assert_eq!(biblioteca.copias.get(&l3).unwrap().len(), 1); // Solo el perdido
+    }
+
+    #[test]
+    fn test_politicas_prestamo() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l1 = libros.first().unwrap().clone();
+        let l2 = libros.get(1).unwrap().clone();
+        let l3 = libros.last().unwrap().clone();
+        let fecha_actual = Fecha::new(1, 3, 2024);
+
+        let cliente1 = Cliente::new(
+            "Nahuel".to_string(),
+            "2218570392".to_string(),
+            "example@gmail.com".to_string(),
+        );
+        let mut cliente2 = Cliente::new(
+            "Pedro".to_string(),
+            "2212604821".to_string(),
+            "test@hotmail.com".to_string(),
+        );
+        cliente2.set_categoria(CategoriaCliente::Docente);
+
+        let c1 = registrar_cliente(&mut biblioteca, cliente1.clone());
+        let c2 = registrar_cliente(&mut biblioteca, cliente2.clone());
+
+        // La fecha de vencimiento depende de la categoria del cliente
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, fecha_actual.clone())
+            .expect_err("");
+        biblioteca
+            .realizar_prestamo(l1.clone(), c2, fecha_actual.clone())
+            .expect_err("");
+
+        assert!(biblioteca
+            .buscar_prestamo(&l1, c1)
+            .unwrap()
+            .fecha_vencimiento
+            .eq(&Fecha::new(15, 3, 2024)));
+        assert!(biblioteca
+            .buscar_prestamo(&l1, c2)
+            .unwrap()
+            .fecha_vencimiento
+            .eq(&Fecha::new(31, 3, 2024)));
+
+        // Un cliente general puede tener como maximo 5 prestamos simultaneos
+
+        for _i in 0..4 {
+            biblioteca
+                .realizar_prestamo(l1.clone(), c1, fecha_actual.clone())
+                .expect_err("");
+        }
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 5);
+        assert_eq!(
+            biblioteca.realizar_prestamo(l1.clone(), c1, fecha_actual.clone()),
+            Err(ErrorBiblioteca::RealizarPrestamo)
+        );
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 5);
+
+        // Politica por genero: los libros de genero Otros se prestan por 7 dias y de a uno
+
+        biblioteca.set_politica_genero(Generos::Otros, PoliticaPrestamo::new(7, 1, 0));
+
+        biblioteca
+            .realizar_prestamo(l2.clone(), c2, fecha_actual.clone())
+            .expect_err("");
+        assert!(biblioteca
+            .buscar_prestamo(&l2, c2)
+            .unwrap()
+            .fecha_vencimiento
+            .eq(&Fecha::new(8, 3, 2024)));
+        assert_eq!(
+            biblioteca.realizar_prestamo(l3.clone(), c2, fecha_actual.clone()),
+            Err(ErrorBiblioteca::RealizarPrestamo)
+        );
+
+        let e = biblioteca
+            .renovar_prestamo(&l2, c2, &fecha_actual)
+            .unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::Renovacion);
+        println!("{}", e);
+
+        // Renovaciones segun la categoria (Docente: 3)
+
+        for _i in 0..3 {
+            biblioteca
+                .renovar_prestamo(&l1, c2, &fecha_actual)
+                .expect_err("");
+        }
+        assert!(biblioteca
+            .buscar_prestamo(&l1, c2)
+            .unwrap()
+            .fecha_vencimiento
+            .eq(&Fecha::new(29, 6, 2024)));
+        assert_eq!(
+            biblioteca.renovar_prestamo(&l1, c2, &fecha_actual),
+            Err(ErrorBiblioteca::Renovacion)
+        );
+
+        // No se renueva un prestamo vencido ni uno inexistente
+
+        assert_eq!(
+            biblioteca.renovar_prestamo(&l1, c1, &Fecha::new(16, 3, 2024)),
+            Err(ErrorBiblioteca::Renovacion)
+        );
+        assert_eq!(
+            biblioteca.renovar_prestamo(&l3, c1, &fecha_actual),
+            Err(ErrorBiblioteca::ModificarPrestamo)
+        );
+
+        // Con clientes en espera tampoco se renueva
+
+        let (mut biblioteca, _) = creacion_contexto();
+        biblioteca
+            .set_politica_categoria(CategoriaCliente::General, PoliticaPrestamo::new(14, 10, 2));
+        let c1 = registrar_cliente(&mut biblioteca, cliente1);
+        let c2 = registrar_cliente(&mut biblioteca, cliente2);
+
+        for _i in 0..3 {
+            biblioteca
+                .realizar_prestamo(l3.clone(), c1, fecha_actual.clone())
+                .expect_err("");
+        }
+        biblioteca
+            .renovar_prestamo(&l3, c1, &fecha_actual)
+            .expect_err("");
+        assert_eq!(biblioteca.buscar_prestamo(&l3, c1).unwrap().renovaciones, 1);
+
+        biblioteca.reservar_libro(&l3, c2).expect_err("");
+        assert_eq!(
+            biblioteca.renovar_prestamo(&l3, c1, &fecha_actual),
+            Err(ErrorBiblioteca::Renovacion)
+        );
+    }
+
+    #[test]
+    fn test_busqueda_catalogo() {
+        let (mut biblioteca, _) = creacion_contexto();
+        biblioteca.path_copias = "test_files/copias_busqueda.json".to_string();
+        biblioteca.path_indice = "test_files/indice_busqueda.json".to_string();
+
+        let nuevos = vec![
+            Libro::new(
+                Isbn::new("978-0-06-088328-7").unwrap(),
+                "Cien años de soledad".to_string(),
+                "Gabriel García Márquez".to_string(),
+                471,
+                Generos::Novela,
+            ),
+            Libro::new(
+                Isbn::new("978-0-06-088286-0").unwrap(),
+                "El otoño del patriarca".to_string(),
+                "Gabriel García Márquez".to_string(),
+                271,
+                Generos::Novela,
+            ),
+            Libro::new(
+                Isbn::new("978-987-00-0012-9").unwrap(),
+                "Garcia para principiantes".to_string(),
+                "Anonimo".to_string(),
+                90,
+                Generos::Tecnico,
+            ),
+        ];
+
+        for l in nuevos {
+            assert!(biblioteca.agregar_copia(l).is_ok());
+        }
+
+        // Sin distinguir mayusculas ni acentos
+
+        let (libros, total) = biblioteca.buscar_catalogo("MARQUEZ", 1, 10);
+        assert_eq!(total, 2);
+        assert_eq!(libros.first().unwrap().titulo, "Cien años de soledad");
+
+        let (libros, _) = biblioteca.buscar_catalogo("otono", 1, 10);
+        assert_eq!(
+            libros.first().unwrap().isbn,
+            Isbn::new("9780060882860").unwrap()
+        );
+
+        // El titulo pesa mas que el autor
+
+        let (libros, total) = biblioteca.buscar_catalogo("garcía", 1, 10);
+        assert_eq!(total, 3);
+        assert_eq!(libros.first().unwrap().titulo, "Garcia para principiantes");
+
+        // Varias palabras suman puntaje y los prefijos tambien coinciden
+
+        let (libros, total) = biblioteca.buscar_catalogo("gabriel sol", 1, 10);
+        assert_eq!(total, 2);
+        assert_eq!(libros.first().unwrap().titulo, "Cien años de soledad");
+
+        // Prefijo de isbn y genero
+
+        let (libros, total) = biblioteca.buscar_catalogo("978-0-06-088", 1, 10);
+        assert_eq!(total, 2);
+        assert!(libros.iter().all(|l| l.autor == "Gabriel García Márquez"));
+
+        let (_, total) = biblioteca.buscar_catalogo("novela", 1, 10);
+        assert_eq!(total, 3); // Incluye a 1984
+
+        // Paginacion
+
+        let (pagina1, total) = biblioteca.buscar_catalogo("novela", 1, 2);
+        let (pagina2, _) = biblioteca.buscar_catalogo("novela", 2, 2);
+        assert_eq!(total, 3);
+        assert_eq!(pagina1.len(), 2);
+        assert_eq!(pagina2.len(), 1);
+        assert!(!pagina1.contains(pagina2.first().unwrap()));
+        assert!(biblioteca.buscar_catalogo("novela", 3, 2).0.is_empty());
+        assert!(biblioteca.buscar_catalogo("novela", 0, 2).0.is_empty());
+
+        assert_eq!(biblioteca.buscar_catalogo("inexistente", 1, 10).1, 0);
+        assert_eq!(biblioteca.buscar_catalogo("  ¿? ", 1, 10).1, 0);
+
+        // El indice queda guardado junto con las copias
+
+        let indice = abrir_archivo::<IndiceCatalogo>(&biblioteca.path_indice).unwrap();
+        assert_eq!(
+            indice
+                .terminos
+                .get("marquez")
+                .unwrap()
+                .get(&Isbn::new("9780060883287").unwrap()),
+            Some(&PESO_AUTOR)
+        );
+
+        // Y se recupera aunque falte el archivo de copias
+
+        let mut recuperada = Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            "test_files/copias_busqueda_inexistente.json".to_string(),
+            Default::default(),
+            Default::default(),
+            biblioteca.path_indice.clone(),
+            Default::default(),
+        );
+        assert_eq!(
+            recuperada.recuperar_archivos(),
+            Err(ErrorBiblioteca::Archivo)
+        ); // Sin paths para prestamos, clientes ni reservas
+        assert_eq!(recuperada.indice.terminos, biblioteca.indice.terminos);
+
+        File::create(&biblioteca.path_indice)
+            .unwrap()
+            .write_all(b"[]")
+            .unwrap();
+        assert_eq!(
+            biblioteca.recuperar_archivos(),
+            Err(ErrorBiblioteca::Archivo)
+        );
+    }
+
+    #[test]
+    fn test_clientes() {
+        let (mut biblioteca, libros) = creacion_contexto();
+        let l1 = libros.first().unwrap().clone();
+        let l3 = libros.last().unwrap().clone();
+        biblioteca.path_clientes = "test_files/clientes1.json".to_string();
+
+        // Validacion de los datos de contacto
+
+        for email in [
+            "",
+            "sin-arroba.com",
+            "a@b",
+            "a@@b.com",
+            "a b@gmail.com",
+            "a@.com",
+        ] {
+            assert_eq!(
+                biblioteca.registrar_cliente(Cliente::new(
+                    "Juan".to_string(),
+                    "2218570392".to_string(),
+                    email.to_string(),
+                )),
+                Err(ErrorBiblioteca::Email)
+            );
+        }
+        for telefono in ["", "221-857", "22185703921234567", "221 abc 0392"] {
+            let e = biblioteca
+                .registrar_cliente(Cliente::new(
+                    "Juan".to_string(),
+                    telefono.to_string(),
+                    "juan@gmail.com".to_string(),
+                ))
+                .unwrap_err();
+            assert_eq!(e, ErrorBiblioteca::Telefono);
+            println!("{}", e);
+        }
+        assert!(biblioteca.clientes.is_empty());
+
+        // Los ids se asignan en orden y el email no puede repetirse
+
+        let c1 = biblioteca
+            .registrar_cliente(Cliente::new(
+                "Juan".to_string(),
+                "+54 (221) 857-0392".to_string(),
+                "juan.perez@gmail.com".to_string(),
+            ))
+            .unwrap();
+        let c2 = biblioteca
+            .registrar_cliente(Cliente::new(
+                "Ana".to_string(),
+                "2212604821".to_string(),
+                "ana@unlp.edu.ar".to_string(),
+            ))
+            .unwrap();
+        assert_eq!((c1, c2), (1, 2));
+
+        let e = biblioteca
+            .registrar_cliente(Cliente::new(
+                "Otro Juan".to_string(),
+                "2210000000".to_string(),
+                "Juan.Perez@gmail.com".to_string(),
+            ))
+            .unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::Cliente);
+        println!("{}", e);
+
+        // Corregir el telefono no desvincula los prestamos del cliente
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(1, 3, 2024))
+            .expect_err("");
+        assert!(biblioteca
+            .modificar_cliente(
+                c1,
+                "Juan Perez".to_string(),
+                "2218570393".to_string(),
+                "juan.perez@gmail.com".to_string(),
+            )
+            .is_ok());
+        assert_eq!(biblioteca.get_cliente(c1).unwrap().telefono, "2218570393");
+        assert_eq!(biblioteca.contar_prestamos_cliente(c1), 1);
+        assert!(biblioteca
+            .devolver_libro(&l1, c1, Fecha::new(10, 3, 2024))
+            .is_err_and(|e| e == ErrorBiblioteca::Archivo));
+
+        assert_eq!(
+            biblioteca.modificar_cliente(
+                c1,
+                "Juan Perez".to_string(),
+                "2218570393".to_string(),
+                "ana@unlp.edu.ar".to_string(),
+            ),
+            Err(ErrorBiblioteca::Cliente)
+        );
+        assert_eq!(
+            biblioteca.modificar_cliente(
+                c1,
+                "Juan Perez".to_string(),
+                "telefono".to_string(),
+                "juan.perez@gmail.com".to_string(),
+            ),
+            Err(ErrorBiblioteca::Telefono)
+        );
+        assert_eq!(
+            biblioteca.modificar_cliente(
+                9,
+                "Nadie".to_string(),
+                "2218570393".to_string(),
+                "nadie@gmail.com".to_string(),
+            ),
+            Err(ErrorBiblioteca::Cliente)
+        );
+
+        // Un cliente inexistente no puede operar
+
+        assert_eq!(
+            biblioteca.realizar_prestamo(l1.clone(), 9, Fecha::new(1, 3, 2024)),
+            Err(ErrorBiblioteca::Cliente)
+        );
+        assert_eq!(
+            biblioteca.reservar_libro(&l1, 9),
+            Err(ErrorBiblioteca::Cliente)
+        );
+
+        // Con la membresia vencida no se prestan ni se renuevan libros
+
+        assert!(biblioteca
+            .renovar_membresia(c2, Fecha::new(31, 3, 2024))
+            .is_ok());
+        biblioteca
+            .realizar_prestamo(l3.clone(), c2, Fecha::new(20, 3, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.contar_prestamos_cliente(c2), 1);
+
+        let e = biblioteca
+            .renovar_prestamo(&l3, c2, &Fecha::new(1, 4, 2024))
+            .unwrap_err();
+        assert_eq!(e, ErrorBiblioteca::MembresiaVencida);
+        println!("{}", e);
+        assert_eq!(
+            biblioteca.realizar_prestamo(l1.clone(), c2, Fecha::new(1, 4, 2024)),
+            Err(ErrorBiblioteca::MembresiaVencida)
+        );
+
+        let membresias = biblioteca.membresias_a_vencer(10, &Fecha::new(25, 3, 2024));
+        assert_eq!(membresias.len(), 1);
+        assert_eq!(membresias[0].id, c2);
+        assert!(biblioteca
+            .membresias_a_vencer(5, &Fecha::new(25, 3, 2024))
+            .is_empty());
+
+        assert!(biblioteca
+            .renovar_membresia(c2, Fecha::new(31, 3, 2025))
+            .is_ok());
+        biblioteca
+            .realizar_prestamo(l1.clone(), c2, Fecha::new(1, 4, 2024))
+            .expect_err("");
+        assert_eq!(biblioteca.contar_prestamos_cliente(c2), 2);
+        assert_eq!(
+            biblioteca.renovar_membresia(9, Fecha::new(31, 3, 2025)),
+            Err(ErrorBiblioteca::Cliente)
+        );
+
+        // El registro se guarda en su propio archivo
+
+        let clientes = abrir_archivo::<Vec<Cliente>>(&biblioteca.path_clientes).unwrap();
+        assert_eq!(clientes, biblioteca.clientes);
+        assert_eq!(
+            clientes[1].vencimiento_membresia,
+            Some(Fecha::new(31, 3, 2025))
+        );
+    }
+
+    #[test]
+    fn test_migracion_archivos_previos() {
+        let path_copias = "test_files/copias_previo.json".to_string();
+        let path_prestamos = "test_files/prestamos_previo.json".to_string();
+        let path_clientes = "test_files/clientes_previo.json".to_string();
+        std::fs::remove_file(&path_clientes).ok();
+
+        // Formato previo: cantidad de copias por libro e isbn numerico. El ultimo libro tiene un
+        //isbn incompleto, que no puede migrarse
+
+        let copias = r#"[
+            [{"isbn": 452284236, "titulo": "1984", "autor": "Orwell", "paginas": 320, "genero": "Novela"}, 2],
+            [{"isbn": 9780547928227, "titulo": "El Hobbit", "autor": "Tolkien", "paginas": 255, "genero": "Otros"}, 0],
+            [{"isbn": 123456, "titulo": "Sin isbn", "autor": "Anonimo", "paginas": 10, "genero": "Otros"}, 1]
+        ]"#;
+        let prestamos = r#"[
+            {
+                "libro": {"isbn": 9780547928227, "titulo": "El Hobbit", "autor": "Tolkien", "paginas": 255, "genero": "Otros"},
+                "cliente": {"nombre": "Nahuel", "telefono": "2218570392", "email": "example@gmail.com"},
+                "fecha_vencimiento": {"dia": 10, "mes": 1, "anio": 2024},
+                "fecha_devolucion": null,
+                "fue_devuelto": false
+            },
+            {
+                "libro": {"isbn": 452284236, "titulo": "1984", "autor": "Orwell", "paginas": 320, "genero": "Novela"},
+                "cliente": {"nombre": "Pedro", "telefono": "2212604821", "email": "test@hotmail.com"},
+                "fecha_vencimiento": {"dia": 15, "mes": 1, "anio": 2024},
+                "fecha_devolucion": {"dia": 14, "mes": 1, "anio": 2024},
+                "fue_devuelto": true
+            },
+            {
+                "libro": {"isbn": 123456, "titulo": "Sin isbn", "autor": "Anonimo", "paginas": 10, "genero": "Otros"},
+                "cliente": {"nombre": "Ana", "telefono": "2210000000", "email": "ana@gmail.com"},
+                "fecha_vencimiento": {"dia": 15, "mes": 1, "anio": 2024},
+                "fecha_devolucion": null,
+                "fue_devuelto": false
+            }
+        ]"#;
+
+        for path in [&path_copias, &path_prestamos] {
+            std::fs::remove_file(Biblioteca::path_rechazados(path)).ok();
+        }
+
+        File::create(&path_copias)
+            .unwrap()
+            .write_all(copias.as_bytes())
+            .unwrap();
+        File::create(&path_prestamos)
+            .unwrap()
+            .write_all(prestamos.as_bytes())
+            .unwrap();
+
+        let mut biblioteca = Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            path_copias.clone(),
+            path_prestamos.clone(),
+            path_clientes.clone(),
+            "test_files/indice_previo.json".to_string(),
+            "test_files/reservas_previo.json".to_string(),
+        );
+
+        assert!(biblioteca.recuperar_archivos().is_ok());
+
+        let l1 = Libro::new(
+            Isbn::new("0-452-28423-6").unwrap(),
+            "1984".to_string(),
+            "Orwell".to_string(),
+            320,
+            Generos::Novela,
+        );
+        let l3 = Libro::new(
+            Isbn::new("978-0-547-92822-7").unwrap(),
+            "El Hobbit".to_string(),
+            "Tolkien".to_string(),
+            255,
+            Generos::Otros,
+        );
+
+        // Las cantidades se convierten en ejemplares y el prestamo activo obtiene el suyo
+
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 2);
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 0);
+        assert_eq!(
+            biblioteca.prestamos.first().unwrap().codigo_ejemplar,
+            "9780547928227-1"
+        );
+        assert_eq!(
+            biblioteca.get_ejemplar("9780547928227-1").unwrap().estado,
+            EstadoEjemplar::Prestado
+        );
+        assert!(biblioteca
+            .prestamos
+            .last()
+            .unwrap()
+            .codigo_ejemplar
+            .is_empty());
+        assert_eq!(biblioteca.buscar_catalogo("hobbit", 1, 10).1, 1);
+
+        // Los clientes de cada prestamo se registran y se referencian por id
+
+        assert_eq!(biblioteca.clientes.len(), 2);
+        let c1 = biblioteca.prestamos.first().unwrap().id_cliente;
+        assert_eq!(biblioteca.get_cliente(c1).unwrap().nombre, "Nahuel");
+        assert_eq!(biblioteca.prestamos.last().unwrap().id_cliente, 2);
+
+        // El prestamo migrado puede devolverse normalmente
+
+        biblioteca
+            .devolver_libro(&l3, c1, Fecha::new(10, 1, 2024))
+            .unwrap();
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l3), 1);
+
+        // Los archivos se reescriben con el isbn como string
+
+        let copias = abrir_archivo::<Vec<(Libro, Vec<Ejemplar>)>>(&path_copias).unwrap();
+        assert_eq!(copias.len(), 2);
+
+        let mut buf = String::new();
+        File::open(&path_prestamos)
+            .unwrap()
+            .read_to_string(&mut buf)
+            .unwrap();
+        assert!(buf.contains("\"isbn\": \"9780452284234\""));
+        assert!(!buf.contains("452284236,"));
+        assert!(buf.contains("\"id_cliente\": 1"));
+        assert!(!buf.contains("\"cliente\""));
+        assert_eq!(
+            abrir_archivo::<Vec<Cliente>>(&path_clientes).unwrap().len(),
+            2
+        );
+
+        // Los registros con isbn invalido se apartan tal como estaban, sin registrar al cliente
+
+        assert_eq!(biblioteca.copias.len(), 2);
+        assert_eq!(biblioteca.prestamos.len(), 2);
+        assert!(biblioteca.clientes.iter().all(|c| c.nombre != "Ana"));
+
+        let rechazados =
+            abrir_archivo::<Vec<serde_json::Value>>(&Biblioteca::path_rechazados(&path_copias))
+                .unwrap();
+        assert_eq!(rechazados.len(), 1);
+        assert_eq!(rechazados[0][0]["isbn"], 123456);
+
+        let rechazados =
+            abrir_archivo::<Vec<serde_json::Value>>(&Biblioteca::path_rechazados(&path_prestamos))
+                .unwrap();
+        assert_eq!(rechazados.len(), 1);
+        assert_eq!(rechazados[0]["cliente"]["nombre"], "Ana");
+
+        // Recuperar el formato actual no modifica los datos
+
+        let mut recuperada = Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            path_copias.clone(),
+            path_prestamos.clone(),
+            path_clientes.clone(),
+            "test_files/indice_previo.json".to_string(),
+            "test_files/reservas_previo.json".to_string(),
+        );
+        assert!(recuperada.recuperar_archivos().is_ok());
+        assert_eq!(recuperada.prestamos, biblioteca.prestamos);
+        assert_eq!(recuperada.clientes, biblioteca.clientes);
+        assert_eq!(recuperada.obtener_cantidad_copias(&l3), 1);
+        assert_eq!(recuperada.copias.get(&l1).unwrap().len(), 2);
+
+        // Un archivo con formato no valido
+
+        File::create(&path_copias)
+            .unwrap()
+            .write_all(b"[[{\"isbn\": \"978-0-452-28423-4\"}, 1]]")
+            .unwrap();
+        assert_eq!(
+            recuperada.recuperar_archivos(),
+            Err(ErrorBiblioteca::Archivo)
+        );
+    }
 }
@@ -0,0 +1,589 @@
+use std::{fmt::Display, fs::File, io::prelude::*};
+
+use super::reportes::formatear_fecha;
+use super::{Biblioteca, Ejemplar, ErrorBiblioteca, EstadoEjemplar, Generos, Libro};
+use crate::tp3::ej10::{ErrorIsbn, Isbn};
+
+// Solo se exportan los ejemplares disponibles, que son los que se registran al importar
+const ENCABEZADO_CATALOGO: &str = "isbn,titulo,autor,paginas,genero,disponibles";
+const ENCABEZADO_PRESTAMOS: &str = "isbn,titulo,ejemplar,id_cliente,cliente,email,fecha_prestamo,fecha_vencimiento,fecha_devolucion,renovaciones,multa,cargo_danio,multa_pagada";
+
+// Cabecera fija de cada registro en formato MARC de texto (una etiqueta por linea)
+const LIDER_MARC: &str = "=LDR  00000nam  2200000   4500";
+
+#[derive(Debug, PartialEq)]
+enum MotivoError {
+    CantidadColumnas(usize),
+    Isbn(ErrorIsbn),
+    Titulo,
+    Autor,
+    Paginas,
+    Genero,
+    Cantidad,
+}
+
+// La fila corresponde a la linea del csv o al numero de registro MARC
+#[derive(Debug, PartialEq)]
+struct ErrorFila {
+    fila: usize,
+    motivo: MotivoError,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct ResultadoImportacion {
+    libros: u32,
+    ejemplares: u32,
+    errores: Vec<ErrorFila>,
+}
+
+impl Display for MotivoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MotivoError::CantidadColumnas(c) => write!(f, "se esperaban 6 columnas y hay {}", c),
+            MotivoError::Isbn(e) => write!(f, "{}", e),
+            MotivoError::Titulo => write!(f, "el titulo no puede estar vacio"),
+            MotivoError::Autor => write!(f, "el autor no puede estar vacio"),
+            MotivoError::Paginas => write!(f, "la cantidad de paginas no es valida"),
+            MotivoError::Genero => write!(f, "el genero no es valido"),
+            MotivoError::Cantidad => write!(f, "la cantidad de copias no es valida"),
+        }
+    }
+}
+
+impl Display for ErrorFila {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fila {}: {}", self.fila, self.motivo)
+    }
+}
+
+impl Generos {
+    fn from_texto(texto: &str) -> Option<Generos> {
+        match texto.trim().to_lowercase().replace('é', "e").as_str() {
+            "novela" => Some(Generos::Novela),
+            "infantil" => Some(Generos::Infantil),
+            "tecnico" => Some(Generos::Tecnico),
+            "otros" => Some(Generos::Otros),
+            _ => None,
+        }
+    }
+}
+
+// Separa una linea csv respetando los campos entre comillas (con "" como comilla escapada)
+fn separar_campos_csv(linea: &str) -> Vec<String> {
+    let mut campos = Vec::new();
+    let mut campo = String::new();
+    let mut entre_comillas = false;
+    let mut caracteres = linea.chars().peekable();
+
+    while let Some(c) = caracteres.next() {
+        match c {
+            '"' if entre_comillas && caracteres.peek() == Some(&'"') => {
+                campo.push('"');
+                caracteres.next();
+            }
+            '"' => entre_comillas = !entre_comillas,
+            ',' if !entre_comillas => campos.push(std::mem::take(&mut campo)),
+            c => campo.push(c),
+        }
+    }
+    campos.push(campo);
+
+    campos
+}
+
+fn escapar_csv(campo: &str) -> String {
+    if campo.contains([',', '"', '\n']) {
+        format!("\"{}\"", campo.replace('"', "\"\""))
+    } else {
+        campo.to_string()
+    }
+}
+
+fn validar_registro(campos: &[String]) -> Result<(Libro, u32), MotivoError> {
+    let [isbn, titulo, autor, paginas, genero, cantidad] = campos else {
+        return Err(MotivoError::CantidadColumnas(campos.len()));
+    };
+
+    let isbn = Isbn::new(isbn.trim()).map_err(MotivoError::Isbn)?;
+
+    if titulo.trim().is_empty() {
+        return Err(MotivoError::Titulo);
+    }
+
+    if autor.trim().is_empty() {
+        return Err(MotivoError::Autor);
+    }
+
+    let Ok(paginas) = paginas.trim().parse::<u32>() else {
+        return Err(MotivoError::Paginas);
+    };
+
+    let Some(genero) = Generos::from_texto(genero) else {
+        return Err(MotivoError::Genero);
+    };
+
+    let Ok(cantidad) = cantidad.trim().parse::<u32>() else {
+        return Err(MotivoError::Cantidad);
+    };
+
+    let libro = Libro::new(
+        isbn,
+        titulo.trim().to_string(),
+        autor.trim().to_string(),
+        paginas,
+        genero,
+    );
+
+    Ok((libro, cantidad))
+}
+
+fn escribir_archivo(path: &String, contenido: &str) -> Result<(), ErrorBiblioteca> {
+    let Ok(mut f) = File::create(path) else {
+        return Err(ErrorBiblioteca::Archivo);
+    };
+
+    let Ok(_) = f.write_all(contenido.as_bytes()) else {
+        return Err(ErrorBiblioteca::Archivo);
+    };
+
+    Ok(())
+}
+
+impl Biblioteca {
+    fn contar_disponibles(ejemplares: &[Ejemplar]) -> usize {
+        ejemplares
+            .iter()
+            .filter(|e| e.estado == EstadoEjemplar::Disponible)
+            .count()
+    }
+
+    // Los libros con un isbn ya registrado suman ejemplares al existente. Las filas con errores
+    //se informan y no impiden importar el resto. Los archivos se actualizan una sola vez al final
+    fn importar_registros(
+        &mut self,
+        registros: Vec<(usize, Vec<String>)>,
+    ) -> Result<ResultadoImportacion, ErrorBiblioteca> {
+        let mut resultado = ResultadoImportacion::default();
+
+        for (fila, campos) in registros {
+            match validar_registro(&campos) {
+                Ok((libro, cantidad)) => {
+                    let libro = match self.copias.keys().find(|l| l.isbn == libro.isbn) {
+                        Some(existente) => existente.clone(),
+                        None => {
+                            resultado.libros += 1;
+                            self.indice.indexar(&libro);
+                            self.copias.insert(libro.clone(), Vec::new());
+                            libro
+                        }
+                    };
+
+                    for _i in 0..cantidad {
+                        self.registrar_ejemplar(&libro, EstadoEjemplar::Disponible);
+                    }
+                    resultado.ejemplares += cantidad;
+                }
+                Err(motivo) => resultado.errores.push(ErrorFila { fila, motivo }),
+            }
+        }
+
+        let resultado_copias = self.actualizar_archivo_copias();
+        resultado_copias.and(Self::actualizar_archivo(&self.path_indice, &self.indice))?;
+
+        Ok(resultado)
+    }
+
+    // El encabezado es opcional y las lineas vacias se ignoran. La ultima columna del encabezado
+    //puede llamarse cantidad, como en los archivos exportados previamente
+    fn importar_csv(&mut self, path: &String) -> Result<ResultadoImportacion, ErrorBiblioteca> {
+        let Some(contenido) = Self::leer_archivo(path) else {
+            return Err(ErrorBiblioteca::Archivo);
+        };
+
+        let registros = contenido
+            .lines()
+            .enumerate()
+            .filter(|(i, linea)| *i > 0 || !linea.starts_with("isbn,titulo,"))
+            .filter(|(_, linea)| !linea.trim().is_empty())
+            .map(|(i, linea)| (i + 1, separar_campos_csv(linea)))
+            .collect();
+
+        self.importar_registros(registros)
+    }
+
+    fn exportar_catalogo_csv(&self, path: &String) -> Result<(), ErrorBiblioteca> {
+        let mut libros: Vec<_> = self.copias.iter().collect();
+        libros.sort_by(|a, b| a.0.titulo.cmp(&b.0.titulo));
+
+        let mut csv = format!("{}\n", ENCABEZADO_CATALOGO);
+
+        for (libro, ejemplares) in libros {
+            let cantidad = Self::contar_disponibles(ejemplares);
+
+            csv.push_str(&format!(
+                "{},{},{},{},{:?},{}\n",
+                libro.isbn,
+                escapar_csv(&libro.titulo),
+                escapar_csv(&libro.autor),
+                libro.paginas,
+                libro.genero,
+                cantidad
+            ));
+        }
+
+        escribir_archivo(path, &csv)
+    }
+
+    fn exportar_prestamos_csv(&self, path: &String) -> Result<(), ErrorBiblioteca> {
+        let mut csv = format!("{}\n", ENCABEZADO_PRESTAMOS);
+
+        for p in &self.prestamos {
+            let (nombre, email) = self
+                .get_cliente(p.id_cliente)
+                .map_or(("", ""), |c| (c.nombre.as_str(), c.email.as_str()));
+
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{:.2},{:.2},{}\n",
+                p.libro.isbn,
+                escapar_csv(&p.libro.titulo),
+                p.codigo_ejemplar,
+                p.id_cliente,
+                escapar_csv(nombre),
+                escapar_csv(email),
+                p.fecha_prestamo
+                    .as_ref()
+                    .map_or(String::new(), formatear_fecha),
+                formatear_fecha(&p.fecha_vencimiento),
+                p.fecha_devolucion
+                    .as_ref()
+                    .map_or(String::new(), formatear_fecha),
+                p.renovaciones,
+                p.multa,
+                p.cargo_danio,
+                p.multa_pagada
+            ));
+        }
+
+        escribir_archivo(path, &csv)
+    }
+
+    // Etiquetas: 020 isbn, 100 autor, 245 titulo, 300 paginas, 655 genero, 852 ejemplares
+    //disponibles
+    fn exportar_marc(&self, path: &String) -> Result<(), ErrorBiblioteca> {
+        let mut libros: Vec<_> = self.copias.iter().collect();
+        libros.sort_by(|a, b| a.0.titulo.cmp(&b.0.titulo));
+
+        let registros: Vec<String> = libros
+            .into_iter()
+            .map(|(libro, ejemplares)| {
+                let cantidad = Self::contar_disponibles(ejemplares);
+
+                format!(
+                    "{}\n=020  $a{}\n=100  $a{}\n=245  $a{}\n=300  $a{} p.\n=655  $a{:?}\n=852  $t{}\n",
+                    LIDER_MARC,
+                    libro.isbn,
+                    libro.autor.replace('$', "{dollar}"),
+                    libro.titulo.replace('$', "{dollar}"),
+                    libro.paginas,
+                    libro.genero,
+                    cantidad
+                )
+            })
+            .collect();
+
+        escribir_archivo(path, &registros.join("\n"))
+    }
+
+    // Los registros se separan por lineas vacias. Las etiquetas desconocidas se ignoran
+    fn importar_marc(&mut self, path: &String) -> Result<ResultadoImportacion, ErrorBiblioteca> {
+        let Some(contenido) = Self::leer_archivo(path) else {
+            return Err(ErrorBiblioteca::Archivo);
+        };
+
+        let registros = contenido
+            .split("\n\n")
+            .filter(|r| !r.trim().is_empty())
+            .enumerate()
+            .map(|(i, registro)| {
+                let mut campos = vec![String::new(); 6];
+
+                for linea in registro.lines() {
+                    let Some((etiqueta, valor)) = linea.trim_start_matches('=').split_once("  ")
+                    else {
+                        continue;
+                    };
+                    let valor = valor
+                        .trim_start_matches("$a")
+                        .trim_start_matches("$t")
+                        .replace("{dollar}", "$");
+
+                    match etiqueta {
+                        "020" => campos[0] = valor,
+                        "245" => campos[1] = valor,
+                        "100" => campos[2] = valor,
+                        "300" => campos[3] = valor.trim_end_matches(" p.").to_string(),
+                        "655" => campos[4] = valor,
+                        "852" => campos[5] = valor,
+                        _ => (),
+                    }
+                }
+
+                (i + 1, campos)
+            })
+            .collect();
+
+        self.importar_registros(registros)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tp3::ej03::Fecha;
+    use crate::tp5::ej04::Cliente;
+
+    fn crear_biblioteca(nombre: &str) -> Biblioteca {
+        Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            format!("test_files/copias_{}.json", nombre),
+            format!("test_files/prestamos_{}.json", nombre),
+            format!("test_files/clientes_{}.json", nombre),
+            format!("test_files/indice_{}.json", nombre),
+            format!("test_files/reservas_{}.json", nombre),
+        )
+    }
+
+    fn leer(path: &String) -> String {
+        let mut buf = String::new();
+        File::open(path).unwrap().read_to_string(&mut buf).unwrap();
+
+        buf
+    }
+
+    #[test]
+    fn test_importar_csv() {
+        let mut biblioteca = crear_biblioteca("csv");
+        let path = "test_files/catalogo.csv".to_string();
+
+        let csv = "isbn,titulo,autor,paginas,genero,cantidad
+978-0-452-28423-4,1984,Orwell,320,Novela,3
+1-4215-2772-3,Battle Royale,Koushun Takami,550,otros,2
+
+\"978-0-06-088328-7\",\"Cien años de soledad, edicion especial\",\"Garcia Marquez\",471,Novela,1
+978-0-452-28423-5,Libro,Autor,100,Novela,1
+978-0-547-92822-7,El Hobbit,Tolkien,doscientas,Otros,1
+978-0-547-92822-7,El Hobbit,Tolkien,255,Fantasia,1
+978-0-547-92822-7,El Hobbit,Tolkien,255
+978-0-547-92822-7,,Tolkien,255,Otros,1
+0-452-28423-6,1984,Orwell,320,Novela,2
+12345678é,Libro,Autor,100,Novela,1
+";
+        escribir_archivo(&path, csv).unwrap();
+
+        let resultado = biblioteca.importar_csv(&path).unwrap();
+
+        assert_eq!(resultado.libros, 3);
+        assert_eq!(resultado.ejemplares, 8);
+        assert_eq!(
+            resultado.errores,
+            vec![
+                ErrorFila {
+                    fila: 6,
+                    motivo: MotivoError::Isbn(ErrorIsbn::DigitoVerificador)
+                },
+                ErrorFila {
+                    fila: 7,
+                    motivo: MotivoError::Paginas
+                },
+                ErrorFila {
+                    fila: 8,
+                    motivo: MotivoError::Genero
+                },
+                ErrorFila {
+                    fila: 9,
+                    motivo: MotivoError::CantidadColumnas(4)
+                },
+                ErrorFila {
+                    fila: 10,
+                    motivo: MotivoError::Titulo
+                },
+                ErrorFila {
+                    fila: 12,
+                    motivo: MotivoError::Isbn(ErrorIsbn::Formato)
+                },
+            ]
+        );
+        assert_eq!(
+            resultado.errores[3].to_string(),
+            "Fila 9: se esperaban 6 columnas y hay 4"
+        );
+
+        // El ISBN-10 de la ultima fila corresponde al mismo libro que la primera
+
+        let l1 = biblioteca
+            .copias
+            .keys()
+            .find(|l| l.titulo == "1984")
+            .unwrap()
+            .clone();
+        assert_eq!(biblioteca.obtener_cantidad_copias(&l1), 5);
+        assert_eq!(biblioteca.buscar_catalogo("soledad", 1, 10).1, 1);
+        assert!(biblioteca
+            .copias
+            .keys()
+            .any(|l| l.titulo == "Cien años de soledad, edicion especial"));
+
+        // Errores de archivo
+
+        assert_eq!(
+            biblioteca.importar_csv(&"test_files/inexistente.csv".to_string()),
+            Err(ErrorBiblioteca::Archivo)
+        );
+    }
+
+    #[test]
+    fn test_exportar_csv() {
+        let mut biblioteca = crear_biblioteca("exportar");
+        let path = "test_files/catalogo_importado.csv".to_string();
+
+        escribir_archivo(
+            &path,
+            "978-0-452-28423-4,1984,Orwell,320,Novela,2\n978-0-06-088328-7,\"Cien años, \"\"soledad\"\"\",Garcia Marquez,471,Novela,1\n",
+        )
+        .unwrap();
+        biblioteca.importar_csv(&path).unwrap();
+
+        let l1 = biblioteca
+            .copias
+            .keys()
+            .find(|l| l.titulo == "1984")
+            .unwrap()
+            .clone();
+        let c1 = biblioteca
+            .registrar_cliente(Cliente::new(
+                "Nahuel".to_string(),
+                "2218570392".to_string(),
+                "example@gmail.com".to_string(),
+            ))
+            .unwrap();
+
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(1, 3, 2024))
+            .unwrap();
+        biblioteca
+            .devolver_libro(&l1, c1, Fecha::new(17, 3, 2024))
+            .unwrap();
+        biblioteca
+            .realizar_prestamo(l1.clone(), c1, Fecha::new(20, 3, 2024))
+            .expect_err(""); // Multa pendiente
+        assert!(biblioteca.reportar_danio("9780452284234-2").is_ok());
+
+        // Catalogo: las comillas y comas se escapan, y el archivo puede volver a importarse. El
+        //ejemplar danado no se exporta, ya que se importaria como disponible
+
+        let path_catalogo = "test_files/catalogo_exportado.csv".to_string();
+        assert!(biblioteca.exportar_catalogo_csv(&path_catalogo).is_ok());
+        assert_eq!(
+            leer(&path_catalogo),
+            "isbn,titulo,autor,paginas,genero,disponibles
+9780452284234,1984,Orwell,320,Novela,1
+9780060883287,\"Cien años, \"\"soledad\"\"\",Garcia Marquez,471,Novela,1
+"
+        );
+
+        let mut copia = crear_biblioteca("reimportar");
+        let resultado = copia.importar_csv(&path_catalogo).unwrap();
+        assert_eq!(resultado.libros, 2);
+        assert_eq!(resultado.ejemplares, 2);
+        assert!(resultado.errores.is_empty());
+
+        // Libro de prestamos
+
+        let path_prestamos = "test_files/prestamos_exportados.csv".to_string();
+        assert!(biblioteca.exportar_prestamos_csv(&path_prestamos).is_ok());
+        let lineas: Vec<String> = leer(&path_prestamos)
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(lineas.len(), 2);
+        assert_eq!(lineas[0], ENCABEZADO_PRESTAMOS);
+        assert_eq!(
+            lineas[1],
+            "9780452284234,1984,9780452284234-1,1,Nahuel,example@gmail.com,01/03/2024,15/03/2024,17/03/2024,0,100.00,0.00,false"
+        );
+
+        assert_eq!(
+            biblioteca.exportar_catalogo_csv(&"/".to_string()),
+            Err(ErrorBiblioteca::Archivo)
+        );
+    }
+
+    #[test]
+    fn test_marc() {
+        let mut biblioteca = crear_biblioteca("marc");
+        let path_csv = "test_files/catalogo_marc.csv".to_string();
+
+        escribir_archivo(
+            &path_csv,
+            "978-0-452-28423-4,1984,Orwell,320,Novela,2\n978-0-547-92822-7,El Hobbit,J. R. R. Tolkien,255,Otros,1\n",
+        )
+        .unwrap();
+        biblioteca.importar_csv(&path_csv).unwrap();
+
+        let path = "test_files/catalogo.mrk".to_string();
+        assert!(biblioteca.exportar_marc(&path).is_ok());
+
+        let marc = leer(&path);
+        assert!(marc.starts_with(&format!(
+            "{}\n=020  $a9780452284234\n=100  $aOrwell\n=245  $a1984\n=300  $a320 p.\n=655  $aNovela\n=852  $t2\n\n",
+            LIDER_MARC
+        )));
+        assert_eq!(marc.matches(LIDER_MARC).count(), 2);
+
+        // Se importa en otra biblioteca
+
+        let mut copia = crear_biblioteca("marc_copia");
+        let resultado = copia.importar_marc(&path).unwrap();
+        assert_eq!(resultado.libros, 2);
+        assert_eq!(resultado.ejemplares, 3);
+        assert!(resultado.errores.is_empty());
+
+        let hobbit = copia
+            .copias
+            .keys()
+            .find(|l| l.autor == "J. R. R. Tolkien")
+            .unwrap();
+        assert_eq!(hobbit.paginas, 255);
+        assert_eq!(hobbit.genero, Generos::Otros);
+
+        // Registros con errores y etiquetas desconocidas
+
+        let path_errores = "test_files/errores.mrk".to_string();
+        escribir_archivo(
+            &path_errores,
+            &format!(
+                "{}\n=020  $a9780452284234\n=100  $aOrwell\n=245  $a1984\n=300  $a320 p.\n=655  $aNovela\n=852  $t1\n=999  $aignorar\n\n{}\n=020  $a9780452284234\n=245  $aSin autor\n=300  $a10 p.\n=655  $aNovela\n=852  $t1\n\n{}\n=020  $a12345678é\n=100  $aAutor\n=245  $aLibro\n=300  $a10 p.\n=655  $aNovela\n=852  $t1\n",
+                LIDER_MARC, LIDER_MARC, LIDER_MARC
+            ),
+        )
+        .unwrap();
+
+        let resultado = copia.importar_marc(&path_errores).unwrap();
+        assert_eq!(resultado.libros, 0);
+        assert_eq!(resultado.ejemplares, 1);
+        assert_eq!(
+            resultado.errores,
+            vec![
+                ErrorFila {
+                    fila: 2,
+                    motivo: MotivoError::Autor
+                },
+                ErrorFila {
+                    fila: 3,
+                    motivo: MotivoError::Isbn(ErrorIsbn::Formato)
+                },
+            ]
+        );
+    }
+}
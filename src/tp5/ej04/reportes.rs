@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+
+use super::{Biblioteca, Cliente, Generos, Libro, Prestamo, MULTA_DIARIA};
+use crate::tp3::ej03::Fecha;
+
+#[derive(Debug, PartialEq)]
+struct EstadisticasCirculacion {
+    prestamos: u32,
+    devoluciones: u32,
+    devoluciones_con_atraso: u32,
+    activos: u32,
+    vencidos: u32,
+    multas: f64,
+}
+
+// Prestamos vencidos de un mismo cliente a la fecha dada
+#[derive(Debug)]
+struct AvisoVencimiento<'a> {
+    biblioteca: &'a str,
+    cliente: &'a Cliente,
+    prestamos: Vec<&'a Prestamo>,
+    fecha: Fecha,
+}
+
+pub(super) fn formatear_fecha(fecha: &Fecha) -> String {
+    format!(
+        "{:02}/{:02}/{}",
+        fecha.get_dia(),
+        fecha.get_mes(),
+        fecha.get_anio()
+    )
+}
+
+impl Prestamo {
+    fn dias_atraso(&self, fecha: &Fecha) -> i64 {
+        self.fecha_vencimiento.dias_hasta(fecha).max(0)
+    }
+
+    // Un prestamo sin fecha de prestamo (archivos previos) se considera anterior a cualquier fecha
+    fn prestado_hasta(&self, fecha: &Fecha) -> bool {
+        self.fecha_prestamo
+            .as_ref()
+            .is_none_or(|f| !f.es_mayor(fecha))
+    }
+
+    fn devuelto_hasta(&self, fecha: &Fecha) -> bool {
+        self.fecha_devolucion
+            .as_ref()
+            .is_some_and(|f| !f.es_mayor(fecha))
+    }
+}
+
+impl AvisoVencimiento<'_> {
+    fn multa_estimada(&self) -> f64 {
+        self.prestamos
+            .iter()
+            .map(|p| p.dias_atraso(&self.fecha) as f64 * MULTA_DIARIA)
+            .sum()
+    }
+
+    fn to_texto(&self) -> String {
+        let mut texto = format!(
+            "Estimado/a {}:\n\nLos siguientes prestamos se encuentran vencidos al {}:\n",
+            self.cliente.nombre,
+            formatear_fecha(&self.fecha)
+        );
+
+        for p in &self.prestamos {
+            texto.push_str(&format!(
+                "- {} ({}), vencido el {}: {} dias de atraso\n",
+                p.libro.titulo,
+                p.libro.autor,
+                formatear_fecha(&p.fecha_vencimiento),
+                p.dias_atraso(&self.fecha)
+            ));
+        }
+
+        texto.push_str(&format!(
+            "\nMulta acumulada a la fecha: ${:.2}\n\nPor favor, devuelva los libros a la brevedad.\n{}\n",
+            self.multa_estimada(),
+            self.biblioteca
+        ));
+
+        texto
+    }
+
+    fn to_email(&self) -> String {
+        format!(
+            "Para: {}\nAsunto: {} - Prestamos vencidos\n\n{}",
+            self.cliente.email,
+            self.biblioteca,
+            self.to_texto()
+        )
+    }
+}
+
+impl Biblioteca {
+    // Ordenados por cantidad de prestamos y luego por titulo
+    fn libros_mas_prestados(&self, cantidad: usize) -> Vec<(&Libro, u32)> {
+        let mut prestamos_libro: HashMap<&Libro, u32> = HashMap::new();
+
+        for p in &self.prestamos {
+            *prestamos_libro.entry(&p.libro).or_insert(0) += 1;
+        }
+
+        let mut libros: Vec<(&Libro, u32)> = prestamos_libro.into_iter().collect();
+        libros.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.titulo.cmp(&b.0.titulo)));
+        libros.truncate(cantidad);
+
+        libros
+    }
+
+    fn generos_mas_prestados(&self) -> Vec<(Generos, u32)> {
+        let mut prestamos_genero: HashMap<Generos, u32> = HashMap::new();
+
+        for p in &self.prestamos {
+            *prestamos_genero.entry(p.libro.genero.clone()).or_insert(0) += 1;
+        }
+
+        let mut generos: Vec<(Generos, u32)> = prestamos_genero.into_iter().collect();
+        generos.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then(format!("{:?}", a.0).cmp(&format!("{:?}", b.0)))
+        });
+
+        generos
+    }
+
+    fn historial_cliente(&self, id_cliente: u32) -> Vec<&Prestamo> {
+        self.prestamos
+            .iter()
+            .filter(|p| p.id_cliente == id_cliente)
+            .collect()
+    }
+
+    // Un aviso por cliente con sus prestamos sin devolver y vencidos, del mas antiguo al mas reciente
+    fn avisos_vencimiento(&self, fecha_actual: &Fecha) -> Vec<AvisoVencimiento<'_>> {
+        let mut avisos: Vec<AvisoVencimiento> = Vec::new();
+
+        for p in self.prestamos_vencidos(fecha_actual) {
+            let Some(cliente) = self.get_cliente(p.id_cliente) else {
+                continue;
+            };
+
+            match avisos.iter_mut().find(|a| a.cliente.id == cliente.id) {
+                Some(aviso) => aviso.prestamos.push(p),
+                None => avisos.push(AvisoVencimiento {
+                    biblioteca: &self.nombre,
+                    cliente,
+                    prestamos: vec![p],
+                    fecha: fecha_actual.clone(),
+                }),
+            }
+        }
+
+        for aviso in avisos.iter_mut() {
+            aviso
+                .prestamos
+                .sort_by_key(|p| -p.fecha_vencimiento.dias_hasta(fecha_actual));
+        }
+
+        avisos
+    }
+
+    // Activos y vencidos se calculan al final del rango
+    fn estadisticas_circulacion(&self, desde: &Fecha, hasta: &Fecha) -> EstadisticasCirculacion {
+        if desde.es_mayor(hasta) {
+            panic!("Rango de fechas no valido");
+        }
+
+        let en_rango = |f: &Fecha| !desde.es_mayor(f) && !f.es_mayor(hasta);
+
+        let mut estadisticas = EstadisticasCirculacion {
+            prestamos: 0,
+            devoluciones: 0,
+            devoluciones_con_atraso: 0,
+            activos: 0,
+            vencidos: 0,
+            multas: 0.0,
+        };
+
+        for p in &self.prestamos {
+            if p.fecha_prestamo.as_ref().is_some_and(en_rango) {
+                estadisticas.prestamos += 1;
+            }
+
+            if let Some(devolucion) = p.fecha_devolucion.as_ref().filter(|f| en_rango(f)) {
+                estadisticas.devoluciones += 1;
+                estadisticas.multas += p.multa;
+
+                if devolucion.es_mayor(&p.fecha_vencimiento) {
+                    estadisticas.devoluciones_con_atraso += 1;
+                }
+            }
+
+            if p.prestado_hasta(hasta) && !p.devuelto_hasta(hasta) {
+                estadisticas.activos += 1;
+
+                if hasta.es_mayor(&p.fecha_vencimiento) {
+                    estadisticas.vencidos += 1;
+                }
+            }
+        }
+
+        estadisticas
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::CategoriaCliente;
+    use super::*;
+    use crate::tp3::ej10::Isbn;
+
+    fn crear_biblioteca() -> (Biblioteca, Vec<Libro>, Vec<u32>) {
+        let mut biblioteca = Biblioteca::new(
+            "Biblioteca UNLP".to_string(),
+            "Direccion".to_string(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        );
+
+        let libros = vec![
+            Libro::new(
+                Isbn::new("978-0-452-28423-4").unwrap(),
+                "1984".to_string(),
+                "Orwell".to_string(),
+                320,
+                Generos::Novela,
+            ),
+            Libro::new(
+                Isbn::new("1-4215-2772-3").unwrap(),
+                "Battle Royale".to_string(),
+                "Koushun Takami".to_string(),
+                550,
+                Generos::Otros,
+            ),
+            Libro::new(
+                Isbn::new("978-0-547-92822-7").unwrap(),
+                "El Hobbit".to_string(),
+                "Tolkien".to_string(),
+                255,
+                Generos::Otros,
+            ),
+        ];
+
+        let mut docente = Cliente::new(
+            "Pedro".to_string(),
+            "2212604821".to_string(),
+            "test@hotmail.com".to_string(),
+        );
+        docente.set_categoria(CategoriaCliente::Docente);
+
+        let nahuel = Cliente::new(
+            "Nahuel".to_string(),
+            "2218570392".to_string(),
+            "example@gmail.com".to_string(),
+        );
+
+        // Sin archivo de clientes el registro falla al guardar, pero los clientes quedan agregados
+        for c in [nahuel, docente] {
+            biblioteca.registrar_cliente(c).expect_err("");
+        }
+        let clientes: Vec<u32> = biblioteca.clientes.iter().map(|c| c.id).collect();
+
+        let fecha_alta = Fecha::new(1, 12, 2023);
+        for l in &libros {
+            biblioteca.agregar_copia(l.clone()).expect_err("");
+            for _i in 0..3 {
+                biblioteca
+                    .incrementar_cantidad_copias(l, &fecha_alta)
+                    .expect_err("");
+            }
+        }
+
+        // Prestamos de 14 dias para Nahuel y de 30 para Pedro
+
+        let prestamos = [
+            (0, 0, Fecha::new(1, 3, 2024)),
+            (2, 0, Fecha::new(5, 3, 2024)),
+            (2, 1, Fecha::new(5, 3, 2024)),
+            (1, 1, Fecha::new(10, 3, 2024)),
+            (2, 0, Fecha::new(20, 3, 2024)),
+        ];
+
+        for (libro, cliente, fecha) in prestamos {
+            biblioteca
+                .realizar_prestamo(libros[libro].clone(), clientes[cliente], fecha)
+                .expect_err("");
+        }
+
+        // Nahuel devuelve 1984 con 4 dias de atraso y Pedro devuelve El Hobbit a tiempo
+
+        biblioteca
+            .devolver_libro(&libros[0], clientes[0], Fecha::new(19, 3, 2024))
+            .expect_err("");
+        biblioteca
+            .devolver_libro(&libros[2], clientes[1], Fecha::new(25, 3, 2024))
+            .expect_err("");
+
+        (biblioteca, libros, clientes)
+    }
+
+    #[test]
+    fn test_mas_prestados_e_historial() {
+        let (biblioteca, libros, clientes) = crear_biblioteca();
+
+        let mas_prestados = biblioteca.libros_mas_prestados(2);
+        assert_eq!(mas_prestados.len(), 2);
+        assert_eq!(mas_prestados[0], (&libros[2], 3));
+        assert_eq!(mas_prestados[1].1, 1);
+        assert_eq!(mas_prestados[1].0.titulo, "1984"); // Desempate por titulo
+
+        assert_eq!(
+            biblioteca.generos_mas_prestados(),
+            vec![(Generos::Otros, 4), (Generos::Novela, 1)]
+        );
+
+        let historial = biblioteca.historial_cliente(clientes[0]);
+        assert_eq!(historial.len(), 3);
+        assert!(historial.first().unwrap().fue_devuelto);
+        assert_eq!(biblioteca.historial_cliente(clientes[1]).len(), 2);
+    }
+
+    #[test]
+    fn test_avisos_vencimiento() {
+        let (biblioteca, _, clientes) = crear_biblioteca();
+
+        // Los prestamos devueltos no se incluyen, aunque hayan vencido
+
+        assert!(biblioteca
+            .prestamos_vencidos(&Fecha::new(19, 3, 2024))
+            .iter()
+            .all(|p| !p.fue_devuelto));
+        assert_eq!(
+            biblioteca
+                .prestamos_a_vencer(20, &Fecha::new(20, 3, 2024))
+                .len(),
+            3
+        );
+
+        let avisos = biblioteca.avisos_vencimiento(&Fecha::new(22, 3, 2024));
+        assert_eq!(avisos.len(), 1);
+        assert_eq!(avisos[0].cliente.id, clientes[0]);
+        assert_eq!(avisos[0].prestamos.len(), 1);
+        assert_eq!(avisos[0].multa_estimada(), 3.0 * MULTA_DIARIA);
+
+        // Un aviso por cliente, con los prestamos del mas antiguo al mas reciente
+
+        let avisos = biblioteca.avisos_vencimiento(&Fecha::new(15, 4, 2024));
+        assert_eq!(avisos.len(), 2);
+
+        let aviso = avisos.iter().find(|a| a.cliente.id == clientes[0]).unwrap();
+        assert_eq!(aviso.prestamos.len(), 2);
+        assert!(aviso.prestamos[1]
+            .fecha_vencimiento
+            .es_mayor(&aviso.prestamos[0].fecha_vencimiento));
+
+        let texto = aviso.to_texto();
+        assert!(texto.starts_with("Estimado/a Nahuel:"));
+        assert!(texto.contains("vencidos al 15/04/2024"));
+        assert!(texto.contains("- El Hobbit (Tolkien), vencido el 19/03/2024: 27 dias de atraso"));
+        assert!(texto.contains("- El Hobbit (Tolkien), vencido el 03/04/2024: 12 dias de atraso"));
+        assert!(texto.contains("Multa acumulada a la fecha: $1950.00"));
+
+        let email = aviso.to_email();
+        assert!(email
+            .starts_with("Para: example@gmail.com\nAsunto: Biblioteca UNLP - Prestamos vencidos"));
+        assert!(email.ends_with(&texto));
+
+        assert!(biblioteca
+            .avisos_vencimiento(&Fecha::new(1, 3, 2024))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_estadisticas_circulacion() {
+        let (biblioteca, _, _) = crear_biblioteca();
+
+        let marzo =
+            biblioteca.estadisticas_circulacion(&Fecha::new(1, 3, 2024), &Fecha::new(31, 3, 2024));
+        assert_eq!(
+            marzo,
+            EstadisticasCirculacion {
+                prestamos: 5,
+                devoluciones: 2,
+                devoluciones_con_atraso: 1,
+                activos: 3,
+                vencidos: 1,
+                multas: 4.0 * MULTA_DIARIA,
+            }
+        );
+
+        // A mitad de mes todavia no hubo devoluciones
+
+        let quincena =
+            biblioteca.estadisticas_circulacion(&Fecha::new(1, 3, 2024), &Fecha::new(15, 3, 2024));
+        assert_eq!(quincena.prestamos, 4);
+        assert_eq!(quincena.devoluciones, 0);
+        assert_eq!(quincena.activos, 4);
+        assert_eq!(quincena.vencidos, 0);
+
+        // Fuera del rango de actividad
+
+        let mayo =
+            biblioteca.estadisticas_circulacion(&Fecha::new(1, 5, 2024), &Fecha::new(31, 5, 2024));
+        assert_eq!(mayo.prestamos, 0);
+        assert_eq!(mayo.activos, 3);
+        assert_eq!(mayo.vencidos, 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rango_invalido() {
+        let (biblioteca, _, _) = crear_biblioteca();
+
+        biblioteca.estadisticas_circulacion(&Fecha::new(2, 1, 2024), &Fecha::new(1, 1, 2024));
+    }
+}
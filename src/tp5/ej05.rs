@@ -7,29 +7,131 @@ use std::{fmt::Display, fs::File, io::prelude::*, path::Path};
 
 use crate::tp3::ej03::Fecha;
 
-struct PlanSuscripcion(f64, u8);
+mod analiticas;
 
-const BASIC_PLAN: PlanSuscripcion = PlanSuscripcion(50.0, 3);
-const CLASSIC_PLAN: PlanSuscripcion = PlanSuscripcion(80.0, 6);
-const SUPER_PLAN: PlanSuscripcion = PlanSuscripcion(100.0, 12);
+// Credito otorgado al usuario que refiere a un nuevo suscriptor
+const CREDITO_REFERIDO: f64 = 20.0;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct PlanSuscripcion {
+    nombre: String,
+    costo: f64,
+    duracion: u8,
+    pantallas: u8,
+    calidad: Calidad,
+    orden: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+enum Calidad {
+    Estandar,
+    Alta,
+    Ultra,
+}
+
+// Los planes se mantienen ordenados segun su campo orden (de menor a mayor categoria)
+#[derive(Debug, PartialEq, Clone)]
+struct CatalogoPlanes {
+    planes: Vec<PlanSuscripcion>,
+}
 
 struct StreamingRust {
-    suscripciones: HashMap<String, Suscripcion>,
+    usuarios: HashMap<u32, Usuario>,
+    suscripciones: Vec<Suscripcion>,
+    catalogo: CatalogoPlanes,
+    cupones: HashMap<String, Cupon>,
+    boveda: BovedaTokens,
     file_path: String,
 }
 
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Cupon {
+    codigo: String,
+    descuento: Descuento,
+    ciclos: u8,
+    max_usos: u32,
+    usos: u32,
+    vencimiento: Fecha,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+enum Descuento {
+    Porcentaje(f64),
+    Fijo(f64),
+}
+
+// Descuento vigente de una suscripcion y la cantidad de ciclos de facturacion que le restan
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct DescuentoAplicado {
+    descuento: Descuento,
+    ciclos_restantes: u8,
+}
+
+enum Promocion {
+    Cupon(String),
+    PruebaGratis(u32),
+    Referido(String),
+}
+
+// Contenido del archivo de datos. Un usuario puede tener multiples suscripciones a lo largo del tiempo
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DatosPlataforma {
+    usuarios: Vec<Usuario>,
+    suscripciones: Vec<Suscripcion>,
+    // Los archivos previos no guardaban cupones
+    #[serde(default)]
+    cupones: Vec<Cupon>,
+}
+
+// Formato previo del archivo: un map de email a suscripcion, con el usuario embebido en ella
+#[derive(Deserialize)]
+struct SuscripcionArchivoPrevio {
+    #[serde(flatten)]
+    suscripcion: Suscripcion,
+    usuario: Usuario,
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 struct Suscripcion {
+    #[serde(default)]
+    id_usuario: u32,
     activo: bool,
     tipo_suscripcion: TipoSuscripcion,
     costo: f64,
     duracion: u8,
     fecha_inicio: Fecha,
-    usuario: Usuario,
+    // Los campos promocionales pueden no existir en archivos generados previamente
+    #[serde(default)]
+    descuento: Option<DescuentoAplicado>,
+    #[serde(default)]
+    fin_prueba: Option<Fecha>,
+    #[serde(default)]
+    creditos: f64,
+    #[serde(default)]
+    historial: Vec<Movimiento>,
+}
+
+// Registro de cada cambio de estado de una suscripcion, utilizado por las analiticas
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct Movimiento {
+    tipo: TipoMovimiento,
+    fecha: Fecha,
+    tipo_suscripcion: TipoSuscripcion,
+    costo: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+enum TipoMovimiento {
+    Alta,
+    Upgrade,
+    Downgrade,
+    Baja,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 struct Usuario {
+    #[serde(default)]
+    id: u32,
     nombre: String,
     apellido: String,
     email: String,
@@ -37,12 +139,10 @@ struct Usuario {
     metodo_pago: MetodoPago,
 }
 
+// Nombre del plan contratado. Se serializa como un string, por lo que es compatible
+//con los archivos generados cuando los tipos eran variantes de un enum
 #[derive(Debug, PartialEq, Clone, Eq, Hash, Serialize, Deserialize)]
-enum TipoSuscripcion {
-    Basic,
-    Classic,
-    Super,
-}
+struct TipoSuscripcion(String);
 
 #[derive(Debug, Eq, Clone, Serialize, Deserialize)]
 enum MetodoPago {
@@ -53,12 +153,26 @@ enum MetodoPago {
     Cripto(DetallePago),
 }
 
-#[derive(Debug, PartialEq, Eq, Default, Hash, Clone, Serialize, Deserialize)]
+// La cuenta solo se conserva en memoria hasta ser tokenizada; en el archivo de suscripciones se
+//guardan el token y los ultimos digitos. Se sigue leyendo para migrar archivos previos
+#[derive(PartialEq, Eq, Default, Hash, Clone, Serialize, Deserialize)]
 struct DetallePago {
+    #[serde(default, skip_serializing)]
     cuenta: String,
+    #[serde(default)]
+    token: String,
+    #[serde(default)]
+    ultimos_digitos: String,
     titular: String,
 }
 
+// Almacena la relacion token -> cuenta en un archivo separado de los datos de la plataforma
+#[derive(Debug, Default)]
+struct BovedaTokens {
+    tokens: HashMap<String, String>,
+    path: String,
+}
+
 impl Hash for MetodoPago {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         discriminant(self).hash(state);
@@ -82,35 +196,333 @@ impl MetodoPago {
             (MetodoPago::Cripto(Default::default()), 0),
         ])
     }
+
+    fn get_detalle(&self) -> Option<&DetallePago> {
+        match self {
+            MetodoPago::Efectivo => None,
+            MetodoPago::MercadoPago(d)
+            | MetodoPago::Credito(d)
+            | MetodoPago::Transferencia(d)
+            | MetodoPago::Cripto(d) => Some(d),
+        }
+    }
+
+    // Reemplaza la cuenta por un token de la boveda. Devuelve true si hubo que tokenizar
+    fn tokenizar(&mut self, boveda: &mut BovedaTokens) -> bool {
+        match self.get_detalle_mut() {
+            Some(d) if !d.esta_tokenizado() && !d.cuenta.is_empty() => {
+                d.token = boveda.tokenizar(&d.cuenta);
+                d.ultimos_digitos = DetallePago::calcular_ultimos_digitos(&d.cuenta);
+                d.cuenta.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn get_detalle_mut(&mut self) -> Option<&mut DetallePago> {
+        match self {
+            MetodoPago::Efectivo => None,
+            MetodoPago::MercadoPago(d)
+            | MetodoPago::Credito(d)
+            | MetodoPago::Transferencia(d)
+            | MetodoPago::Cripto(d) => Some(d),
+        }
+    }
+
+    fn es_valido(&self) -> bool {
+        match self {
+            MetodoPago::Efectivo => true,
+            MetodoPago::MercadoPago(d) => MetodoPago::es_alias_valido(&d.cuenta),
+            MetodoPago::Credito(d) => MetodoPago::es_tarjeta_valida(&d.cuenta),
+            MetodoPago::Transferencia(d) => MetodoPago::es_cbu_valido(&d.cuenta),
+            MetodoPago::Cripto(d) => MetodoPago::es_billetera_valida(&d.cuenta),
+        }
+    }
+
+    // Algoritmo de Luhn: desde la derecha se duplica cada segundo digito y la suma debe ser multiplo de 10
+    fn es_tarjeta_valida(numero: &str) -> bool {
+        let digitos: Vec<u32> = numero
+            .chars()
+            .filter(|c| *c != ' ' && *c != '-')
+            .map(|c| c.to_digit(10).unwrap_or(10))
+            .collect();
+
+        if digitos.len() < 13 || digitos.len() > 19 || digitos.contains(&10) {
+            return false;
+        }
+
+        let suma: u32 = digitos
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, d)| match i % 2 {
+                1 if d * 2 > 9 => d * 2 - 9,
+                1 => d * 2,
+                _ => *d,
+            })
+            .sum();
+
+        suma.is_multiple_of(10)
+    }
+
+    // CBU y CVU: 22 digitos en dos bloques (8 y 14), cada uno terminado en su digito verificador
+    fn es_cbu_valido(cbu: &str) -> bool {
+        let digitos: Vec<u32> = cbu.chars().filter_map(|c| c.to_digit(10)).collect();
+
+        if cbu.len() != 22 || digitos.len() != 22 {
+            return false;
+        }
+
+        let verificar_bloque = |bloque: &[u32], pesos: &[u32]| {
+            let suma: u32 = bloque.iter().zip(pesos).map(|(d, p)| d * p).sum();
+            (10 - suma % 10) % 10 == bloque[bloque.len() - 1]
+        };
+
+        verificar_bloque(&digitos[..8], &[7, 1, 3, 9, 7, 1, 3])
+            && verificar_bloque(&digitos[8..], &[3, 9, 7, 1, 3, 9, 7, 1, 3, 9, 7, 1, 3])
+    }
+
+    // Alias de 6 a 20 caracteres: letras, numeros, puntos y guiones
+    fn es_alias_valido(alias: &str) -> bool {
+        (6..=20).contains(&alias.len())
+            && alias
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    }
+
+    // Direcciones de Ethereum (0x + 40 hexadecimales) o Bitcoin (legacy en base58 o bech32)
+    fn es_billetera_valida(direccion: &str) -> bool {
+        if let Some(hex) = direccion.strip_prefix("0x") {
+            return hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit());
+        }
+
+        if let Some(resto) = direccion.strip_prefix("bc1") {
+            return (11..=71).contains(&resto.len())
+                && resto
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+        }
+
+        (direccion.starts_with('1') || direccion.starts_with('3'))
+            && (26..=35).contains(&direccion.len())
+            && direccion
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c))
+    }
+}
+
+impl Display for MetodoPago {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nombre = match self {
+            MetodoPago::Efectivo => return write!(f, "Efectivo"),
+            MetodoPago::MercadoPago(_) => "MercadoPago",
+            MetodoPago::Credito(_) => "Credito",
+            MetodoPago::Transferencia(_) => "Transferencia",
+            MetodoPago::Cripto(_) => "Cripto",
+        };
+
+        match self.get_detalle() {
+            Some(d) => write!(f, "{} ({})", nombre, d.get_cuenta_enmascarada()),
+            None => write!(f, "{}", nombre),
+        }
+    }
 }
 
 impl TipoSuscripcion {
-    fn get_tabla_tipos() -> HashMap<Self, i32> {
-        HashMap::from([
-            (TipoSuscripcion::Basic, 0),
-            (TipoSuscripcion::Classic, 0),
-            (TipoSuscripcion::Super, 0),
-        ])
+    fn new(nombre: &str) -> TipoSuscripcion {
+        TipoSuscripcion(nombre.to_string())
+    }
+
+    fn get_nombre(&self) -> &String {
+        &self.0
     }
 }
 
-impl Suscripcion {
+impl PlanSuscripcion {
     fn new(
-        tipo_suscripcion: TipoSuscripcion,
-        fecha_inicio: Fecha,
-        usuario: Usuario,
-    ) -> Suscripcion {
-        let costo_y_duracion = Suscripcion::calcular_costo_y_duracion(&tipo_suscripcion);
+        nombre: String,
+        costo: f64,
+        duracion: u8,
+        pantallas: u8,
+        calidad: Calidad,
+        orden: u32,
+    ) -> PlanSuscripcion {
+        PlanSuscripcion {
+            nombre,
+            costo,
+            duracion,
+            pantallas,
+            calidad,
+            orden,
+        }
+    }
 
-        Suscripcion {
-            activo: true,
-            tipo_suscripcion,
-            costo: costo_y_duracion.0,
-            duracion: costo_y_duracion.1,
-            fecha_inicio,
-            usuario,
+    fn get_tipo(&self) -> TipoSuscripcion {
+        TipoSuscripcion::new(&self.nombre)
+    }
+
+    fn get_costo(&self) -> f64 {
+        self.costo
+    }
+
+    fn get_duracion(&self) -> u8 {
+        self.duracion
+    }
+
+    fn get_pantallas(&self) -> u8 {
+        self.pantallas
+    }
+
+    fn get_calidad(&self) -> &Calidad {
+        &self.calidad
+    }
+}
+
+impl Default for CatalogoPlanes {
+    // Planes ofrecidos originalmente por la plataforma
+    fn default() -> Self {
+        CatalogoPlanes {
+            planes: vec![
+                PlanSuscripcion::new("Basic".to_string(), 50.0, 3, 1, Calidad::Estandar, 1),
+                PlanSuscripcion::new("Classic".to_string(), 80.0, 6, 2, Calidad::Alta, 2),
+                PlanSuscripcion::new("Super".to_string(), 100.0, 12, 4, Calidad::Ultra, 3),
+            ],
+        }
+    }
+}
+
+impl CatalogoPlanes {
+    fn new(mut planes: Vec<PlanSuscripcion>) -> Result<CatalogoPlanes, ErrorPlataforma> {
+        planes.sort_by_key(|p| p.orden);
+
+        for (i, p) in planes.iter().enumerate() {
+            let repetido = planes[i + 1..]
+                .iter()
+                .any(|otro| otro.nombre == p.nombre || otro.orden == p.orden);
+
+            if p.nombre.is_empty() || p.duracion == 0 || p.costo < 0.0 || repetido {
+                return Err(ErrorPlataforma::FormatoCatalogo);
+            }
+        }
+
+        Ok(CatalogoPlanes { planes })
+    }
+
+    fn from_archivo(path: &String) -> Result<CatalogoPlanes, ErrorPlataforma> {
+        let Ok(mut f) = File::open(path) else {
+            return Err(ErrorPlataforma::AbrirArchivo);
+        };
+
+        let mut buf = String::new();
+        if f.read_to_string(&mut buf).is_err() {
+            return Err(ErrorPlataforma::AbrirArchivo);
+        }
+
+        match serde_json::from_str::<Vec<PlanSuscripcion>>(&buf) {
+            Ok(planes) => CatalogoPlanes::new(planes),
+            Err(_) => Err(ErrorPlataforma::FormatoCatalogo),
+        }
+    }
+
+    fn get_plan(&self, tipo: &TipoSuscripcion) -> Option<&PlanSuscripcion> {
+        self.planes.iter().find(|p| p.nombre.eq(tipo.get_nombre()))
+    }
+
+    fn get_plan_superior(&self, tipo: &TipoSuscripcion) -> Option<&PlanSuscripcion> {
+        let index = self
+            .planes
+            .iter()
+            .position(|p| p.nombre.eq(tipo.get_nombre()))?;
+
+        self.planes.get(index + 1)
+    }
+
+    fn get_plan_inferior(&self, tipo: &TipoSuscripcion) -> Option<&PlanSuscripcion> {
+        let index = self
+            .planes
+            .iter()
+            .position(|p| p.nombre.eq(tipo.get_nombre()))?;
+
+        match index {
+            0 => None,
+            i => self.planes.get(i - 1),
+        }
+    }
+
+    fn get_tabla_tipos(&self) -> HashMap<TipoSuscripcion, i32> {
+        self.planes.iter().map(|p| (p.get_tipo(), 0)).collect()
+    }
+}
+
+impl Descuento {
+    fn aplicar(&self, costo: f64) -> f64 {
+        let costo_final = match self {
+            Descuento::Porcentaje(p) => costo - costo * p / 100.0,
+            Descuento::Fijo(monto) => costo - monto,
+        };
+
+        costo_final.max(0.0)
+    }
+}
+
+impl Cupon {
+    fn new(
+        codigo: String,
+        descuento: Descuento,
+        ciclos: u8,
+        max_usos: u32,
+        vencimiento: Fecha,
+    ) -> Cupon {
+        Cupon {
+            codigo,
+            descuento,
+            ciclos,
+            max_usos,
+            usos: 0,
+            vencimiento,
+        }
+    }
+
+    fn es_valido(&self, fecha_actual: &Fecha) -> bool {
+        self.usos < self.max_usos && !fecha_actual.es_mayor(&self.vencimiento)
+    }
+
+    fn get_descuento_aplicado(&self) -> DescuentoAplicado {
+        DescuentoAplicado {
+            descuento: self.descuento.clone(),
+            ciclos_restantes: self.ciclos,
         }
     }
+}
+
+impl Suscripcion {
+    fn new(plan: &PlanSuscripcion, fecha_inicio: Fecha, id_usuario: u32) -> Suscripcion {
+        let mut suscripcion = Suscripcion {
+            id_usuario,
+            activo: true,
+            tipo_suscripcion: plan.get_tipo(),
+            costo: plan.get_costo(),
+            duracion: plan.get_duracion(),
+            fecha_inicio: fecha_inicio.clone(),
+            descuento: None,
+            fin_prueba: None,
+            creditos: 0.0,
+            historial: Vec::new(),
+        };
+
+        suscripcion.registrar_movimiento(TipoMovimiento::Alta, fecha_inicio);
+        suscripcion
+    }
+
+    fn registrar_movimiento(&mut self, tipo: TipoMovimiento, fecha: Fecha) {
+        self.historial.push(Movimiento {
+            tipo,
+            fecha,
+            tipo_suscripcion: self.tipo_suscripcion.clone(),
+            costo: self.costo,
+        });
+    }
 
     fn get_tipo_suscripcion(&self) -> &TipoSuscripcion {
         &self.tipo_suscripcion
@@ -128,56 +540,75 @@ impl Suscripcion {
         &self.fecha_inicio
     }
 
-    fn get_usuario(&self) -> &Usuario {
-        &self.usuario
+    fn get_id_usuario(&self) -> u32 {
+        self.id_usuario
+    }
+
+    fn en_prueba(&self, fecha_actual: &Fecha) -> bool {
+        match &self.fin_prueba {
+            Some(f) => f.es_mayor(fecha_actual),
+            None => false,
+        }
+    }
+
+    fn get_costo_con_descuento(&self) -> f64 {
+        match &self.descuento {
+            Some(d) if d.ciclos_restantes > 0 => d.descuento.aplicar(self.costo),
+            _ => self.costo,
+        }
+    }
+
+    // Costo del proximo ciclo de facturacion, una vez aplicados prueba gratuita, descuento y creditos
+    fn get_costo_efectivo(&self, fecha_actual: &Fecha) -> f64 {
+        if self.en_prueba(fecha_actual) {
+            return 0.0;
+        }
+
+        (self.get_costo_con_descuento() - self.creditos).max(0.0)
     }
 
-    fn calcular_costo_y_duracion(tipo_suscripcion: &TipoSuscripcion) -> PlanSuscripcion {
-        match tipo_suscripcion {
-            TipoSuscripcion::Basic => BASIC_PLAN,
-            TipoSuscripcion::Classic => CLASSIC_PLAN,
-            TipoSuscripcion::Super => SUPER_PLAN,
+    // Devuelve el monto cobrado, consumiendo un ciclo del descuento y los creditos utilizados
+    fn cobrar_ciclo(&mut self, fecha_actual: &Fecha) -> f64 {
+        if self.en_prueba(fecha_actual) {
+            return 0.0;
+        }
+
+        let costo = self.get_costo_con_descuento();
+        let creditos_utilizados = self.creditos.min(costo);
+        self.creditos -= creditos_utilizados;
+
+        if let Some(d) = &mut self.descuento {
+            d.ciclos_restantes = d.ciclos_restantes.saturating_sub(1);
         }
+
+        costo - creditos_utilizados
     }
 
-    fn actualizar_datos(&mut self, tipo_suscripcion: TipoSuscripcion) {
-        let costo_y_duracion = Suscripcion::calcular_costo_y_duracion(&TipoSuscripcion::Classic);
-        self.tipo_suscripcion = tipo_suscripcion;
-        self.costo = costo_y_duracion.0;
-        self.duracion = costo_y_duracion.1;
+    fn actualizar_datos(&mut self, plan: &PlanSuscripcion, tipo_movimiento: TipoMovimiento) {
+        self.tipo_suscripcion = plan.get_tipo();
+        self.costo = plan.get_costo();
+        self.duracion = plan.get_duracion();
         self.fecha_inicio = Fecha::from(Local::now());
+        self.registrar_movimiento(tipo_movimiento, self.fecha_inicio.clone());
     }
 
-    fn upgrade_suscripcion(&mut self) -> bool {
+    fn upgrade_suscripcion(&mut self, catalogo: &CatalogoPlanes) -> bool {
         if self.activo {
-            match &self.tipo_suscripcion {
-                TipoSuscripcion::Basic => {
-                    self.actualizar_datos(TipoSuscripcion::Classic);
-                    true
-                }
-                TipoSuscripcion::Classic => {
-                    self.actualizar_datos(TipoSuscripcion::Super);
-                    true
-                }
-                TipoSuscripcion::Super => false,
+            if let Some(plan) = catalogo.get_plan_superior(&self.tipo_suscripcion) {
+                self.actualizar_datos(plan, TipoMovimiento::Upgrade);
+                return true;
             }
-        } else {
-            false
         }
+
+        false
     }
 
-    fn downgrade_suscripcion(&mut self) -> bool {
-        if self.activo {
-            match &self.tipo_suscripcion {
-                TipoSuscripcion::Super => {
-                    self.actualizar_datos(TipoSuscripcion::Classic);
-                }
-                TipoSuscripcion::Classic => {
-                    self.actualizar_datos(TipoSuscripcion::Basic);
-                }
-                TipoSuscripcion::Basic => {
-                    self.activo = false;
-                }
+    // Al bajar de categoria desde el plan mas economico, la suscripcion se desactiva
+    fn downgrade_suscripcion(&mut self, catalogo: &CatalogoPlanes) -> bool {
+        if self.activo && catalogo.get_plan(&self.tipo_suscripcion).is_some() {
+            match catalogo.get_plan_inferior(&self.tipo_suscripcion) {
+                Some(plan) => self.actualizar_datos(plan, TipoMovimiento::Downgrade),
+                None => self.cancelar_suscripcion(),
             }
 
             return true;
@@ -188,6 +619,7 @@ impl Suscripcion {
 
     fn cancelar_suscripcion(&mut self) {
         self.activo = false;
+        self.registrar_movimiento(TipoMovimiento::Baja, Fecha::from(Local::now()));
     }
 }
 
@@ -200,6 +632,7 @@ impl Usuario {
         metodo_pago: MetodoPago,
     ) -> Usuario {
         Usuario {
+            id: 0,
             nombre,
             apellido,
             email,
@@ -208,6 +641,10 @@ impl Usuario {
         }
     }
 
+    fn get_id(&self) -> u32 {
+        self.id
+    }
+
     fn get_nombre(&self) -> &String {
         &self.nombre
     }
@@ -231,52 +668,192 @@ impl Usuario {
 
 impl DetallePago {
     fn new(cuenta: String, titular: String) -> DetallePago {
-        DetallePago { cuenta, titular }
+        DetallePago {
+            cuenta,
+            token: String::new(),
+            ultimos_digitos: String::new(),
+            titular,
+        }
+    }
+
+    fn esta_tokenizado(&self) -> bool {
+        !self.token.is_empty() && self.cuenta.is_empty()
+    }
+
+    fn get_cuenta_enmascarada(&self) -> String {
+        let ultimos = match self.ultimos_digitos.is_empty() {
+            true => DetallePago::calcular_ultimos_digitos(&self.cuenta),
+            false => self.ultimos_digitos.clone(),
+        };
+
+        format!("****{}", ultimos)
+    }
+
+    fn calcular_ultimos_digitos(cuenta: &str) -> String {
+        let caracteres: Vec<char> = cuenta.chars().collect();
+
+        caracteres[caracteres.len().saturating_sub(4)..]
+            .iter()
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for DetallePago {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DetallePago")
+            .field("cuenta", &self.get_cuenta_enmascarada())
+            .field("titular", &self.titular)
+            .finish()
+    }
+}
+
+impl BovedaTokens {
+    fn new(path: String) -> BovedaTokens {
+        let mut tokens = HashMap::new();
+
+        if let Ok(mut f) = File::open(&path) {
+            let mut buf = String::new();
+            if f.read_to_string(&mut buf).is_ok() {
+                if let Ok(t) = serde_json::from_str(&buf) {
+                    tokens = t;
+                }
+            }
+        }
+
+        BovedaTokens { tokens, path }
+    }
+
+    fn actualizar_archivo(&self) -> Result<(), ErrorPlataforma> {
+        if let Ok(mut f) = File::create(&self.path) {
+            let Ok(s) = serde_json::to_string_pretty(&self.tokens) else {
+                return Err(ErrorPlataforma::FormatoSuscripciones);
+            };
+
+            let Ok(_) = f.write_all(s.as_bytes()) else {
+                return Err(ErrorPlataforma::EscribirArchivo);
+            };
+
+            return Ok(());
+        }
+
+        Err(ErrorPlataforma::CrearArchivo)
+    }
+
+    // Una misma cuenta conserva siempre el mismo token
+    fn tokenizar(&mut self, cuenta: &str) -> String {
+        if let Some((token, _)) = self.tokens.iter().find(|(_, c)| c.as_str() == cuenta) {
+            return token.clone();
+        }
+
+        let mut token = format!("tok_{:016x}", rand::random::<u64>());
+        while self.tokens.contains_key(&token) {
+            token = format!("tok_{:016x}", rand::random::<u64>());
+        }
+
+        self.tokens.insert(token.clone(), cuenta.to_string());
+        token
+    }
+
+    fn get_cuenta(&self, token: &String) -> Option<&String> {
+        self.tokens.get(token)
     }
 }
 
 impl StreamingRust {
-    fn new(file_path: String) -> StreamingRust {
-        let mut suscripciones = HashMap::new();
-        if let Ok(s) = StreamingRust::recuperar_datos_archivo(&file_path) {
-            suscripciones = s;
+    fn new(file_path: String, path_boveda: String, catalogo: CatalogoPlanes) -> StreamingRust {
+        let mut datos = DatosPlataforma::default();
+        if let Ok(d) = StreamingRust::recuperar_datos_archivo(&file_path) {
+            datos = d;
         }
 
-        StreamingRust {
-            suscripciones,
+        let mut plataforma = StreamingRust {
+            usuarios: datos.usuarios.into_iter().map(|u| (u.id, u)).collect(),
+            suscripciones: datos.suscripciones,
+            catalogo,
+            cupones: datos
+                .cupones
+                .into_iter()
+                .map(|c| (c.codigo.clone(), c))
+                .collect(),
+            boveda: BovedaTokens::new(path_boveda),
             file_path,
+        };
+
+        // Archivos previos guardaban la cuenta en texto plano: se tokeniza y se reescriben
+        let mut migrado = false;
+        for u in plataforma.usuarios.values_mut() {
+            migrado |= u.metodo_pago.tokenizar(&mut plataforma.boveda);
+        }
+
+        if migrado && plataforma.boveda.actualizar_archivo().is_ok() {
+            let _ = plataforma.actualizar_archivo();
         }
+
+        plataforma
     }
 
-    fn recuperar_datos_archivo(
-        path: &String,
-    ) -> Result<HashMap<String, Suscripcion>, ErrorPlataforma> {
+    fn recuperar_datos_archivo(path: &String) -> Result<DatosPlataforma, ErrorPlataforma> {
         if let Ok(mut f) = File::open(path) {
             let mut buf = String::new();
             f.read_to_string(&mut buf)
                 .expect("El archivo de datos posee informacion no valida");
 
-            let result: Result<HashMap<String, Suscripcion>, serde_json::Error> =
+            let result: Result<DatosPlataforma, serde_json::Error> = serde_json::from_str(&buf);
+
+            if let Ok(datos) = result {
+                return Ok(datos);
+            }
+
+            let result: Result<HashMap<String, SuscripcionArchivoPrevio>, serde_json::Error> =
                 serde_json::from_str(&buf);
 
             if let Ok(suscripciones) = result {
-                return Ok(suscripciones);
+                return Ok(StreamingRust::migrar_datos(suscripciones));
             }
         }
 
         Err(ErrorPlataforma::AbrirArchivo)
     }
 
-    fn actualizar_archivo(
-        &self,
-        suscripciones: &HashMap<String, Suscripcion>,
-    ) -> Result<(), ErrorPlataforma> {
+    // Convierte el formato previo asignando un id a cada usuario. Se ordena por email para que
+    //los ids resultantes no dependan del orden de iteracion del map
+    fn migrar_datos(suscripciones: HashMap<String, SuscripcionArchivoPrevio>) -> DatosPlataforma {
+        let mut previas: Vec<SuscripcionArchivoPrevio> = suscripciones.into_values().collect();
+        previas.sort_by(|a, b| a.usuario.email.cmp(&b.usuario.email));
+
+        let mut datos = DatosPlataforma::default();
+
+        for (i, mut previa) in previas.into_iter().enumerate() {
+            let id = i as u32 + 1;
+
+            previa.usuario.id = id;
+            previa.suscripcion.id_usuario = id;
+
+            datos.usuarios.push(previa.usuario);
+            datos.suscripciones.push(previa.suscripcion);
+        }
+
+        datos
+    }
+
+    fn actualizar_archivo(&self) -> Result<(), ErrorPlataforma> {
+        let mut usuarios: Vec<Usuario> = self.usuarios.values().cloned().collect();
+        usuarios.sort_by_key(|u| u.id);
+        let mut cupones: Vec<Cupon> = self.cupones.values().cloned().collect();
+        cupones.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+
+        let datos = DatosPlataforma {
+            usuarios,
+            suscripciones: self.suscripciones.clone(),
+            cupones,
+        };
+
         if let Ok(mut f) = File::create(&self.file_path) {
-            let Ok(s) = serde_json::to_string_pretty(&suscripciones) else {
+            let Ok(s) = serde_json::to_string_pretty(&datos) else {
                 return Err(ErrorPlataforma::FormatoSuscripciones);
             };
 
-            let Ok(_) = f.write_all(&s.as_bytes()) else {
+            let Ok(_) = f.write_all(s.as_bytes()) else {
                 return Err(ErrorPlataforma::EscribirArchivo);
             };
 
@@ -286,10 +863,45 @@ impl StreamingRust {
         Err(ErrorPlataforma::CrearArchivo)
     }
 
+    fn get_usuario(&self, email: &String) -> Option<&Usuario> {
+        self.usuarios.values().find(|u| u.email.eq(email))
+    }
+
+    // Posicion de la suscripcion mas reciente del usuario
+    fn get_pos_suscripcion(&self, email: &String) -> Option<usize> {
+        let id = self.get_usuario(email)?.id;
+
+        self.suscripciones.iter().rposition(|s| s.id_usuario == id)
+    }
+
     fn get_suscripcion(&self, email: &String) -> Option<&Suscripcion> {
-        self.suscripciones.get(email)
+        self.suscripciones.get(self.get_pos_suscripcion(email)?)
+    }
+
+    fn get_suscripcion_mut(&mut self, email: &String) -> Option<&mut Suscripcion> {
+        let pos = self.get_pos_suscripcion(email)?;
+
+        self.suscripciones.get_mut(pos)
     }
 
+    fn get_historial_suscripciones(&self, email: &String) -> Vec<&Suscripcion> {
+        match self.get_usuario(email) {
+            Some(u) => self
+                .suscripciones
+                .iter()
+                .filter(|s| s.id_usuario == u.id)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn get_metodo_pago(&self, suscripcion: &Suscripcion) -> Option<&MetodoPago> {
+        self.usuarios
+            .get(&suscripcion.id_usuario)
+            .map(|u| u.get_metodo_pago())
+    }
+
+    // Un usuario inactivo que vuelve a darse de alta genera una nueva suscripcion, conservando las previas
     fn alta_usuario(
         &mut self,
         nombre: String,
@@ -299,33 +911,68 @@ impl StreamingRust {
         metodo_pago: MetodoPago,
         tipo_suscripcion: TipoSuscripcion,
     ) -> Result<(), ErrorPlataforma> {
-        let searched_suscription = self.suscripciones.get_mut(&email);
-
-        if let Some(sub) = searched_suscription {
-            if !sub.activo {
-                sub.activo = true;
-            } else {
-                return Err(ErrorPlataforma::AltaSucripcion);
-            }
-        } else {
-            let suscription = Suscripcion::new(
-                tipo_suscripcion,
-                Fecha::from(Local::now()),
-                Usuario::new(nombre, apellido, email, telefono, metodo_pago),
-            );
-            self.suscripciones
-                .insert(suscription.get_usuario().get_email().clone(), suscription);
+        if self.get_suscripcion(&email).is_some_and(|s| s.activo) {
+            return Err(ErrorPlataforma::AltaSucripcion);
         }
 
-        self.actualizar_archivo(&self.suscripciones)
+        let Some(plan) = self.catalogo.get_plan(&tipo_suscripcion) else {
+            return Err(ErrorPlataforma::PlanInexistente);
+        };
+
+        let mut tokenizado = false;
+
+        let id = match self.get_usuario(&email) {
+            Some(u) => u.id,
+            None => {
+                if !metodo_pago.es_valido() {
+                    return Err(ErrorPlataforma::MetodoPagoInvalido);
+                }
+
+                let mut usuario = Usuario::new(nombre, apellido, email, telefono, metodo_pago);
+                usuario.id = self.usuarios.keys().max().unwrap_or(&0) + 1;
+                tokenizado = usuario.metodo_pago.tokenizar(&mut self.boveda);
+
+                let id = usuario.id;
+                self.usuarios.insert(id, usuario);
+                id
+            }
+        };
+
+        self.suscripciones
+            .push(Suscripcion::new(plan, Fecha::from(Local::now()), id));
+
+        // La boveda se persiste antes que los datos para que ningun token quede sin su cuenta
+        if tokenizado {
+            self.boveda.actualizar_archivo()?;
+        }
+
+        self.actualizar_archivo()
     }
 
-    fn upgrade_usuario(&mut self, user_email: &String) -> Result<(), ErrorPlataforma> {
-        let searched_suscription = self.suscripciones.get_mut(user_email);
+    fn modificar_email(
+        &mut self,
+        email_actual: &String,
+        email_nuevo: String,
+    ) -> Result<(), ErrorPlataforma> {
+        if self.get_usuario(&email_nuevo).is_some() {
+            return Err(ErrorPlataforma::ModificacionUsuario);
+        }
 
-        if let Some(s) = searched_suscription {
-            if s.upgrade_suscripcion() {
-                return self.actualizar_archivo(&self.suscripciones);
+        let Some(id) = self.get_usuario(email_actual).map(|u| u.id) else {
+            return Err(ErrorPlataforma::ModificacionUsuario);
+        };
+
+        if let Some(u) = self.usuarios.get_mut(&id) {
+            u.email = email_nuevo;
+        }
+
+        self.actualizar_archivo()
+    }
+
+    fn upgrade_usuario(&mut self, user_email: &String) -> Result<(), ErrorPlataforma> {
+        if let Some(pos) = self.get_pos_suscripcion(user_email) {
+            if self.suscripciones[pos].upgrade_suscripcion(&self.catalogo) {
+                return self.actualizar_archivo();
             }
         }
 
@@ -333,24 +980,152 @@ impl StreamingRust {
     }
 
     fn downgrade_usuario(&mut self, user_email: &String) -> Result<(), ErrorPlataforma> {
-        let searched_suscription = self.suscripciones.get_mut(user_email);
-
-        if let Some(s) = searched_suscription {
-            if s.downgrade_suscripcion() {
-                return self.actualizar_archivo(&self.suscripciones);
+        if let Some(pos) = self.get_pos_suscripcion(user_email) {
+            if self.suscripciones[pos].downgrade_suscripcion(&self.catalogo) {
+                return self.actualizar_archivo();
             }
         }
 
         Err(ErrorPlataforma::ModificacionSuscripcion)
     }
 
+    fn registrar_cupon(&mut self, cupon: Cupon) -> Result<(), ErrorPlataforma> {
+        if self.cupones.contains_key(&cupon.codigo) {
+            return Err(ErrorPlataforma::PromocionInvalida);
+        }
+
+        self.cupones.insert(cupon.codigo.clone(), cupon);
+        self.actualizar_archivo()
+    }
+
+    // La prueba gratuita y los referidos solo aplican a usuarios que nunca estuvieron suscriptos
+    fn validar_promocion(
+        &self,
+        email: &String,
+        promocion: &Promocion,
+        fecha_actual: &Fecha,
+    ) -> Result<(), ErrorPlataforma> {
+        let es_nuevo = self.get_usuario(email).is_none();
+
+        let es_valida = match promocion {
+            Promocion::Cupon(codigo) => self
+                .cupones
+                .get(codigo)
+                .is_some_and(|c| c.es_valido(fecha_actual)),
+            Promocion::PruebaGratis(dias) => es_nuevo && *dias > 0,
+            Promocion::Referido(email_referente) => {
+                es_nuevo
+                    && email_referente != email
+                    && self
+                        .get_suscripcion(email_referente)
+                        .is_some_and(|s| s.activo)
+            }
+        };
+
+        match es_valida {
+            true => Ok(()),
+            false => Err(ErrorPlataforma::PromocionInvalida),
+        }
+    }
+
+    fn aplicar_promocion(&mut self, email: &String, promocion: Promocion, fecha_actual: &Fecha) {
+        match promocion {
+            Promocion::Cupon(codigo) => {
+                if let Some(cupon) = self.cupones.get_mut(&codigo) {
+                    cupon.usos += 1;
+                    let descuento = cupon.get_descuento_aplicado();
+
+                    if let Some(s) = self.get_suscripcion_mut(email) {
+                        s.descuento = Some(descuento);
+                    }
+                }
+            }
+            Promocion::PruebaGratis(dias) => {
+                if let Some(s) = self.get_suscripcion_mut(email) {
+                    let mut fin_prueba = fecha_actual.clone();
+                    fin_prueba.sumar_dias(dias);
+                    s.fin_prueba = Some(fin_prueba);
+                }
+            }
+            Promocion::Referido(email_referente) => {
+                if let Some(s) = self.get_suscripcion_mut(&email_referente) {
+                    s.creditos += CREDITO_REFERIDO;
+                }
+            }
+        }
+    }
+
+    fn alta_usuario_con_promocion(
+        &mut self,
+        usuario: Usuario,
+        tipo_suscripcion: TipoSuscripcion,
+        promocion: Promocion,
+    ) -> Result<(), ErrorPlataforma> {
+        let fecha_actual = Fecha::from(Local::now());
+        let email = usuario.email.clone();
+
+        self.validar_promocion(&email, &promocion, &fecha_actual)?;
+
+        let resultado = self.alta_usuario(
+            usuario.nombre,
+            usuario.apellido,
+            usuario.email,
+            usuario.telefono,
+            usuario.metodo_pago,
+            tipo_suscripcion,
+        );
+
+        // Si el alta falla por cualquier motivo, la promocion no se consume
+        resultado?;
+        self.aplicar_promocion(&email, promocion, &fecha_actual);
+
+        self.actualizar_archivo()
+    }
+
+    fn upgrade_usuario_con_cupon(
+        &mut self,
+        user_email: &String,
+        codigo: String,
+    ) -> Result<(), ErrorPlataforma> {
+        let fecha_actual = Fecha::from(Local::now());
+        let promocion = Promocion::Cupon(codigo);
+
+        self.validar_promocion(user_email, &promocion, &fecha_actual)?;
+
+        self.upgrade_usuario(user_email)?;
+        self.aplicar_promocion(user_email, promocion, &fecha_actual);
+
+        self.actualizar_archivo()
+    }
+
+    fn cobrar_suscripcion(
+        &mut self,
+        user_email: &String,
+        fecha_actual: &Fecha,
+    ) -> Result<f64, ErrorPlataforma> {
+        let Some(s) = self.get_suscripcion_mut(user_email) else {
+            return Err(ErrorPlataforma::CobroSuscripcion);
+        };
+
+        if !s.activo {
+            return Err(ErrorPlataforma::CobroSuscripcion);
+        }
+
+        let monto = s.cobrar_ciclo(fecha_actual);
+
+        match self.actualizar_archivo() {
+            Ok(_) => Ok(monto),
+            Err(e) => Err(e),
+        }
+    }
+
     fn baja_usuario(&mut self, user_email: &String) -> Result<(), ErrorPlataforma> {
-        let searched_suscription = self.suscripciones.get_mut(user_email);
+        let searched_suscription = self.get_suscripcion_mut(user_email);
 
         if let Some(s) = searched_suscription {
             if s.activo {
                 s.cancelar_suscripcion();
-                return self.actualizar_archivo(&self.suscripciones);
+                return self.actualizar_archivo();
             }
         }
 
@@ -383,11 +1158,31 @@ impl StreamingRust {
         None
     }
 
+    // Suma de los costos efectivos del proximo ciclo de todas las suscripciones activas
+    fn recaudacion_activa(&self, fecha_actual: &Fecha) -> f64 {
+        self.suscripciones
+            .iter()
+            .filter(|s| s.activo)
+            .map(|s| s.get_costo_efectivo(fecha_actual))
+            .sum()
+    }
+
+    fn cantidad_suscripciones_en_prueba(&self, fecha_actual: &Fecha) -> usize {
+        self.suscripciones
+            .iter()
+            .filter(|s| s.activo && s.en_prueba(fecha_actual))
+            .count()
+    }
+
     fn metodo_pago_activo_mas_utilizado(&self) -> Option<MetodoPago> {
         let mut metodos = MetodoPago::get_tabla_metodos();
 
-        self.suscripciones.values().for_each(|s| match s.activo {
-            true => *metodos.get_mut(&s.get_usuario().get_metodo_pago()).unwrap() += 1,
+        self.suscripciones.iter().for_each(|s| match s.activo {
+            true => {
+                if let Some(m) = self.get_metodo_pago(s) {
+                    *metodos.get_mut(m).unwrap() += 1;
+                }
+            }
             false => (),
         });
 
@@ -395,10 +1190,14 @@ impl StreamingRust {
     }
 
     fn suscripcion_activa_mas_contratada(&self) -> Option<TipoSuscripcion> {
-        let mut tipos = TipoSuscripcion::get_tabla_tipos();
+        let mut tipos = self.catalogo.get_tabla_tipos();
 
-        self.suscripciones.values().for_each(|s| match s.activo {
-            true => *tipos.get_mut(&s.tipo_suscripcion).unwrap() += 1,
+        self.suscripciones.iter().for_each(|s| match s.activo {
+            true => {
+                if let Some(cant) = tipos.get_mut(&s.tipo_suscripcion) {
+                    *cant += 1;
+                }
+            }
             false => (),
         });
 
@@ -408,19 +1207,23 @@ impl StreamingRust {
     fn metodo_pago_general_mas_utilizado(&self) -> Option<MetodoPago> {
         let mut metodos = MetodoPago::get_tabla_metodos();
 
-        self.suscripciones
-            .values()
-            .for_each(|s| *metodos.get_mut(s.get_usuario().get_metodo_pago()).unwrap() += 1);
+        self.suscripciones.iter().for_each(|s| {
+            if let Some(m) = self.get_metodo_pago(s) {
+                *metodos.get_mut(m).unwrap() += 1;
+            }
+        });
 
         StreamingRust::determinar_maximo_metodo_pago(&metodos)
     }
 
     fn suscripcion_general_mas_contratada(&self) -> Option<TipoSuscripcion> {
-        let mut tipos = TipoSuscripcion::get_tabla_tipos();
+        let mut tipos = self.catalogo.get_tabla_tipos();
 
-        self.suscripciones
-            .values()
-            .for_each(|s| *tipos.get_mut(&s.tipo_suscripcion).unwrap() += 1);
+        self.suscripciones.iter().for_each(|s| {
+            if let Some(cant) = tipos.get_mut(&s.tipo_suscripcion) {
+                *cant += 1;
+            }
+        });
 
         StreamingRust::determinar_maximo_suscripciones(&tipos)
     }
@@ -435,6 +1238,12 @@ enum ErrorPlataforma {
     AltaSucripcion,
     BajaSuscripcion,
     ModificacionSuscripcion,
+    PlanInexistente,
+    FormatoCatalogo,
+    PromocionInvalida,
+    CobroSuscripcion,
+    ModificacionUsuario,
+    MetodoPagoInvalido,
 }
 
 impl Display for ErrorPlataforma {
@@ -456,6 +1265,27 @@ impl Display for ErrorPlataforma {
                 f,
                 "Error al intentar cambiar el estado de suscripcion de un usuario"
             ),
+            ErrorPlataforma::PlanInexistente => {
+                write!(f, "El plan solicitado no existe en el catalogo")
+            }
+            ErrorPlataforma::FormatoCatalogo => {
+                write!(f, "El catalogo de planes posee informacion no valida")
+            }
+            ErrorPlataforma::PromocionInvalida => {
+                write!(
+                    f,
+                    "La promocion no es valida o no puede aplicarse al usuario"
+                )
+            }
+            ErrorPlataforma::CobroSuscripcion => {
+                write!(f, "Error al intentar cobrar la suscripcion de un usuario")
+            }
+            ErrorPlataforma::ModificacionUsuario => {
+                write!(f, "Error al intentar modificar los datos de un usuario")
+            }
+            ErrorPlataforma::MetodoPagoInvalido => {
+                write!(f, "Los datos del metodo de pago no son validos")
+            }
         }
     }
 }
@@ -467,7 +1297,10 @@ mod test {
     fn alta_y_baja_usuario() {
         // Creo suscripcion con usuario (metodo de pago con detalle)
 
-        let detalle = DetallePago::new("462942".to_string(), "Luna".to_string());
+        let detalle = DetallePago::new(
+            "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+            "Luna".to_string(),
+        );
         let user = Usuario::new(
             "Nahuel".to_string(),
             "Luna".to_string(),
@@ -475,10 +1308,11 @@ mod test {
             "2217482148".to_string(),
             MetodoPago::Cripto(detalle),
         );
+        let catalogo = CatalogoPlanes::default();
         let suscripcion = Suscripcion::new(
-            TipoSuscripcion::Classic,
+            catalogo.get_plan(&TipoSuscripcion::new("Classic")).unwrap(),
             Fecha::from(Local::now()),
-            user.clone(),
+            user.get_id(),
         );
 
         // Compruebo cada campo
@@ -486,24 +1320,27 @@ mod test {
         assert!(suscripcion.activo);
         assert_eq!(
             suscripcion.get_tipo_suscripcion(),
-            &TipoSuscripcion::Classic
+            &TipoSuscripcion::new("Classic")
         );
         assert_eq!(suscripcion.get_costo(), 80.0);
         assert_eq!(suscripcion.get_duracion(), 6);
         assert_eq!(suscripcion.get_fecha_inicio(), &Fecha::from(Local::now()));
 
-        assert_eq!(suscripcion.get_usuario().get_nombre(), "Nahuel");
-        assert_eq!(suscripcion.get_usuario().get_apellido(), "Luna");
-        assert_eq!(suscripcion.get_usuario().get_email(), "example@gmail.com");
-        assert_eq!(suscripcion.get_usuario().get_telefono(), "2217482148");
+        assert_eq!(suscripcion.get_id_usuario(), 0); // El id lo asigna la plataforma en el alta
+
+        assert_eq!(user.get_nombre(), "Nahuel");
+        assert_eq!(user.get_apellido(), "Luna");
+        assert_eq!(user.get_email(), "example@gmail.com");
+        assert_eq!(user.get_telefono(), "2217482148");
         assert_eq!(
-            suscripcion.get_usuario().get_metodo_pago(),
+            user.get_metodo_pago(),
             &MetodoPago::Cripto(Default::default())
         );
 
         // Instancio StreamingRust e intento baja con estructura vacia
 
-        let mut stream_rust = StreamingRust::new(Default::default());
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
 
         assert_eq!(
             stream_rust
@@ -520,7 +1357,7 @@ mod test {
             user.email.clone(),
             user.telefono.clone(),
             user.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
 
         // Intento agregar nuevamente al mismo usuario --> no es posible
@@ -533,7 +1370,7 @@ mod test {
                     user.email.clone(),
                     user.telefono.clone(),
                     user.metodo_pago.clone(),
-                    TipoSuscripcion::Classic,
+                    TipoSuscripcion::new("Classic"),
                 )
                 .unwrap_err(),
             ErrorPlataforma::AltaSucripcion
@@ -549,7 +1386,7 @@ mod test {
                     "example@gmail.com".to_string(),
                     "2218488843".to_string(),
                     MetodoPago::Efectivo,
-                    TipoSuscripcion::Super,
+                    TipoSuscripcion::new("Super"),
                 )
                 .unwrap_err(),
             ErrorPlataforma::AltaSucripcion
@@ -588,7 +1425,7 @@ mod test {
                     user.email.clone(),
                     user.telefono.clone(),
                     user.metodo_pago.clone(),
-                    TipoSuscripcion::Classic,
+                    TipoSuscripcion::new("Classic"),
                 )
                 .unwrap_err(),
             ErrorPlataforma::AltaSucripcion
@@ -599,7 +1436,8 @@ mod test {
     fn test_upgrade_y_downgrade_suscripcion() {
         // Prueba con estructura vacia
 
-        let mut stream_rust = StreamingRust::new(Default::default());
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
 
         assert_eq!(
             stream_rust
@@ -616,7 +1454,10 @@ mod test {
 
         // Creacion estructuras
 
-        let detalle1 = DetallePago::new("462942".to_string(), "Luna".to_string());
+        let detalle1 = DetallePago::new(
+            "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+            "Luna".to_string(),
+        );
         let user1 = Usuario::new(
             "Nahuel".to_string(),
             "Luna".to_string(),
@@ -633,7 +1474,10 @@ mod test {
             MetodoPago::Efectivo,
         );
 
-        let detalle3 = DetallePago::new("75302".to_string(), "Perez".to_string());
+        let detalle3 = DetallePago::new(
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string(),
+            "Perez".to_string(),
+        );
         let user3 = Usuario::new(
             "German".to_string(),
             "Perez".to_string(),
@@ -648,7 +1492,7 @@ mod test {
             user1.email.clone(),
             user1.telefono.clone(),
             user1.metodo_pago.clone(),
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
         stream_rust.alta_usuario(
             user2.nombre.clone(),
@@ -656,7 +1500,7 @@ mod test {
             user2.email.clone(),
             user2.telefono.clone(),
             user2.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
         stream_rust.alta_usuario(
             user3.nombre.clone(),
@@ -664,7 +1508,7 @@ mod test {
             user3.email.clone(),
             user3.telefono.clone(),
             user3.metodo_pago.clone(),
-            TipoSuscripcion::Super,
+            TipoSuscripcion::new("Super"),
         );
 
         // Upgrade usuario Basic, Classic y Super
@@ -678,7 +1522,7 @@ mod test {
                 .get_suscripcion(&user1.email)
                 .unwrap()
                 .get_tipo_suscripcion(),
-            &TipoSuscripcion::Classic
+            &TipoSuscripcion::new("Classic")
         );
 
         assert_ne!(
@@ -690,7 +1534,7 @@ mod test {
                 .get_suscripcion(&user2.email)
                 .unwrap()
                 .get_tipo_suscripcion(),
-            &TipoSuscripcion::Super
+            &TipoSuscripcion::new("Super")
         );
 
         assert_eq!(
@@ -702,7 +1546,7 @@ mod test {
                 .get_suscripcion(&user3.email)
                 .unwrap()
                 .get_tipo_suscripcion(),
-            &TipoSuscripcion::Super
+            &TipoSuscripcion::new("Super")
         );
 
         // Downgrade usuario Classic y Super
@@ -716,7 +1560,7 @@ mod test {
                 .get_suscripcion(&user1.email)
                 .unwrap()
                 .get_tipo_suscripcion(),
-            &TipoSuscripcion::Basic
+            &TipoSuscripcion::new("Basic")
         );
 
         assert_ne!(
@@ -728,7 +1572,7 @@ mod test {
                 .get_suscripcion(&user2.email)
                 .unwrap()
                 .get_tipo_suscripcion(),
-            &TipoSuscripcion::Classic
+            &TipoSuscripcion::new("Classic")
         );
 
         // Downgrade usuario Basic
@@ -771,14 +1615,18 @@ mod test {
     fn test_metodo_pago_mas_utilizado() {
         // Prueba con estructura vacia
 
-        let mut stream_rust = StreamingRust::new(Default::default());
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
 
         assert_eq!(stream_rust.metodo_pago_activo_mas_utilizado(), None);
         assert_eq!(stream_rust.metodo_pago_general_mas_utilizado(), None);
 
         // Creacion estructuras
 
-        let detalle1 = DetallePago::new("462942".to_string(), "Luna".to_string());
+        let detalle1 = DetallePago::new(
+            "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+            "Luna".to_string(),
+        );
         let user1 = Usuario::new(
             "Nahuel".to_string(),
             "Luna".to_string(),
@@ -795,7 +1643,10 @@ mod test {
             MetodoPago::Efectivo,
         );
 
-        let detalle3 = DetallePago::new("75302".to_string(), "Perez".to_string());
+        let detalle3 = DetallePago::new(
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string(),
+            "Perez".to_string(),
+        );
         let user3 = Usuario::new(
             "German".to_string(),
             "Perez".to_string(),
@@ -810,7 +1661,7 @@ mod test {
             user1.email.clone(),
             user1.telefono.clone(),
             user1.metodo_pago.clone(),
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
         stream_rust.alta_usuario(
             user2.nombre.clone(),
@@ -818,7 +1669,7 @@ mod test {
             user2.email.clone(),
             user2.telefono.clone(),
             user2.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
         stream_rust.alta_usuario(
             user3.nombre.clone(),
@@ -826,7 +1677,7 @@ mod test {
             user3.email.clone(),
             user3.telefono.clone(),
             user3.metodo_pago.clone(),
-            TipoSuscripcion::Super,
+            TipoSuscripcion::new("Super"),
         );
 
         // Prueba con 3 suscripciones para activos
@@ -847,7 +1698,7 @@ mod test {
             "juancame@hotmail.com".to_string(),
             "0117470202".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
         stream_rust.alta_usuario(
             "Romeo".to_string(),
@@ -855,7 +1706,7 @@ mod test {
             "romeosan@yahoo.com".to_string(),
             "2217740022".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
 
         // Prueba con 5 usuarios para activos
@@ -909,14 +1760,18 @@ mod test {
     fn test_tipo_suscripcion_mas_contratada() {
         // Prueba con estructura vacia
 
-        let mut stream_rust = StreamingRust::new(Default::default());
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
 
         assert_eq!(stream_rust.suscripcion_activa_mas_contratada(), None);
         assert_eq!(stream_rust.suscripcion_general_mas_contratada(), None);
 
         // Creacion estructuras
 
-        let detalle1 = DetallePago::new("462942".to_string(), "Luna".to_string());
+        let detalle1 = DetallePago::new(
+            "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+            "Luna".to_string(),
+        );
         let user1 = Usuario::new(
             "Nahuel".to_string(),
             "Luna".to_string(),
@@ -933,7 +1788,10 @@ mod test {
             MetodoPago::Efectivo,
         );
 
-        let detalle3 = DetallePago::new("75302".to_string(), "Perez".to_string());
+        let detalle3 = DetallePago::new(
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string(),
+            "Perez".to_string(),
+        );
         let user3 = Usuario::new(
             "German".to_string(),
             "Perez".to_string(),
@@ -948,7 +1806,7 @@ mod test {
             user1.email.clone(),
             user1.telefono.clone(),
             user1.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
         stream_rust.alta_usuario(
             user2.nombre.clone(),
@@ -956,7 +1814,7 @@ mod test {
             user2.email.clone(),
             user2.telefono.clone(),
             user2.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
         stream_rust.alta_usuario(
             user3.nombre.clone(),
@@ -964,14 +1822,14 @@ mod test {
             user3.email.clone(),
             user3.telefono.clone(),
             user3.metodo_pago.clone(),
-            TipoSuscripcion::Super,
+            TipoSuscripcion::new("Super"),
         );
 
         // Prueba con 3 suscripciones para activos
 
         assert_eq!(
             stream_rust.suscripcion_activa_mas_contratada().unwrap(),
-            TipoSuscripcion::Classic
+            TipoSuscripcion::new("Classic")
         );
 
         // Alta dos usuarios mas
@@ -982,7 +1840,7 @@ mod test {
             "juancame@hotmail.com".to_string(),
             "0117470202".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
         stream_rust.alta_usuario(
             "Romeo".to_string(),
@@ -990,14 +1848,14 @@ mod test {
             "romeosan@yahoo.com".to_string(),
             "2217740022".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
 
         // Prueba con 5 usuarios para activos
 
         assert_ne!(
             stream_rust.suscripcion_activa_mas_contratada().unwrap(),
-            TipoSuscripcion::Super
+            TipoSuscripcion::new("Super")
         ); // Toma el primer mayor (dada la estructura de HashMap, puede ser cualquiera de los mayores)
 
         // Alta un usuario mas
@@ -1007,8 +1865,11 @@ mod test {
             "Eliono".to_string(),
             "gaspi@yahoo.com".to_string(),
             "2216720032".to_string(),
-            MetodoPago::MercadoPago(Default::default()),
-            TipoSuscripcion::Basic,
+            MetodoPago::MercadoPago(DetallePago::new(
+                "gaspi.eliono".to_string(),
+                "Gaspar Eliono".to_string(),
+            )),
+            TipoSuscripcion::new("Basic"),
         );
 
         // Baja 3 usuarios con suscripcion Basic
@@ -1021,12 +1882,12 @@ mod test {
 
         assert_eq!(
             stream_rust.suscripcion_activa_mas_contratada().unwrap(),
-            TipoSuscripcion::Classic
+            TipoSuscripcion::new("Classic")
         );
 
         assert_eq!(
             stream_rust.suscripcion_general_mas_contratada().unwrap(),
-            TipoSuscripcion::Basic
+            TipoSuscripcion::new("Basic")
         );
 
         // Baja 3 usuarios que quedan
@@ -1048,12 +1909,637 @@ mod test {
         assert_eq!(stream_rust.suscripcion_activa_mas_contratada(), None);
         assert_eq!(
             stream_rust.suscripcion_general_mas_contratada().unwrap(),
-            TipoSuscripcion::Basic
+            TipoSuscripcion::new("Basic")
+        );
+    }
+
+    fn catalogo_personalizado() -> CatalogoPlanes {
+        CatalogoPlanes::new(vec![
+            PlanSuscripcion::new("Anual".to_string(), 900.0, 12, 4, Calidad::Ultra, 30),
+            PlanSuscripcion::new("Basic".to_string(), 50.0, 3, 1, Calidad::Estandar, 10),
+            PlanSuscripcion::new("Familiar".to_string(), 120.0, 6, 5, Calidad::Alta, 20),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_catalogo_planes() {
+        let catalogo = catalogo_personalizado();
+
+        // Los planes quedan ordenados segun su orden y no segun su insercion
+
+        assert_eq!(catalogo.planes.first().unwrap().nombre, "Basic");
+        assert_eq!(catalogo.planes.last().unwrap().nombre, "Anual");
+
+        let familiar = catalogo
+            .get_plan(&TipoSuscripcion::new("Familiar"))
+            .unwrap();
+        assert_eq!(familiar.get_pantallas(), 5);
+        assert_eq!(familiar.get_calidad(), &Calidad::Alta);
+
+        assert_eq!(
+            catalogo
+                .get_plan_superior(&TipoSuscripcion::new("Basic"))
+                .unwrap()
+                .nombre,
+            "Familiar"
+        );
+        assert!(catalogo
+            .get_plan_superior(&TipoSuscripcion::new("Anual"))
+            .is_none());
+        assert!(catalogo
+            .get_plan_inferior(&TipoSuscripcion::new("Basic"))
+            .is_none());
+        assert!(catalogo
+            .get_plan(&TipoSuscripcion::new("Classic"))
+            .is_none());
+
+        // Catalogos con nombres u ordenes repetidos no son validos
+
+        assert_eq!(
+            CatalogoPlanes::new(vec![
+                PlanSuscripcion::new("Basic".to_string(), 50.0, 3, 1, Calidad::Estandar, 1),
+                PlanSuscripcion::new("Basic".to_string(), 80.0, 6, 2, Calidad::Alta, 2),
+            ])
+            .unwrap_err(),
+            ErrorPlataforma::FormatoCatalogo
+        );
+        assert_eq!(
+            CatalogoPlanes::new(vec![
+                PlanSuscripcion::new("Basic".to_string(), 50.0, 3, 1, Calidad::Estandar, 1),
+                PlanSuscripcion::new("Classic".to_string(), 80.0, 6, 2, Calidad::Alta, 1),
+            ])
+            .unwrap_err(),
+            ErrorPlataforma::FormatoCatalogo
+        );
+    }
+
+    #[test]
+    fn test_suscripciones_catalogo_personalizado() {
+        let mut stream_rust = StreamingRust::new(
+            Default::default(),
+            Default::default(),
+            catalogo_personalizado(),
+        );
+        let email = "nahuel@gmail.com".to_string();
+
+        // Plan que no pertenece al catalogo
+
+        assert_eq!(
+            stream_rust
+                .alta_usuario(
+                    "Nahuel".to_string(),
+                    "Luna".to_string(),
+                    email.clone(),
+                    "2217482148".to_string(),
+                    MetodoPago::Efectivo,
+                    TipoSuscripcion::new("Super"),
+                )
+                .unwrap_err(),
+            ErrorPlataforma::PlanInexistente
+        );
+        assert!(stream_rust.get_suscripcion(&email).is_none());
+
+        let _ = stream_rust.alta_usuario(
+            "Nahuel".to_string(),
+            "Luna".to_string(),
+            email.clone(),
+            "2217482148".to_string(),
+            MetodoPago::Efectivo,
+            TipoSuscripcion::new("Basic"),
+        );
+
+        // Upgrade recorre el orden del catalogo: Basic -> Familiar -> Anual
+
+        let _ = stream_rust.upgrade_usuario(&email);
+        let suscripcion = stream_rust.get_suscripcion(&email).unwrap();
+        assert_eq!(
+            suscripcion.get_tipo_suscripcion(),
+            &TipoSuscripcion::new("Familiar")
+        );
+        assert_eq!(suscripcion.get_costo(), 120.0);
+        assert_eq!(suscripcion.get_duracion(), 6);
+
+        let _ = stream_rust.upgrade_usuario(&email);
+        assert_eq!(
+            stream_rust.get_suscripcion(&email).unwrap().get_costo(),
+            900.0
+        );
+        assert_eq!(
+            stream_rust.upgrade_usuario(&email).unwrap_err(),
+            ErrorPlataforma::ModificacionSuscripcion
+        );
+
+        let _ = stream_rust.downgrade_usuario(&email);
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&email)
+                .unwrap()
+                .get_tipo_suscripcion(),
+            &TipoSuscripcion::new("Familiar")
+        );
+
+        assert_eq!(
+            stream_rust.suscripcion_activa_mas_contratada().unwrap(),
+            TipoSuscripcion::new("Familiar")
+        );
+    }
+
+    #[test]
+    fn test_catalogo_archivo() {
+        let path = "test_files/catalogo1.json".to_string();
+        let catalogo = catalogo_personalizado();
+
+        let mut f = File::create(&path).unwrap();
+        f.write_all(
+            serde_json::to_string_pretty(&catalogo.planes)
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(CatalogoPlanes::from_archivo(&path).unwrap(), catalogo);
+
+        // Archivo inexistente y archivo con formato no valido
+
+        assert_eq!(
+            CatalogoPlanes::from_archivo(&"test_files/no_existe.json".to_string()).unwrap_err(),
+            ErrorPlataforma::AbrirArchivo
+        );
+
+        let path = "test_files/catalogo2.json".to_string();
+        let mut f = File::create(&path).unwrap();
+        f.write_all(b"[{\"nombre\": \"Basic\"}]").unwrap();
+
+        let e = CatalogoPlanes::from_archivo(&path).unwrap_err();
+        assert_eq!(e, ErrorPlataforma::FormatoCatalogo);
+        println!("{}", e);
+    }
+
+    #[test]
+    fn test_cupones() {
+        let mut stream_rust = StreamingRust::new(
+            "test_files/suscripciones_cupones.json".to_string(),
+            "test_files/boveda_cupones.json".to_string(),
+            Default::default(),
+        );
+        let mut usuarios = datos_usuarios();
+        let hoy = Fecha::from(Local::now());
+
+        let mut vencimiento = hoy.clone();
+        vencimiento.sumar_dias(30);
+        let mut vencido = hoy.clone();
+        vencido.restar_dias(1);
+
+        assert!(stream_rust
+            .registrar_cupon(Cupon::new(
+                "MITAD".to_string(),
+                Descuento::Porcentaje(50.0),
+                2,
+                1,
+                vencimiento.clone(),
+            ))
+            .is_ok());
+        assert!(stream_rust
+            .registrar_cupon(Cupon::new(
+                "VENCIDO".to_string(),
+                Descuento::Fijo(10.0),
+                1,
+                10,
+                vencido,
+            ))
+            .is_ok());
+        assert!(stream_rust
+            .registrar_cupon(Cupon::new(
+                "UPGRADE".to_string(),
+                Descuento::Fijo(30.0),
+                1,
+                10,
+                vencimiento.clone(),
+            ))
+            .is_ok());
+
+        // Codigo repetido
+
+        assert_eq!(
+            stream_rust
+                .registrar_cupon(Cupon::new(
+                    "MITAD".to_string(),
+                    Descuento::Fijo(1.0),
+                    1,
+                    1,
+                    vencimiento,
+                ))
+                .unwrap_err(),
+            ErrorPlataforma::PromocionInvalida
+        );
+
+        let user1 = usuarios.remove(0);
+        let user2 = usuarios.remove(0);
+        let user3 = usuarios.remove(0);
+
+        // Cupon valido: 50% durante 2 ciclos (Classic = 80)
+
+        let _ = stream_rust.alta_usuario_con_promocion(
+            user1.clone(),
+            TipoSuscripcion::new("Classic"),
+            Promocion::Cupon("MITAD".to_string()),
+        );
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&user1.email)
+                .unwrap()
+                .get_costo_efectivo(&hoy),
+            40.0
+        );
+
+        // Cupon sin usos disponibles, cupon vencido y cupon inexistente
+
+        for codigo in ["MITAD", "VENCIDO", "NO_EXISTE"] {
+            assert_eq!(
+                stream_rust
+                    .alta_usuario_con_promocion(
+                        user2.clone(),
+                        TipoSuscripcion::new("Basic"),
+                        Promocion::Cupon(codigo.to_string()),
+                    )
+                    .unwrap_err(),
+                ErrorPlataforma::PromocionInvalida
+            );
+        }
+        assert!(stream_rust.get_suscripcion(&user2.email).is_none());
+
+        // Si el alta falla, el cupon no se consume
+
+        let mut user_invalido = user2.clone();
+        user_invalido.metodo_pago = MetodoPago::Credito(DetallePago::new(
+            "4539148803436468".to_string(),
+            "Gonzalez".to_string(),
+        ));
+        assert_eq!(
+            stream_rust
+                .alta_usuario_con_promocion(
+                    user_invalido,
+                    TipoSuscripcion::new("Basic"),
+                    Promocion::Cupon("UPGRADE".to_string()),
+                )
+                .unwrap_err(),
+            ErrorPlataforma::MetodoPagoInvalido
+        );
+        assert_eq!(stream_rust.cupones.get("UPGRADE").unwrap().usos, 0);
+
+        // Cobro de ciclos: el descuento finaliza luego de 2 ciclos
+
+        let _ = stream_rust.alta_usuario_con_promocion(
+            user3.clone(),
+            TipoSuscripcion::new("Basic"),
+            Promocion::PruebaGratis(7),
+        );
+        let mut cobros = Vec::new();
+        for _ in 0..3 {
+            if let Err(e) = stream_rust.cobrar_suscripcion(&user1.email, &hoy) {
+                assert_eq!(e, ErrorPlataforma::CrearArchivo); // No hay path valido de archivo
+            }
+            cobros.push(
+                stream_rust
+                    .get_suscripcion(&user1.email)
+                    .unwrap()
+                    .get_costo_efectivo(&hoy),
+            );
+        }
+        assert_eq!(cobros, vec![40.0, 80.0, 80.0]);
+
+        // Cupon aplicado al realizar un upgrade (Classic -> Super = 100)
+
+        let _ = stream_rust.upgrade_usuario_con_cupon(&user1.email, "UPGRADE".to_string());
+        let suscripcion = stream_rust.get_suscripcion(&user1.email).unwrap();
+        assert_eq!(
+            suscripcion.get_tipo_suscripcion(),
+            &TipoSuscripcion::new("Super")
+        );
+        assert_eq!(suscripcion.get_costo_efectivo(&hoy), 70.0);
+
+        // Cupon valido pero upgrade imposible
+
+        assert_eq!(
+            stream_rust
+                .upgrade_usuario_con_cupon(&user1.email, "UPGRADE".to_string())
+                .unwrap_err(),
+            ErrorPlataforma::ModificacionSuscripcion
+        );
+        assert_eq!(stream_rust.cupones.get("UPGRADE").unwrap().usos, 1);
+
+        // Los cupones y sus usos se recuperan del archivo
+
+        let reiniciada = StreamingRust::new(
+            "test_files/suscripciones_cupones.json".to_string(),
+            "test_files/boveda_cupones.json".to_string(),
+            Default::default(),
+        );
+        assert_eq!(reiniciada.cupones, stream_rust.cupones);
+        assert!(!reiniciada.cupones.get("MITAD").unwrap().es_valido(&hoy));
+    }
+
+    #[test]
+    fn test_prueba_gratis_y_referidos() {
+        let mut stream_rust = StreamingRust::new(
+            "test_files/suscripciones_promociones.json".to_string(),
+            "test_files/boveda_promociones.json".to_string(),
+            Default::default(),
+        );
+        let mut usuarios = datos_usuarios();
+        let hoy = Fecha::from(Local::now());
+
+        let user1 = usuarios.remove(0);
+        let user2 = usuarios.remove(0);
+        let user3 = usuarios.remove(0);
+
+        let _ = stream_rust.alta_usuario_con_promocion(
+            user1.clone(),
+            TipoSuscripcion::new("Classic"),
+            Promocion::PruebaGratis(14),
+        );
+
+        // Durante la prueba el costo es 0, luego pasa a ser pago si no se cancela
+
+        let mut fin_prueba = hoy.clone();
+        fin_prueba.sumar_dias(14);
+
+        let suscripcion = stream_rust.get_suscripcion(&user1.email).unwrap();
+        assert_eq!(suscripcion.get_costo_efectivo(&hoy), 0.0);
+        assert_eq!(suscripcion.get_costo_efectivo(&fin_prueba), 80.0);
+        assert_eq!(stream_rust.cantidad_suscripciones_en_prueba(&hoy), 1);
+        assert_eq!(stream_rust.cantidad_suscripciones_en_prueba(&fin_prueba), 0);
+        assert_eq!(stream_rust.recaudacion_activa(&hoy), 0.0);
+
+        // Un usuario ya registrado no puede volver a obtener prueba gratuita
+
+        let _ = stream_rust.baja_usuario(&user1.email);
+        assert_eq!(
+            stream_rust
+                .alta_usuario_con_promocion(
+                    user1.clone(),
+                    TipoSuscripcion::new("Classic"),
+                    Promocion::PruebaGratis(14),
+                )
+                .unwrap_err(),
+            ErrorPlataforma::PromocionInvalida
+        );
+        let _ = stream_rust.alta_usuario(
+            user1.nombre.clone(),
+            user1.apellido.clone(),
+            user1.email.clone(),
+            user1.telefono.clone(),
+            user1.metodo_pago.clone(),
+            TipoSuscripcion::new("Classic"),
+        );
+
+        // Referido: el referente debe existir y estar activo, y solo recibe el credito si el
+        //alta del referido se completa
+
+        let mut user_invalido = user2.clone();
+        user_invalido.metodo_pago = MetodoPago::Credito(DetallePago::new(
+            "4539148803436468".to_string(),
+            "Gonzalez".to_string(),
+        ));
+        assert!(stream_rust
+            .alta_usuario_con_promocion(
+                user_invalido,
+                TipoSuscripcion::new("Basic"),
+                Promocion::Referido(user1.email.clone()),
+            )
+            .is_err());
+        assert_eq!(
+            stream_rust.get_suscripcion(&user1.email).unwrap().creditos,
+            0.0
+        );
+
+        assert_eq!(
+            stream_rust
+                .alta_usuario_con_promocion(
+                    user2.clone(),
+                    TipoSuscripcion::new("Basic"),
+                    Promocion::Referido(user3.email.clone()),
+                )
+                .unwrap_err(),
+            ErrorPlataforma::PromocionInvalida
+        );
+
+        let _ = stream_rust.alta_usuario_con_promocion(
+            user2.clone(),
+            TipoSuscripcion::new("Basic"),
+            Promocion::Referido(user1.email.clone()),
+        );
+        let _ = stream_rust.alta_usuario_con_promocion(
+            user3.clone(),
+            TipoSuscripcion::new("Basic"),
+            Promocion::Referido(user1.email.clone()),
+        );
+
+        // El referente acumula 2 creditos, que se consumen en los cobros
+
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&user1.email)
+                .unwrap()
+                .get_costo_efectivo(&fin_prueba),
+            40.0
+        );
+        assert_eq!(stream_rust.recaudacion_activa(&fin_prueba), 140.0);
+
+        let _ = stream_rust.cobrar_suscripcion(&user1.email, &fin_prueba);
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&user1.email)
+                .unwrap()
+                .get_costo_efectivo(&fin_prueba),
+            80.0
+        );
+
+        // No es posible cobrar a usuarios inexistentes o inactivos
+
+        let _ = stream_rust.baja_usuario(&user2.email);
+        for email in [&user2.email, &"pepe@example.com".to_string()] {
+            let e = stream_rust.cobrar_suscripcion(email, &hoy).unwrap_err();
+            assert_eq!(e, ErrorPlataforma::CobroSuscripcion);
+            println!("{}", e);
+        }
+    }
+
+    #[test]
+    fn test_historial_movimientos() {
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
+        let user = datos_usuarios().remove(0);
+
+        let _ = stream_rust.alta_usuario(
+            user.nombre.clone(),
+            user.apellido.clone(),
+            user.email.clone(),
+            user.telefono.clone(),
+            user.metodo_pago.clone(),
+            TipoSuscripcion::new("Basic"),
+        );
+        let _ = stream_rust.upgrade_usuario(&user.email);
+        let _ = stream_rust.downgrade_usuario(&user.email);
+        let _ = stream_rust.downgrade_usuario(&user.email); // Baja por downgrade desde Basic
+        let _ = stream_rust.alta_usuario(
+            user.nombre.clone(),
+            user.apellido.clone(),
+            user.email.clone(),
+            user.telefono.clone(),
+            user.metodo_pago.clone(),
+            TipoSuscripcion::new("Basic"),
+        );
+
+        let suscripciones = stream_rust.get_historial_suscripciones(&user.email);
+        let historial = &suscripciones.first().unwrap().historial;
+        let tipos: Vec<&TipoMovimiento> = historial.iter().map(|m| &m.tipo).collect();
+
+        assert_eq!(
+            tipos,
+            vec![
+                &TipoMovimiento::Alta,
+                &TipoMovimiento::Upgrade,
+                &TipoMovimiento::Downgrade,
+                &TipoMovimiento::Baja,
+            ]
+        );
+        assert_eq!(suscripciones.last().unwrap().historial.len(), 1); // Nueva suscripcion
+        assert_eq!(historial.get(1).unwrap().costo, 80.0);
+        assert_eq!(
+            historial.get(1).unwrap().tipo_suscripcion,
+            TipoSuscripcion::new("Classic")
+        );
+    }
+
+    #[test]
+    fn test_validacion_metodos_pago() {
+        let detalle = |cuenta: &str| DetallePago::new(cuenta.to_string(), "Luna".to_string());
+
+        // Tarjetas de credito (Luhn)
+
+        assert!(MetodoPago::Credito(detalle("4539 1488 0343 6467")).es_valido());
+        assert!(MetodoPago::Credito(detalle("5555555555554444")).es_valido());
+        assert!(!MetodoPago::Credito(detalle("4539148803436468")).es_valido());
+        assert!(!MetodoPago::Credito(detalle("4539a48803436467")).es_valido());
+        assert!(!MetodoPago::Credito(detalle("42")).es_valido());
+
+        // CBU y CVU (digitos verificadores de ambos bloques)
+
+        assert!(MetodoPago::Transferencia(detalle("2850590940090418135201")).es_valido());
+        assert!(MetodoPago::Transferencia(detalle("0000003100010000000009")).es_valido());
+        assert!(!MetodoPago::Transferencia(detalle("2850590940090418135202")).es_valido());
+        assert!(!MetodoPago::Transferencia(detalle("2850591940090418135201")).es_valido());
+        assert!(!MetodoPago::Transferencia(detalle("285059094009041813520")).es_valido());
+
+        // Alias de MercadoPago
+
+        assert!(MetodoPago::MercadoPago(detalle("casa.perro.sol")).es_valido());
+        assert!(!MetodoPago::MercadoPago(detalle("sol")).es_valido());
+        assert!(!MetodoPago::MercadoPago(detalle("casa perro sol")).es_valido());
+
+        // Billeteras
+
+        assert!(
+            MetodoPago::Cripto(detalle("0x52908400098527886E0F7030069857D2E4169EE7")).es_valido()
+        );
+        assert!(MetodoPago::Cripto(detalle("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2")).es_valido());
+        assert!(
+            MetodoPago::Cripto(detalle("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq")).es_valido()
+        );
+        assert!(
+            !MetodoPago::Cripto(detalle("0x52908400098527886E0F7030069857D2E4169EZ7")).es_valido()
+        );
+        assert!(!MetodoPago::Cripto(detalle("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN0")).es_valido());
+
+        assert!(MetodoPago::Efectivo.es_valido());
+
+        // No es posible dar de alta un usuario con un metodo de pago no valido
+
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
+        let e = stream_rust
+            .alta_usuario(
+                "Nahuel".to_string(),
+                "Luna".to_string(),
+                "nahuel@gmail.com".to_string(),
+                "2217482148".to_string(),
+                MetodoPago::Credito(detalle("4539148803436468")),
+                TipoSuscripcion::new("Basic"),
+            )
+            .unwrap_err();
+        assert_eq!(e, ErrorPlataforma::MetodoPagoInvalido);
+        println!("{}", e);
+        assert!(stream_rust.usuarios.is_empty());
+    }
+
+    #[test]
+    fn test_tokenizacion_metodos_pago() {
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
+        let path = "test_files/suscripciones_tokens.json".to_string();
+        let path_boveda = "test_files/boveda2.json".to_string();
+        stream_rust.file_path = path.clone();
+        stream_rust.boveda.path = path_boveda.clone();
+
+        let metodo = MetodoPago::Credito(DetallePago::new(
+            "4539148803436467".to_string(),
+            "Luna".to_string(),
+        ));
+
+        // Enmascarado antes de tokenizar
+
+        assert_eq!(metodo.to_string(), "Credito (****6467)");
+        assert!(!format!("{:?}", metodo).contains("4539148803436467"));
+
+        assert!(stream_rust
+            .alta_usuario(
+                "Nahuel".to_string(),
+                "Luna".to_string(),
+                "nahuel@gmail.com".to_string(),
+                "2217482148".to_string(),
+                metodo,
+                TipoSuscripcion::new("Basic"),
+            )
+            .is_ok());
+
+        let usuario = stream_rust
+            .get_usuario(&"nahuel@gmail.com".to_string())
+            .unwrap();
+        let detalle = usuario.get_metodo_pago().get_detalle().unwrap();
+
+        assert!(detalle.esta_tokenizado());
+        assert_eq!(detalle.ultimos_digitos, "6467");
+        assert_eq!(usuario.get_metodo_pago().to_string(), "Credito (****6467)");
+
+        // En el archivo solo se encuentran el token y los ultimos digitos
+
+        let mut buf = String::new();
+        File::open(&path).unwrap().read_to_string(&mut buf).unwrap();
+        assert!(!buf.contains("4539148803436467"));
+        assert!(buf.contains(&detalle.token));
+
+        // La boveda permite recuperar la cuenta a partir del token
+
+        let boveda = BovedaTokens::new(path_boveda);
+        assert_eq!(
+            boveda.get_cuenta(&detalle.token),
+            Some(&"4539148803436467".to_string())
         );
+
+        // Una misma cuenta obtiene el mismo token
+
+        let mut boveda = boveda;
+        assert_eq!(boveda.tokenizar("4539148803436467"), detalle.token);
+        assert_ne!(boveda.tokenizar("5555555555554444"), detalle.token);
     }
 
     fn datos_usuarios() -> Vec<Usuario> {
-        let detalle1 = DetallePago::new("462942".to_string(), "Luna".to_string());
+        let detalle1 = DetallePago::new(
+            "0x52908400098527886E0F7030069857D2E4169EE7".to_string(),
+            "Luna".to_string(),
+        );
         let user1 = Usuario::new(
             "Nahuel".to_string(),
             "Luna".to_string(),
@@ -1070,7 +2556,10 @@ mod test {
             MetodoPago::Efectivo,
         );
 
-        let detalle3 = DetallePago::new("75302".to_string(), "Perez".to_string());
+        let detalle3 = DetallePago::new(
+            "1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2".to_string(),
+            "Perez".to_string(),
+        );
         let user3 = Usuario::new(
             "German".to_string(),
             "Perez".to_string(),
@@ -1086,13 +2575,15 @@ mod test {
     fn test_suscripciones_archivo() {
         let path = "test_files/suscripciones1.json".to_string();
         let mut usuarios = datos_usuarios();
-        let mut stream_rust = StreamingRust::new(Default::default()); // Para que no recupere automaticamente los datos del archivo en la instanciacion
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default()); // Para que no recupere automaticamente los datos del archivo en la instanciacion
 
         let user1 = usuarios.remove(0);
         let user2 = usuarios.remove(0);
         let user3 = usuarios.remove(0);
 
         stream_rust.file_path = path.clone();
+        stream_rust.boveda.path = "test_files/boveda1.json".to_string();
 
         // Alta de 3 usuarios
 
@@ -1102,7 +2593,7 @@ mod test {
             user1.email.clone(),
             user1.telefono.clone(),
             user1.metodo_pago.clone(),
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
         let _ = stream_rust.alta_usuario(
             user2.nombre.clone(),
@@ -1110,7 +2601,7 @@ mod test {
             user2.email.clone(),
             user2.telefono.clone(),
             user2.metodo_pago.clone(),
-            TipoSuscripcion::Classic,
+            TipoSuscripcion::new("Classic"),
         );
         let _ = stream_rust.alta_usuario(
             user3.nombre.clone(),
@@ -1118,17 +2609,18 @@ mod test {
             user3.email.clone(),
             user3.telefono.clone(),
             user3.metodo_pago.clone(),
-            TipoSuscripcion::Super,
+            TipoSuscripcion::new("Super"),
         );
 
         // Recupero informacion del archivo y compruebo sus datos
 
-        let mut f = StreamingRust::recuperar_datos_archivo(&path).unwrap();
+        let f = StreamingRust::recuperar_datos_archivo(&path).unwrap();
 
-        assert_eq!(f.len(), 3);
+        assert_eq!(f.usuarios.len(), 3);
+        assert_eq!(f.suscripciones.len(), 3);
         assert_eq!(
-            f.get("nahuel@gmail.com").unwrap().tipo_suscripcion,
-            TipoSuscripcion::Basic
+            f.suscripciones.first().unwrap().tipo_suscripcion,
+            TipoSuscripcion::new("Basic")
         );
 
         // Se realizan modificaciones y bajas de suscripciones
@@ -1139,22 +2631,239 @@ mod test {
 
         // Chequeo que la informacion se haya trasladado al archivo
 
-        f = StreamingRust::recuperar_datos_archivo(&path).unwrap();
+        let recuperado = StreamingRust::new(
+            path.clone(),
+            "test_files/boveda1.json".to_string(),
+            Default::default(),
+        );
+
+        assert!(!recuperado.get_suscripcion(&user1.email).unwrap().activo);
+        assert_eq!(
+            recuperado
+                .get_suscripcion(&user2.email)
+                .unwrap()
+                .tipo_suscripcion,
+            TipoSuscripcion::new("Super")
+        );
+        assert_eq!(
+            recuperado
+                .get_suscripcion(&user3.email)
+                .unwrap()
+                .tipo_suscripcion,
+            TipoSuscripcion::new("Classic")
+        );
+
+        // Un nuevo alta conserva la suscripcion previa en el archivo
+
+        assert!(stream_rust
+            .alta_usuario(
+                user1.nombre.clone(),
+                user1.apellido.clone(),
+                user1.email.clone(),
+                user1.telefono.clone(),
+                user1.metodo_pago.clone(),
+                TipoSuscripcion::new("Super"),
+            )
+            .is_ok());
+
+        let f = StreamingRust::recuperar_datos_archivo(&path).unwrap();
+        assert_eq!(f.usuarios.len(), 3);
+        assert_eq!(f.suscripciones.len(), 4);
+    }
+
+    #[test]
+    fn test_historial_y_cambio_email() {
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
+        let user = datos_usuarios().remove(0);
+        let nuevo_email = "nahuel.luna@gmail.com".to_string();
+
+        let _ = stream_rust.alta_usuario(
+            user.nombre.clone(),
+            user.apellido.clone(),
+            user.email.clone(),
+            user.telefono.clone(),
+            user.metodo_pago.clone(),
+            TipoSuscripcion::new("Basic"),
+        );
+        let _ = stream_rust.baja_usuario(&user.email);
+
+        // Volver a suscribirse no sobreescribe la suscripcion previa
+
+        let _ = stream_rust.alta_usuario(
+            user.nombre.clone(),
+            user.apellido.clone(),
+            user.email.clone(),
+            user.telefono.clone(),
+            user.metodo_pago.clone(),
+            TipoSuscripcion::new("Super"),
+        );
+
+        let historial = stream_rust.get_historial_suscripciones(&user.email);
+        assert_eq!(historial.len(), 2);
+        assert!(!historial.first().unwrap().activo);
+        assert!(historial.last().unwrap().activo);
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&user.email)
+                .unwrap()
+                .get_tipo_suscripcion(),
+            &TipoSuscripcion::new("Super")
+        );
+        assert_eq!(stream_rust.usuarios.len(), 1);
+
+        // Cambio de email conservando el historial
+
+        let _ = stream_rust.modificar_email(&user.email, nuevo_email.clone());
+
+        assert!(stream_rust.get_usuario(&user.email).is_none());
+        assert!(stream_rust.get_suscripcion(&user.email).is_none());
+        assert_eq!(
+            stream_rust.get_historial_suscripciones(&nuevo_email).len(),
+            2
+        );
+        assert_eq!(
+            stream_rust.metodo_pago_general_mas_utilizado(),
+            Some(MetodoPago::Cripto(Default::default()))
+        ); // Las estadisticas siguen asociando ambas suscripciones al usuario
+
+        // Email inexistente o ya utilizado por otro usuario
+
+        let _ = stream_rust.alta_usuario(
+            "Pedro".to_string(),
+            "Gonzalez".to_string(),
+            "pedro@gmail.com".to_string(),
+            "2217482148".to_string(),
+            MetodoPago::Efectivo,
+            TipoSuscripcion::new("Basic"),
+        );
+
+        let e = stream_rust
+            .modificar_email(&user.email, "otro@gmail.com".to_string())
+            .unwrap_err();
+        assert_eq!(e, ErrorPlataforma::ModificacionUsuario);
+        println!("{}", e);
+
+        assert_eq!(
+            stream_rust
+                .modificar_email(&nuevo_email, "pedro@gmail.com".to_string())
+                .unwrap_err(),
+            ErrorPlataforma::ModificacionUsuario
+        );
+    }
+
+    #[test]
+    fn test_migracion_archivo_previo() {
+        let path = "test_files/suscripciones_previo.json".to_string();
+
+        // Formato previo: map de email a suscripcion con el usuario embebido
+
+        let previo = r#"{
+            "pedro@gmail.com": {
+                "activo": false,
+                "tipo_suscripcion": "Classic",
+                "costo": 80.0,
+                "duracion": 6,
+                "fecha_inicio": { "dia": 3, "mes": 2, "anio": 2024 },
+                "usuario": {
+                    "nombre": "Pedro",
+                    "apellido": "Gonzalez",
+                    "email": "pedro@gmail.com",
+                    "telefono": "2217482148",
+                    "metodo_pago": "Efectivo"
+                }
+            },
+            "nahuel@gmail.com": {
+                "activo": true,
+                "tipo_suscripcion": "Basic",
+                "costo": 50.0,
+                "duracion": 3,
+                "fecha_inicio": { "dia": 1, "mes": 1, "anio": 2024 },
+                "usuario": {
+                    "nombre": "Nahuel",
+                    "apellido": "Luna",
+                    "email": "nahuel@gmail.com",
+                    "telefono": "2217482148",
+                    "metodo_pago": { "Cripto": { "cuenta": "462942", "titular": "Luna" } }
+                }
+            }
+        }"#;
+
+        File::create(&path)
+            .unwrap()
+            .write_all(previo.as_bytes())
+            .unwrap();
+
+        let path_boveda = "test_files/boveda_previo.json".to_string();
+        let mut stream_rust =
+            StreamingRust::new(path.clone(), path_boveda.clone(), Default::default());
+
+        // La cuenta en texto plano se tokeniza y el archivo se reescribe sin ella
+
+        let mut buf = String::new();
+        File::open(&path).unwrap().read_to_string(&mut buf).unwrap();
+        assert!(!buf.contains("462942"));
+        assert!(buf.contains("\"ultimos_digitos\": \"2942\""));
+
+        let metodo = stream_rust
+            .get_usuario(&"nahuel@gmail.com".to_string())
+            .unwrap()
+            .get_metodo_pago();
+        let token = &metodo.get_detalle().unwrap().token;
+        assert_eq!(
+            BovedaTokens::new(path_boveda).get_cuenta(token),
+            Some(&"462942".to_string())
+        );
+
+        // Los ids se asignan por orden de email
 
-        assert!(!f.get(&user1.email).unwrap().activo);
         assert_eq!(
-            f.get(&user2.email).unwrap().tipo_suscripcion,
-            TipoSuscripcion::Super
+            stream_rust
+                .get_usuario(&"nahuel@gmail.com".to_string())
+                .unwrap()
+                .id,
+            1
+        );
+        assert_eq!(
+            stream_rust
+                .get_usuario(&"pedro@gmail.com".to_string())
+                .unwrap()
+                .id,
+            2
+        );
+        assert_eq!(
+            stream_rust
+                .get_suscripcion(&"nahuel@gmail.com".to_string())
+                .unwrap()
+                .get_costo(),
+            50.0
+        );
+        assert!(
+            !stream_rust
+                .get_suscripcion(&"pedro@gmail.com".to_string())
+                .unwrap()
+                .activo
         );
+
+        // Al modificar datos, el archivo se reescribe en el nuevo formato
+
+        assert!(stream_rust
+            .upgrade_usuario(&"nahuel@gmail.com".to_string())
+            .is_ok());
+
+        let f = StreamingRust::recuperar_datos_archivo(&path).unwrap();
+        assert_eq!(f.usuarios.len(), 2);
+        assert_eq!(f.suscripciones.first().unwrap().id_usuario, 1);
         assert_eq!(
-            f.get(&user3.email).unwrap().tipo_suscripcion,
-            TipoSuscripcion::Classic
+            f.suscripciones.first().unwrap().tipo_suscripcion,
+            TipoSuscripcion::new("Classic")
         );
     }
 
     #[test]
     fn test_errores_archivo() {
-        let mut stream_rust = StreamingRust::new("".to_string());
+        let mut stream_rust =
+            StreamingRust::new("".to_string(), Default::default(), Default::default());
 
         let mut result;
 
@@ -1184,7 +2893,7 @@ mod test {
             "example@gmail.com".to_string(),
             "2217369742".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
 
         if let Err(e) = result {
@@ -1200,7 +2909,7 @@ mod test {
             "example@gmail.com".to_string(),
             "2217369742".to_string(),
             MetodoPago::Efectivo,
-            TipoSuscripcion::Basic,
+            TipoSuscripcion::new("Basic"),
         );
 
         if let Err(e) = result {
@@ -0,0 +1,714 @@
+use std::collections::HashMap;
+use std::{fs::File, io::prelude::*};
+
+use super::{
+    CatalogoPlanes, ErrorPlataforma, MetodoPago, Movimiento, StreamingRust, Suscripcion,
+    TipoMovimiento, TipoSuscripcion,
+};
+use crate::tp3::ej03::Fecha;
+
+#[derive(Debug, Clone)]
+struct RangoFechas {
+    desde: Fecha,
+    hasta: Fecha,
+}
+
+#[derive(Debug, PartialEq)]
+struct Cohorte {
+    anio: i32,
+    mes: u32,
+    altas: u32,
+    activas: u32,
+}
+
+#[derive(Debug)]
+struct ReporteAnaliticas {
+    rango: RangoFechas,
+    distribucion_metodos_pago: HashMap<MetodoPago, u32>,
+    distribucion_tipos_suscripcion: HashMap<TipoSuscripcion, u32>,
+    tasa_churn: f64,
+    mrr: f64,
+    arr: f64,
+    vida_promedio: f64,
+    upgrades: u32,
+    downgrades: u32,
+    cohortes: Vec<Cohorte>,
+}
+
+impl RangoFechas {
+    fn new(desde: Fecha, hasta: Fecha) -> RangoFechas {
+        if desde.es_mayor(&hasta) {
+            panic!("Rango de fechas no valido");
+        }
+
+        RangoFechas { desde, hasta }
+    }
+
+    fn contiene(&self, fecha: &Fecha) -> bool {
+        !self.desde.es_mayor(fecha) && !fecha.es_mayor(&self.hasta)
+    }
+}
+
+impl Cohorte {
+    fn tasa_retencion(&self) -> f64 {
+        match self.altas {
+            0 => 0.0,
+            altas => self.activas as f64 / altas as f64,
+        }
+    }
+}
+
+impl MetodoPago {
+    fn get_nombre(&self) -> &str {
+        match self {
+            MetodoPago::Efectivo => "Efectivo",
+            MetodoPago::MercadoPago(_) => "MercadoPago",
+            MetodoPago::Credito(_) => "Credito",
+            MetodoPago::Transferencia(_) => "Transferencia",
+            MetodoPago::Cripto(_) => "Cripto",
+        }
+    }
+}
+
+impl Suscripcion {
+    // Los archivos previos al registro de movimientos solo conocen la fecha de inicio. Los
+    //movimientos guardados con una fecha no valida no se consideran
+    fn get_movimientos(&self) -> Vec<Movimiento> {
+        if self.historial.is_empty() {
+            if !self.fecha_inicio.es_fecha_valida() {
+                return Vec::new();
+            }
+
+            return vec![Movimiento {
+                tipo: TipoMovimiento::Alta,
+                fecha: self.fecha_inicio.clone(),
+                tipo_suscripcion: self.tipo_suscripcion.clone(),
+                costo: self.costo,
+            }];
+        }
+
+        self.historial
+            .iter()
+            .filter(|m| m.fecha.es_fecha_valida())
+            .cloned()
+            .collect()
+    }
+
+    fn get_fecha_alta(&self) -> Option<Fecha> {
+        self.get_movimientos().into_iter().next().map(|m| m.fecha)
+    }
+
+    // Ultimo movimiento hasta la fecha dada, solo si la suscripcion se encontraba activa
+    fn get_estado(&self, fecha: &Fecha) -> Option<Movimiento> {
+        let ultimo = self
+            .get_movimientos()
+            .into_iter()
+            .rev()
+            .find(|m| !m.fecha.es_mayor(fecha));
+
+        ultimo.filter(|m| m.tipo != TipoMovimiento::Baja)
+    }
+
+    fn estuvo_activa(&self, rango: &RangoFechas) -> bool {
+        self.get_estado(&rango.desde).is_some()
+            || self
+                .get_movimientos()
+                .iter()
+                .any(|m| m.tipo != TipoMovimiento::Baja && rango.contiene(&m.fecha))
+    }
+
+    // Ingreso efectivo del ciclo vigente a la fecha dada, repartido en los meses que dura el plan.
+    //Descuento y creditos guardados corresponden al plan actual, por lo que los planes anteriores
+    //solo descuentan la prueba gratuita
+    fn get_ingreso_mensual(&self, fecha: &Fecha, catalogo: &CatalogoPlanes) -> Option<f64> {
+        let estado = self.get_estado(fecha)?;
+        let es_plan_actual = self.activo && self.get_movimientos().last() == Some(&estado);
+
+        let (costo, duracion) = if es_plan_actual {
+            (self.get_costo_efectivo(fecha), self.duracion)
+        } else {
+            let duracion = catalogo
+                .get_plan(&estado.tipo_suscripcion)
+                .map_or(self.duracion, |p| p.get_duracion());
+            let costo = if self.en_prueba(fecha) {
+                0.0
+            } else {
+                estado.costo
+            };
+            (costo, duracion)
+        };
+
+        Some(costo / duracion.max(1) as f64)
+    }
+
+    // Dias activos desde el alta hasta la fecha dada, descontando los periodos de baja
+    fn dias_activa(&self, hasta: &Fecha) -> i64 {
+        let mut dias = 0;
+        let mut inicio_periodo: Option<Fecha> = None;
+
+        for m in self.get_movimientos() {
+            if m.fecha.es_mayor(hasta) {
+                break;
+            }
+
+            match (&m.tipo, &inicio_periodo) {
+                (TipoMovimiento::Baja, Some(inicio)) => {
+                    dias += inicio.dias_hasta(&m.fecha);
+                    inicio_periodo = None;
+                }
+                (TipoMovimiento::Baja, None) => (),
+                (_, None) => inicio_periodo = Some(m.fecha.clone()),
+                (_, Some(_)) => (),
+            }
+        }
+
+        if let Some(inicio) = inicio_periodo {
+            dias += inicio.dias_hasta(hasta);
+        }
+
+        dias
+    }
+}
+
+impl StreamingRust {
+    fn distribucion_metodos_pago(&self, rango: &RangoFechas) -> HashMap<MetodoPago, u32> {
+        let mut metodos = HashMap::new();
+
+        self.suscripciones
+            .iter()
+            .filter(|s| s.estuvo_activa(rango))
+            .filter_map(|s| self.get_metodo_pago(s))
+            .for_each(|m| *metodos.entry(m.clone()).or_insert(0) += 1);
+
+        metodos
+    }
+
+    // Cada suscripcion se cuenta en el ultimo plan que tuvo contratado dentro del rango
+    fn distribucion_tipos_suscripcion(&self, rango: &RangoFechas) -> HashMap<TipoSuscripcion, u32> {
+        let mut tipos = HashMap::new();
+
+        for s in self.suscripciones.iter() {
+            if !s.estuvo_activa(rango) {
+                continue;
+            }
+
+            let ultimo_plan = s
+                .get_movimientos()
+                .into_iter()
+                .rev()
+                .find(|m| !m.fecha.es_mayor(&rango.hasta) && m.tipo != TipoMovimiento::Baja);
+
+            if let Some(m) = ultimo_plan {
+                *tipos.entry(m.tipo_suscripcion).or_insert(0) += 1;
+            }
+        }
+
+        tipos
+    }
+
+    fn cantidad_movimientos(&self, rango: &RangoFechas, tipo: TipoMovimiento) -> u32 {
+        self.suscripciones
+            .iter()
+            .flat_map(|s| s.get_movimientos())
+            .filter(|m| m.tipo == tipo && rango.contiene(&m.fecha))
+            .count() as u32
+    }
+
+    // Bajas del rango sobre el total de suscripciones que estuvieron activas en el mismo
+    fn tasa_churn(&self, rango: &RangoFechas) -> f64 {
+        let activas = self
+            .suscripciones
+            .iter()
+            .filter(|s| s.estuvo_activa(rango))
+            .count();
+
+        match activas {
+            0 => 0.0,
+            activas => {
+                self.cantidad_movimientos(rango, TipoMovimiento::Baja) as f64 / activas as f64
+            }
+        }
+    }
+
+    // Ingreso mensual recurrente de las suscripciones activas al final del rango
+    fn mrr(&self, rango: &RangoFechas) -> f64 {
+        self.suscripciones
+            .iter()
+            .filter_map(|s| s.get_ingreso_mensual(&rango.hasta, &self.catalogo))
+            .sum()
+    }
+
+    fn arr(&self, rango: &RangoFechas) -> f64 {
+        self.mrr(rango) * 12.0
+    }
+
+    // Promedio en dias de las suscripciones dadas de alta dentro del rango
+    fn vida_promedio_suscripcion(&self, rango: &RangoFechas) -> f64 {
+        let dias: Vec<i64> = self
+            .suscripciones
+            .iter()
+            .filter(|s| s.get_fecha_alta().is_some_and(|f| rango.contiene(&f)))
+            .map(|s| s.dias_activa(&rango.hasta))
+            .collect();
+
+        match dias.len() {
+            0 => 0.0,
+            cant => dias.iter().sum::<i64>() as f64 / cant as f64,
+        }
+    }
+
+    fn retencion_cohortes(&self, rango: &RangoFechas) -> Vec<Cohorte> {
+        let mut cohortes: Vec<Cohorte> = Vec::new();
+
+        for s in self.suscripciones.iter() {
+            let Some(alta) = s.get_fecha_alta().filter(|f| rango.contiene(f)) else {
+                continue;
+            };
+
+            let activa = s.get_estado(&rango.hasta).is_some() as u32;
+
+            match cohortes
+                .iter_mut()
+                .find(|c| c.anio == alta.get_anio() && c.mes == alta.get_mes())
+            {
+                Some(c) => {
+                    c.altas += 1;
+                    c.activas += activa;
+                }
+                None => cohortes.push(Cohorte {
+                    anio: alta.get_anio(),
+                    mes: alta.get_mes(),
+                    altas: 1,
+                    activas: activa,
+                }),
+            }
+        }
+
+        cohortes.sort_by_key(|c| (c.anio, c.mes));
+        cohortes
+    }
+
+    fn generar_reporte(&self, rango: RangoFechas) -> ReporteAnaliticas {
+        ReporteAnaliticas {
+            distribucion_metodos_pago: self.distribucion_metodos_pago(&rango),
+            distribucion_tipos_suscripcion: self.distribucion_tipos_suscripcion(&rango),
+            tasa_churn: self.tasa_churn(&rango),
+            mrr: self.mrr(&rango),
+            arr: self.arr(&rango),
+            vida_promedio: self.vida_promedio_suscripcion(&rango),
+            upgrades: self.cantidad_movimientos(&rango, TipoMovimiento::Upgrade),
+            downgrades: self.cantidad_movimientos(&rango, TipoMovimiento::Downgrade),
+            cohortes: self.retencion_cohortes(&rango),
+            rango,
+        }
+    }
+}
+
+impl ReporteAnaliticas {
+    // Formato seccion,clave,valor. Las distribuciones se ordenan por nombre para obtener una salida estable
+    fn to_csv(&self) -> String {
+        let mut lineas = vec!["seccion,clave,valor".to_string()];
+
+        lineas.push(format!(
+            "rango,desde,{:02}/{:02}/{}",
+            self.rango.desde.get_dia(),
+            self.rango.desde.get_mes(),
+            self.rango.desde.get_anio()
+        ));
+        lineas.push(format!(
+            "rango,hasta,{:02}/{:02}/{}",
+            self.rango.hasta.get_dia(),
+            self.rango.hasta.get_mes(),
+            self.rango.hasta.get_anio()
+        ));
+
+        let mut metodos: Vec<(&str, u32)> = self
+            .distribucion_metodos_pago
+            .iter()
+            .map(|(m, cant)| (m.get_nombre(), *cant))
+            .collect();
+        metodos.sort();
+        metodos
+            .iter()
+            .for_each(|(m, cant)| lineas.push(format!("metodo_pago,{},{}", m, cant)));
+
+        let mut tipos: Vec<(&String, u32)> = self
+            .distribucion_tipos_suscripcion
+            .iter()
+            .map(|(t, cant)| (t.get_nombre(), *cant))
+            .collect();
+        tipos.sort();
+        tipos
+            .iter()
+            .for_each(|(t, cant)| lineas.push(format!("tipo_suscripcion,{},{}", t, cant)));
+
+        lineas.push(format!("resumen,tasa_churn,{:.4}", self.tasa_churn));
+        lineas.push(format!("resumen,mrr,{:.2}", self.mrr));
+        lineas.push(format!("resumen,arr,{:.2}", self.arr));
+        lineas.push(format!(
+            "resumen,vida_promedio_dias,{:.2}",
+            self.vida_promedio
+        ));
+        lineas.push(format!("resumen,upgrades,{}", self.upgrades));
+        lineas.push(format!("resumen,downgrades,{}", self.downgrades));
+
+        for c in &self.cohortes {
+            let clave = format!("{}-{:02}", c.anio, c.mes);
+            lineas.push(format!("cohorte_altas,{},{}", clave, c.altas));
+            lineas.push(format!("cohorte_activas,{},{}", clave, c.activas));
+            lineas.push(format!(
+                "cohorte_retencion,{},{:.4}",
+                clave,
+                c.tasa_retencion()
+            ));
+        }
+
+        lineas.join("\n") + "\n"
+    }
+
+    fn exportar_csv(&self, path: &String) -> Result<(), ErrorPlataforma> {
+        let Ok(mut f) = File::create(path) else {
+            return Err(ErrorPlataforma::CrearArchivo);
+        };
+
+        let Ok(_) = f.write_all(self.to_csv().as_bytes()) else {
+            return Err(ErrorPlataforma::EscribirArchivo);
+        };
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{Descuento, DescuentoAplicado, DetallePago, Usuario};
+    use super::*;
+
+    fn agregar_suscripcion(
+        stream_rust: &mut StreamingRust,
+        email: &str,
+        metodo_pago: MetodoPago,
+        movimientos: Vec<(TipoMovimiento, Fecha, &str, f64)>,
+    ) {
+        let mut usuario = Usuario::new(
+            "Nombre".to_string(),
+            "Apellido".to_string(),
+            email.to_string(),
+            "2210000000".to_string(),
+            metodo_pago,
+        );
+        usuario.id = stream_rust.usuarios.len() as u32 + 1;
+
+        let catalogo = CatalogoPlanes::default();
+        let mut s = Suscripcion::new(
+            catalogo.get_plan(&TipoSuscripcion::new("Basic")).unwrap(),
+            movimientos.first().unwrap().1.clone(),
+            usuario.id,
+        );
+
+        s.historial = movimientos
+            .into_iter()
+            .map(|(tipo, fecha, plan, costo)| Movimiento {
+                tipo,
+                fecha,
+                tipo_suscripcion: TipoSuscripcion::new(plan),
+                costo,
+            })
+            .collect();
+
+        let ultimo = s.historial.last().unwrap().clone();
+        s.activo = ultimo.tipo != TipoMovimiento::Baja;
+        s.tipo_suscripcion = ultimo.tipo_suscripcion.clone();
+        s.costo = ultimo.costo;
+        s.duracion = catalogo
+            .get_plan(&ultimo.tipo_suscripcion)
+            .unwrap()
+            .get_duracion();
+
+        stream_rust.usuarios.insert(usuario.id, usuario);
+        stream_rust.suscripciones.push(s);
+    }
+
+    fn crear_plataforma() -> StreamingRust {
+        let mut stream_rust =
+            StreamingRust::new(Default::default(), Default::default(), Default::default());
+
+        // Alta en enero, upgrade en febrero, sigue activa
+        agregar_suscripcion(
+            &mut stream_rust,
+            "a@gmail.com",
+            MetodoPago::Efectivo,
+            vec![
+                (TipoMovimiento::Alta, Fecha::new(10, 1, 2024), "Basic", 50.0),
+                (
+                    TipoMovimiento::Upgrade,
+                    Fecha::new(10, 2, 2024),
+                    "Classic",
+                    80.0,
+                ),
+            ],
+        );
+
+        // Alta en enero, baja en marzo
+        agregar_suscripcion(
+            &mut stream_rust,
+            "b@gmail.com",
+            MetodoPago::Cripto(DetallePago::default()),
+            vec![
+                (
+                    TipoMovimiento::Alta,
+                    Fecha::new(20, 1, 2024),
+                    "Classic",
+                    80.0,
+                ),
+                (
+                    TipoMovimiento::Baja,
+                    Fecha::new(19, 3, 2024),
+                    "Classic",
+                    80.0,
+                ),
+            ],
+        );
+
+        // Alta en febrero, downgrade en abril
+        agregar_suscripcion(
+            &mut stream_rust,
+            "c@gmail.com",
+            MetodoPago::Efectivo,
+            vec![
+                (TipoMovimiento::Alta, Fecha::new(1, 2, 2024), "Super", 100.0),
+                (
+                    TipoMovimiento::Downgrade,
+                    Fecha::new(1, 4, 2024),
+                    "Classic",
+                    80.0,
+                ),
+            ],
+        );
+
+        // Alta y baja previas al rango analizado
+        agregar_suscripcion(
+            &mut stream_rust,
+            "d@gmail.com",
+            MetodoPago::MercadoPago(DetallePago::default()),
+            vec![
+                (TipoMovimiento::Alta, Fecha::new(1, 6, 2023), "Basic", 50.0),
+                (TipoMovimiento::Baja, Fecha::new(1, 7, 2023), "Basic", 50.0),
+            ],
+        );
+
+        stream_rust
+    }
+
+    #[test]
+    fn test_distribuciones() {
+        let stream_rust = crear_plataforma();
+        let rango = RangoFechas::new(Fecha::new(1, 1, 2024), Fecha::new(30, 4, 2024));
+
+        let metodos = stream_rust.distribucion_metodos_pago(&rango);
+        assert_eq!(metodos.get(&MetodoPago::Efectivo), Some(&2));
+        assert_eq!(
+            metodos.get(&MetodoPago::Cripto(Default::default())),
+            Some(&1)
+        );
+        assert!(!metodos.contains_key(&MetodoPago::MercadoPago(Default::default())));
+
+        // Se consideran empates: Classic es el ultimo plan de las 3 suscripciones
+
+        let tipos = stream_rust.distribucion_tipos_suscripcion(&rango);
+        assert_eq!(tipos.len(), 1);
+        assert_eq!(tipos.get(&TipoSuscripcion::new("Classic")), Some(&3));
+
+        // Rango acotado a enero
+
+        let enero = RangoFechas::new(Fecha::new(1, 1, 2024), Fecha::new(31, 1, 2024));
+        let tipos = stream_rust.distribucion_tipos_suscripcion(&enero);
+        assert_eq!(tipos.get(&TipoSuscripcion::new("Basic")), Some(&1));
+        assert_eq!(tipos.get(&TipoSuscripcion::new("Classic")), Some(&1));
+        assert!(!tipos.contains_key(&TipoSuscripcion::new("Super")));
+    }
+
+    #[test]
+    fn test_metricas() {
+        let stream_rust = crear_plataforma();
+        let rango = RangoFechas::new(Fecha::new(1, 1, 2024), Fecha::new(30, 4, 2024));
+
+        assert_eq!(
+            stream_rust.cantidad_movimientos(&rango, TipoMovimiento::Upgrade),
+            1
+        );
+        assert_eq!(
+            stream_rust.cantidad_movimientos(&rango, TipoMovimiento::Downgrade),
+            1
+        );
+
+        // 1 baja sobre 3 suscripciones activas en el rango
+
+        assert!((stream_rust.tasa_churn(&rango) - 1.0 / 3.0).abs() < 1e-9);
+
+        // Activas al 30/04: a (Classic) y c (Classic), 80 cada 6 meses
+
+        assert!((stream_rust.mrr(&rango) - 160.0 / 6.0).abs() < 1e-9);
+        assert!((stream_rust.arr(&rango) - 320.0).abs() < 1e-9);
+
+        // Al 15/03 b seguia activa y c tenia Super (100 cada 12 meses)
+
+        let marzo = RangoFechas::new(Fecha::new(1, 3, 2024), Fecha::new(15, 3, 2024));
+        assert!((stream_rust.mrr(&marzo) - 35.0).abs() < 1e-9);
+        assert_eq!(stream_rust.tasa_churn(&marzo), 0.0);
+
+        // a: 111 dias, b: 59 dias, c: 89 dias
+
+        assert!((stream_rust.vida_promedio_suscripcion(&rango) - 259.0 / 3.0).abs() < 1e-9);
+
+        // Rango sin suscripciones
+
+        let vacio = RangoFechas::new(Fecha::new(1, 1, 2020), Fecha::new(31, 12, 2020));
+        assert_eq!(stream_rust.tasa_churn(&vacio), 0.0);
+        assert_eq!(stream_rust.mrr(&vacio), 0.0);
+        assert_eq!(stream_rust.vida_promedio_suscripcion(&vacio), 0.0);
+    }
+
+    #[test]
+    fn test_mrr_con_promociones() {
+        let mut stream_rust = crear_plataforma();
+        let rango = RangoFechas::new(Fecha::new(1, 1, 2024), Fecha::new(30, 4, 2024));
+
+        // a: 50% de descuento vigente, c: 20 de credito a consumir en el proximo ciclo
+
+        stream_rust.suscripciones[0].descuento = Some(DescuentoAplicado {
+            descuento: Descuento::Porcentaje(50.0),
+            ciclos_restantes: 1,
+        });
+        stream_rust.suscripciones[2].creditos = 20.0;
+        assert!((stream_rust.mrr(&rango) - (40.0 + 60.0) / 6.0).abs() < 1e-9);
+
+        // Durante la prueba gratuita no hay ingreso, tampoco en planes anteriores al actual
+
+        stream_rust.suscripciones[2].fin_prueba = Some(Fecha::new(1, 5, 2024));
+        assert!((stream_rust.mrr(&rango) - 40.0 / 6.0).abs() < 1e-9);
+
+        // Al 15/03 a ya tenia su plan actual con descuento, b pagaba completo y c estaba en prueba
+
+        let marzo = RangoFechas::new(Fecha::new(1, 3, 2024), Fecha::new(15, 3, 2024));
+        assert!((stream_rust.mrr(&marzo) - (40.0 + 80.0) / 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cohortes() {
+        let stream_rust = crear_plataforma();
+        let rango = RangoFechas::new(Fecha::new(1, 1, 2023), Fecha::new(30, 4, 2024));
+
+        let cohortes = stream_rust.retencion_cohortes(&rango);
+
+        assert_eq!(
+            cohortes,
+            vec![
+                Cohorte {
+                    anio: 2023,
+                    mes: 6,
+                    altas: 1,
+                    activas: 0
+                },
+                Cohorte {
+                    anio: 2024,
+                    mes: 1,
+                    altas: 2,
+                    activas: 1
+                },
+                Cohorte {
+                    anio: 2024,
+                    mes: 2,
+                    altas: 1,
+                    activas: 1
+                },
+            ]
+        );
+        assert_eq!(cohortes.get(1).unwrap().tasa_retencion(), 0.5);
+    }
+
+    #[test]
+    fn test_dias_activa_y_archivos_previos() {
+        let mut stream_rust = crear_plataforma();
+
+        // La baja finaliza el periodo activo aunque la fecha consultada sea posterior
+
+        let s = stream_rust
+            .get_suscripcion(&"b@gmail.com".to_string())
+            .unwrap();
+        assert_eq!(s.dias_activa(&Fecha::new(30, 4, 2024)), 59);
+        assert_eq!(s.dias_activa(&Fecha::new(30, 1, 2024)), 10);
+        assert!(s.get_estado(&Fecha::new(20, 3, 2024)).is_none());
+
+        // Suscripcion sin historial (archivo generado antes de registrar movimientos)
+
+        agregar_suscripcion(
+            &mut stream_rust,
+            "f@gmail.com",
+            MetodoPago::Efectivo,
+            vec![(TipoMovimiento::Alta, Fecha::new(5, 3, 2024), "Basic", 50.0)],
+        );
+        let previa = stream_rust.suscripciones.last_mut().unwrap();
+        previa.historial.clear();
+
+        assert_eq!(previa.get_fecha_alta(), Some(Fecha::new(5, 3, 2024)));
+        assert_eq!(
+            previa.get_estado(&Fecha::new(6, 3, 2024)).unwrap().costo,
+            50.0
+        );
+
+        // Las fechas no validas del archivo se ignoran en lugar de interrumpir el reporte
+
+        let no_valida =
+            || -> Fecha { serde_json::from_str(r#"{"dia":30,"mes":2,"anio":2024}"#).unwrap() };
+        previa.fecha_inicio = no_valida();
+        assert!(previa.get_fecha_alta().is_none());
+
+        let s = stream_rust.suscripciones.first_mut().unwrap();
+        s.historial[1].fecha = no_valida();
+        assert_eq!(s.get_movimientos().len(), 1);
+
+        let rango = RangoFechas::new(Fecha::new(1, 1, 2024), Fecha::new(30, 4, 2024));
+        let reporte = stream_rust.generar_reporte(rango);
+        assert_eq!(reporte.upgrades, 0);
+        assert_eq!(reporte.cohortes.len(), 2);
+    }
+
+    #[test]
+    fn test_exportar_csv() {
+        let stream_rust = crear_plataforma();
+        let reporte = stream_rust.generar_reporte(RangoFechas::new(
+            Fecha::new(1, 1, 2024),
+            Fecha::new(30, 4, 2024),
+        ));
+
+        let csv = reporte.to_csv();
+        let lineas: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lineas.first(), Some(&"seccion,clave,valor"));
+        assert!(lineas.contains(&"rango,desde,01/01/2024"));
+        assert!(lineas.contains(&"metodo_pago,Efectivo,2"));
+        assert!(lineas.contains(&"tipo_suscripcion,Classic,3"));
+        assert!(lineas.contains(&"resumen,mrr,26.67"));
+        assert!(lineas.contains(&"resumen,upgrades,1"));
+        assert!(lineas.contains(&"cohorte_retencion,2024-01,0.5000"));
+
+        let path = "test_files/analiticas1.csv".to_string();
+        assert!(reporte.exportar_csv(&path).is_ok());
+
+        let mut buf = String::new();
+        File::open(&path).unwrap().read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, csv);
+
+        assert_eq!(
+            reporte.exportar_csv(&"/".to_string()).unwrap_err(),
+            ErrorPlataforma::CrearArchivo
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn test_rango_invalido() {
+        RangoFechas::new(Fecha::new(2, 1, 2024), Fecha::new(1, 1, 2024));
+    }
+}